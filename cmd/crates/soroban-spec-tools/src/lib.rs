@@ -711,6 +711,153 @@ impl Spec {
     }
 }
 
+impl Spec {
+    /// Whether `val` is a value of type `type_`, in which case it can be converted with
+    /// [`Spec::xdr_to_json`]
+    pub fn is_of_type(&self, val: &ScVal, type_: &ScType) -> bool {
+        match (val, type_) {
+            (ScVal::Void, ScType::Val | ScType::Void | ScType::Option(_))
+            | (ScVal::Map(None) | ScVal::Vec(None), ScType::Option(_))
+            | (ScVal::Bool(_), ScType::Bool)
+            | (ScVal::String(_), ScType::String)
+            | (ScVal::Symbol(_), ScType::Symbol)
+            | (ScVal::U64(_), ScType::U64)
+            | (ScVal::I64(_), ScType::I64)
+            | (ScVal::U32(_), ScType::U32)
+            | (ScVal::I32(_), ScType::I32)
+            | (ScVal::U128(_), ScType::U128)
+            | (ScVal::I128(_), ScType::I128)
+            | (ScVal::U256(_), ScType::U256)
+            | (ScVal::I256(_), ScType::I256)
+            | (ScVal::Duration(_), ScType::Duration)
+            | (ScVal::Timepoint(_), ScType::Timepoint)
+            | (ScVal::Address(_), ScType::Address)
+            | (ScVal::Bytes(_), ScType::Bytes) => true,
+            (ScVal::Bytes(bytes), ScType::BytesN(bytes_n)) => bytes.len() == bytes_n.n as usize,
+            (val, ScType::Result(inner)) => self.is_of_type(val, &inner.ok_type),
+            (val, ScType::Option(inner)) => self.is_of_type(val, &inner.value_type),
+            (ScVal::Vec(Some(vec_)), ScType::Vec(inner)) => {
+                vec_.iter().all(|v| self.is_of_type(v, &inner.element_type))
+            }
+            (ScVal::Vec(Some(vec_)), ScType::Tuple(inner)) => {
+                vec_.len() == inner.value_types.len()
+                    && vec_
+                        .iter()
+                        .zip(inner.value_types.iter())
+                        .all(|(v, t)| self.is_of_type(v, t))
+            }
+            (ScVal::Map(Some(map)), ScType::Map(inner)) => {
+                map.iter().all(|ScMapEntry { key, val }| {
+                    self.is_of_type(key, &inner.key_type) && self.is_of_type(val, &inner.value_type)
+                })
+            }
+            (ScVal::Map(Some(map)), ScType::Set(inner)) => map
+                .iter()
+                .all(|ScMapEntry { key, .. }| self.is_of_type(key, &inner.element_type)),
+            (val, ScType::Udt(ScSpecTypeUdt { name })) => self
+                .find(&name.to_string_lossy())
+                .is_ok_and(|udt| self.is_of_udt(val, udt)),
+            _ => false,
+        }
+    }
+
+    fn is_of_udt(&self, val: &ScVal, udt: &ScSpecEntry) -> bool {
+        match (val, udt) {
+            (ScVal::Map(Some(map)), ScSpecEntry::UdtStructV0(strukt)) => {
+                map.len() == strukt.fields.len()
+                    && map.iter().zip(strukt.fields.iter()).all(|(entry, field)| {
+                        let ScVal::Symbol(name) = &entry.key else {
+                            return false;
+                        };
+                        name.as_vec() == field.name.as_vec()
+                            && self.is_of_type(&entry.val, &field.type_)
+                    })
+            }
+            (ScVal::Vec(Some(vec_)), ScSpecEntry::UdtStructV0(strukt)) => {
+                vec_.len() == strukt.fields.len()
+                    && vec_
+                        .iter()
+                        .zip(strukt.fields.iter())
+                        .all(|(v, field)| self.is_of_type(v, &field.type_))
+            }
+            (ScVal::Vec(Some(vec_)), ScSpecEntry::UdtUnionV0(union)) => {
+                let Some(ScVal::Symbol(case_name)) = vec_.first() else {
+                    return false;
+                };
+                union.cases.iter().any(|case| match case {
+                    ScSpecUdtUnionCaseV0::VoidV0(v) => {
+                        v.name.as_vec() == case_name.as_vec() && vec_.len() == 1
+                    }
+                    ScSpecUdtUnionCaseV0::TupleV0(v) => {
+                        v.name.as_vec() == case_name.as_vec()
+                            && vec_.len() == 2
+                            && v.type_.len() == 1
+                            && self.is_of_type(&vec_[1], &v.type_[0])
+                    }
+                })
+            }
+            (ScVal::U32(value), ScSpecEntry::UdtEnumV0(enum_)) => {
+                enum_.cases.iter().any(|case| case.value == *value)
+            }
+            _ => false,
+        }
+    }
+
+    /// First struct or union of the spec `val` is a value of
+    pub fn infer_type(&self, val: &ScVal) -> Option<ScType> {
+        self.0.as_ref()?.iter().find_map(|entry| {
+            let name = match entry {
+                ScSpecEntry::UdtStructV0(ScSpecUdtStructV0 { name, .. })
+                | ScSpecEntry::UdtUnionV0(ScSpecUdtUnionV0 { name, .. }) => name,
+                _ => return None,
+            };
+            self.is_of_udt(val, entry)
+                .then(|| ScType::Udt(ScSpecTypeUdt { name: name.clone() }))
+        })
+    }
+
+    /// Convert a value of unknown type, such as a value read from contract storage, to JSON.
+    /// Values of one of the structs or unions of the spec are converted as that type, with field
+    /// and case names; the items of other vectors and maps are converted the same way.
+    ///
+    /// # Errors
+    ///
+    /// Might return an error
+    pub fn xdr_to_json_inferred(&self, val: &ScVal) -> Result<Value, Error> {
+        if let Some(type_) = self.infer_type(val) {
+            return self.xdr_to_json(val, &type_);
+        }
+        Ok(match val {
+            ScVal::Vec(Some(vec_)) => Value::Array(
+                vec_.iter()
+                    .map(|v| self.xdr_to_json_inferred(v))
+                    .collect::<Result<Vec<_>, Error>>()?,
+            ),
+            ScVal::Map(Some(map)) => Value::Object(
+                map.iter()
+                    .map(|ScMapEntry { key, val }| {
+                        Ok((to_string(key)?, self.xdr_to_json_inferred(val)?))
+                    })
+                    .collect::<Result<serde_json::Map<String, _>, Error>>()?,
+            ),
+            _ => to_json(val)?,
+        })
+    }
+
+    /// Like [`Spec::xdr_to_json_inferred`], but top-level symbols are returned as is rather than
+    /// as JSON strings, as in [`to_string`]
+    ///
+    /// # Errors
+    ///
+    /// Might return an error
+    pub fn to_string_inferred(&self, val: &ScVal) -> Result<String, Error> {
+        Ok(match val {
+            ScVal::Symbol(_) => to_string(val)?,
+            _ => self.xdr_to_json_inferred(val)?.to_string(),
+        })
+    }
+}
+
 /// # Errors
 ///
 /// Might return an error
@@ -1331,7 +1478,7 @@ impl Spec {
 mod tests {
     use super::*;
 
-    use stellar_xdr::ScSpecTypeBytesN;
+    use stellar_xdr::{ScSpecTypeBytesN, ScSpecUdtStructFieldV0};
 
     #[test]
     fn from_json_primitives_bytesn() {
@@ -1435,4 +1582,84 @@ mod tests {
             Err(e) => panic!("Unexpected error: {e}"),
         }
     }
+
+    #[test]
+    fn test_xdr_to_json_inferred() {
+        let spec = Spec::new(vec![
+            ScSpecEntry::UdtStructV0(ScSpecUdtStructV0 {
+                doc: StringM::default(),
+                lib: StringM::default(),
+                name: "Point".try_into().unwrap(),
+                fields: ["x", "y"]
+                    .into_iter()
+                    .map(|name| ScSpecUdtStructFieldV0 {
+                        doc: StringM::default(),
+                        name: name.try_into().unwrap(),
+                        type_: ScType::U32,
+                    })
+                    .collect::<Vec<_>>()
+                    .try_into()
+                    .unwrap(),
+            }),
+            ScSpecEntry::UdtUnionV0(ScSpecUdtUnionV0 {
+                doc: StringM::default(),
+                lib: StringM::default(),
+                name: "DataKey".try_into().unwrap(),
+                cases: vec![
+                    ScSpecUdtUnionCaseV0::VoidV0(ScSpecUdtUnionCaseVoidV0 {
+                        doc: StringM::default(),
+                        name: "Counter".try_into().unwrap(),
+                    }),
+                    ScSpecUdtUnionCaseV0::TupleV0(ScSpecUdtUnionCaseTupleV0 {
+                        doc: StringM::default(),
+                        name: "Position".try_into().unwrap(),
+                        type_: vec![ScType::U32].try_into().unwrap(),
+                    }),
+                ]
+                .try_into()
+                .unwrap(),
+            }),
+        ]);
+        let symbol = |s: &str| ScVal::Symbol(ScSymbol(s.try_into().unwrap()));
+        let vec_ = |v: Vec<ScVal>| ScVal::Vec(Some(ScVec(v.try_into().unwrap())));
+
+        let point = ScVal::Map(Some(ScMap(
+            vec![
+                ScMapEntry {
+                    key: symbol("x"),
+                    val: ScVal::U32(1),
+                },
+                ScMapEntry {
+                    key: symbol("y"),
+                    val: ScVal::U32(2),
+                },
+            ]
+            .try_into()
+            .unwrap(),
+        )));
+        assert_eq!(
+            spec.infer_type(&point),
+            Some(ScType::Udt(ScSpecTypeUdt {
+                name: "Point".try_into().unwrap()
+            }))
+        );
+        assert_eq!(
+            spec.xdr_to_json_inferred(&point).unwrap(),
+            json!({ "x": 1, "y": 2 })
+        );
+
+        let key = vec_(vec![symbol("Position"), ScVal::U32(7)]);
+        assert_eq!(
+            spec.xdr_to_json_inferred(&key).unwrap(),
+            json!({ "Position": 7 })
+        );
+
+        // Values that aren't of any type of the spec are converted as is, decoding their items
+        let keys = vec_(vec![vec_(vec![symbol("Counter")]), symbol("Other")]);
+        assert_eq!(spec.infer_type(&keys), None);
+        assert_eq!(
+            spec.xdr_to_json_inferred(&keys).unwrap(),
+            json!(["Counter", "Other"])
+        );
+    }
 }
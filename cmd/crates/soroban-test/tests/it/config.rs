@@ -267,3 +267,111 @@ fn test_hd_path(sandbox: &TestEnv, hd_path: usize) {
     let test_address = sandbox.test_address(hd_path);
     assert_eq!(pub_key, test_address);
 }
+
+#[test]
+fn migrate_identity() {
+    let sandbox = TestEnv::default();
+    let name = add_test_id(sandbox.dir());
+    let address = |sandbox: &TestEnv| {
+        sandbox
+            .new_assert_cmd("config")
+            .arg("identity")
+            .arg("address")
+            .arg(&name)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone()
+    };
+    let plaintext_address = address(&sandbox);
+
+    sandbox
+        .new_assert_cmd("config")
+        .env("SOROBAN_IDENTITY_PASSPHRASE", "")
+        .arg("identity")
+        .arg("migrate")
+        .assert()
+        .failure();
+
+    sandbox
+        .new_assert_cmd("config")
+        .env("SOROBAN_IDENTITY_PASSPHRASE", "correct horse")
+        .arg("identity")
+        .arg("migrate")
+        .assert()
+        .success()
+        .stdout(format!("{name}\n"));
+
+    let file_contents =
+        fs::read_to_string(sandbox.dir().join(format!(".soroban/identity/{name}.toml"))).unwrap();
+    assert!(file_contents.contains("ciphertext"));
+    assert!(!file_contents.contains("SBGWSG6BTNCKCOB3DIFBGCVMUPQFYPA2G4O34RMTB343OYPXU5DJDVMN"));
+
+    // The default address is stored in the clear
+    assert_eq!(address(&sandbox), plaintext_address);
+
+    sandbox
+        .new_assert_cmd("config")
+        .env("SOROBAN_IDENTITY_PASSPHRASE", "correct horse")
+        .arg("identity")
+        .arg("show")
+        .arg(&name)
+        .assert()
+        .success()
+        .stdout("SBGWSG6BTNCKCOB3DIFBGCVMUPQFYPA2G4O34RMTB343OYPXU5DJDVMN\n");
+
+    sandbox
+        .new_assert_cmd("config")
+        .env("SOROBAN_IDENTITY_PASSPHRASE", "wrong")
+        .arg("identity")
+        .arg("show")
+        .arg(&name)
+        .assert()
+        .failure();
+
+    // Already encrypted identities are left alone
+    sandbox
+        .new_assert_cmd("config")
+        .env("SOROBAN_IDENTITY_PASSPHRASE", "other")
+        .arg("identity")
+        .arg("migrate")
+        .assert()
+        .success()
+        .stdout("");
+}
+
+#[test]
+fn add_external_signer_identity() {
+    let sandbox = TestEnv::default();
+    let public_key = sandbox.test_address(0);
+    sandbox
+        .new_assert_cmd("config")
+        .arg("identity")
+        .arg("add")
+        .arg("external")
+        .arg("--signer-command")
+        .arg("my-signer --flag")
+        .arg("--public-key")
+        .arg(&public_key)
+        .assert()
+        .success()
+        .stdout("");
+
+    let file_contents =
+        fs::read_to_string(sandbox.dir().join(".soroban/identity/external.toml")).unwrap();
+    assert_eq!(
+        file_contents,
+        format!("public_key = \"{public_key}\"\ncommand = \"my-signer --flag\"\n")
+    );
+
+    // The address is known without running the signer
+    sandbox
+        .new_assert_cmd("config")
+        .arg("identity")
+        .arg("address")
+        .arg("external")
+        .assert()
+        .success()
+        .stdout(format!("{public_key}\n"));
+}
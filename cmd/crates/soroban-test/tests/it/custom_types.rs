@@ -195,7 +195,8 @@ fn number_arg_return_err() {
                 "--u32_=2",
             ])
             .unwrap_err();
-        if let commands::contract::invoke::Error::ContractInvoke(name, doc) = &res {
+        if let commands::contract::invoke::Error::Failed(report) = &res {
+            let (name, doc) = report.contract_error.as_ref().unwrap();
             assert_eq!(name, "OhNo");
             assert_eq!(doc, "Unknown error has occured");
         };
@@ -1,6 +1,7 @@
 use soroban_cli::commands::{
     config::identity,
     contract::{self, fetch},
+    global, sandbox,
 };
 use soroban_test::TestEnv;
 
@@ -89,6 +90,127 @@ fn invoke_hello_world() {
         .success();
 }
 
+#[test]
+fn invoke_hello_world_sim_only() {
+    let sandbox = TestEnv::default();
+    sandbox
+        .new_assert_cmd("contract")
+        .arg("invoke")
+        .arg("--sim-only")
+        .arg("--id=1")
+        .arg("--wasm")
+        .arg(HELLO_WORLD.path())
+        .arg("--")
+        .arg("hello")
+        .arg("--world=world")
+        .assert()
+        .stdout("[\"Hello\",\"world\"]\n")
+        .stderr(predicates::str::contains("footprint: {"))
+        .stderr(predicates::str::contains("auth: []"))
+        .stderr(predicates::str::contains("cpu_insns: "))
+        .stderr(predicates::str::contains("mem_bytes: "))
+        .success();
+    assert!(!sandbox.dir().join(".soroban/ledger.json").exists());
+}
+
+#[test]
+fn invoke_hello_world_json() {
+    let sandbox = TestEnv::default();
+    let output = assert_cmd::Command::cargo_bin("soroban")
+        .unwrap()
+        .current_dir(sandbox.dir())
+        .args([
+            "-q",
+            "--output=json",
+            "contract",
+            "invoke",
+            "--id=1",
+            "--wasm",
+        ])
+        .arg(HELLO_WORLD.path())
+        .args(["--", "hello", "--world=world"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(json["result"], serde_json::json!(["Hello", "world"]));
+    assert!(!json["footprint"]["read_only"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+    assert!(json["events"].is_array());
+}
+
+#[test]
+fn invoke_build_only_requires_network() {
+    let sandbox = TestEnv::default();
+    sandbox
+        .new_assert_cmd("contract")
+        .arg("invoke")
+        .arg("--build-only")
+        .arg("--id=1")
+        .arg("--wasm")
+        .arg(HELLO_WORLD.path())
+        .arg("--")
+        .arg("hello")
+        .arg("--world=world")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "`--build-only` requires a network",
+        ));
+    assert!(!sandbox.dir().join(".soroban/ledger.json").exists());
+}
+
+#[test]
+fn invoke_hello_world_with_args_from_files() {
+    let sandbox = TestEnv::default();
+    let world = sandbox.dir().join("world.txt");
+    std::fs::write(&world, "world\n").unwrap();
+    sandbox
+        .new_assert_cmd("contract")
+        .arg("invoke")
+        .arg("--id=1")
+        .arg("--wasm")
+        .arg(HELLO_WORLD.path())
+        .arg("--")
+        .arg("hello")
+        .arg("--world-file-path")
+        .arg(&world)
+        .assert()
+        .stdout("[\"Hello\",\"world\"]\n")
+        .success();
+
+    let args = sandbox.dir().join("args.json");
+    std::fs::write(&args, r#"{ "world": "file" }"#).unwrap();
+    sandbox
+        .new_assert_cmd("contract")
+        .args(["invoke", "--id=1", "--args-file"])
+        .arg(&args)
+        .args(["--", "hello"])
+        .assert()
+        .stdout("[\"Hello\",\"file\"]\n")
+        .success();
+    sandbox
+        .new_assert_cmd("contract")
+        .args(["invoke", "--id=1", "--args-file=-", "--", "hello"])
+        .write_stdin(r#"{ "world": "stdin" }"#)
+        .assert()
+        .stdout("[\"Hello\",\"stdin\"]\n")
+        .success();
+    sandbox
+        .new_assert_cmd("contract")
+        .args(["invoke", "--id=1", "--args-file=-", "--", "hello"])
+        .write_stdin(r#"{ "planet": "mars" }"#)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "function hello has no argument planet",
+        ));
+}
+
 #[test]
 fn invoke_hello_world_with_lib() {
     TestEnv::with_default(|e| {
@@ -151,7 +273,7 @@ fn invoke_auth_with_identity() {
 
     sandbox
         .cmd::<identity::generate::Cmd>("test -d ")
-        .run()
+        .run(&global::Args::default())
         .unwrap();
     sandbox
         .new_assert_cmd("contract")
@@ -204,7 +326,7 @@ fn invoke_auth_with_different_test_account_fail() {
     assert!(res.is_err());
     if let Err(e) = res {
         assert!(
-            matches!(e, contract::invoke::Error::Host(_)),
+            matches!(e, contract::invoke::Error::Failed(_)),
             "Expected host error got {e:?}"
         );
     };
@@ -280,6 +402,281 @@ async fn fetch() {
         "--out-file",
         f.to_str().unwrap(),
     ]);
-    cmd.run().await.unwrap();
+    cmd.run(&global::Args::default()).await.unwrap();
     assert!(f.exists());
 }
+
+#[test]
+fn sandbox_ledger_time_travel() {
+    let e = TestEnv::default();
+    let cmd = e.cmd::<sandbox::ledger::Cmd>("--ledger-sequence 100 --advance-time 3600");
+    cmd.run(&global::Args::default()).unwrap();
+    let state = cmd
+        .ledger_file
+        .read(&cmd.locator.config_dir().unwrap())
+        .unwrap();
+    assert_eq!(state.sequence_number, 100);
+    assert_eq!(state.timestamp, 3600);
+}
+
+#[test]
+fn invoke_archived_contract_then_restore() {
+    let sandbox = TestEnv::default();
+    sandbox
+        .new_assert_cmd("contract")
+        .arg("deploy")
+        .arg("--wasm")
+        .arg(HELLO_WORLD.path())
+        .arg("--id=1")
+        .assert()
+        .success();
+    sandbox
+        .new_assert_cmd("sandbox")
+        .arg("ledger")
+        .arg("--advance-ledgers=5000")
+        .assert()
+        .success();
+
+    sandbox
+        .new_assert_cmd("contract")
+        .arg("invoke")
+        .arg("--id=1")
+        .arg("--")
+        .arg("hello")
+        .arg("--world=world")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("is archived"));
+
+    // Restore the contract instance, then its code
+    sandbox
+        .new_assert_cmd("contract")
+        .arg("restore")
+        .arg("--id=1")
+        .arg("--key-xdr=AAAAFA==")
+        .assert()
+        .success();
+    sandbox
+        .new_assert_cmd("contract")
+        .arg("restore")
+        .arg("--wasm")
+        .arg(HELLO_WORLD.path())
+        .assert()
+        .success();
+
+    sandbox
+        .new_assert_cmd("contract")
+        .arg("invoke")
+        .arg("--id=1")
+        .arg("--")
+        .arg("hello")
+        .arg("--world=world")
+        .assert()
+        .success()
+        .stdout("[\"Hello\",\"world\"]\n");
+}
+
+#[test]
+fn bump_and_restore_all_entries_of_a_contract() {
+    let sandbox = TestEnv::default();
+    sandbox
+        .new_assert_cmd("contract")
+        .arg("deploy")
+        .arg("--wasm")
+        .arg(HELLO_WORLD.path())
+        .arg("--id=1")
+        .assert()
+        .success();
+    sandbox
+        .new_assert_cmd("contract")
+        .args(["invoke", "--id=1", "--", "inc"])
+        .assert()
+        .success();
+    sandbox
+        .new_assert_cmd("contract")
+        .args(["bump", "--id=1", "--all", "--ledgers-to-expire=10"])
+        .assert()
+        .success()
+        .stdout(predicates::str::starts_with("New expiration ledger: "));
+
+    sandbox
+        .new_assert_cmd("sandbox")
+        .arg("ledger")
+        .arg("--advance-ledgers=5000")
+        .assert()
+        .success();
+    sandbox
+        .new_assert_cmd("contract")
+        .args(["restore", "--id=1", "--all"])
+        .assert()
+        .success();
+    // The code of the contract is restored along with its storage
+    sandbox
+        .new_assert_cmd("contract")
+        .args(["invoke", "--id=1", "--", "hello", "--world=world"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn sandbox_snapshot_save_diff_restore() {
+    let sandbox = TestEnv::default();
+    sandbox
+        .new_assert_cmd("contract")
+        .arg("deploy")
+        .arg("--wasm")
+        .arg(HELLO_WORLD.path())
+        .arg("--id=1")
+        .assert()
+        .success();
+    sandbox
+        .new_assert_cmd("sandbox")
+        .args(["snapshot", "save", "deployed"])
+        .assert()
+        .success();
+    sandbox
+        .new_assert_cmd("sandbox")
+        .args(["snapshot", "save", "deployed"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("already exists"));
+    sandbox
+        .new_assert_cmd("sandbox")
+        .args(["snapshot", "ls"])
+        .assert()
+        .success()
+        .stdout("deployed\n");
+
+    for _ in 0..2 {
+        sandbox
+            .new_assert_cmd("contract")
+            .args(["invoke", "--id=1", "--", "inc"])
+            .assert()
+            .success();
+    }
+    sandbox
+        .new_assert_cmd("sandbox")
+        .args(["snapshot", "diff", "deployed"])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            "+ Temporary entry COUNTER of contract",
+        ))
+        .stdout(predicates::str::contains(": 2, expires after ledger"));
+
+    sandbox
+        .new_assert_cmd("sandbox")
+        .args(["snapshot", "restore", "deployed"])
+        .assert()
+        .success();
+    sandbox
+        .new_assert_cmd("sandbox")
+        .args(["snapshot", "diff", "deployed"])
+        .assert()
+        .success()
+        .stdout("");
+}
+
+#[test]
+fn contract_storage_lists_entries() {
+    let sandbox = TestEnv::default();
+    sandbox
+        .new_assert_cmd("contract")
+        .arg("deploy")
+        .arg("--wasm")
+        .arg(HELLO_WORLD.path())
+        .arg("--id=1")
+        .assert()
+        .success();
+    sandbox
+        .new_assert_cmd("contract")
+        .args(["invoke", "--id=1", "--", "inc"])
+        .assert()
+        .success();
+    sandbox
+        .new_assert_cmd("contract")
+        .args(["storage", "--id=1"])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            "temporary COUNTER: 1, expires after ledger",
+        ));
+}
+
+#[test]
+fn contract_read_decodes_entries_with_the_spec() {
+    let sandbox = TestEnv::default();
+    sandbox
+        .new_assert_cmd("contract")
+        .arg("deploy")
+        .arg("--wasm")
+        .arg(HELLO_WORLD.path())
+        .arg("--id=1")
+        .assert()
+        .success();
+    sandbox
+        .new_assert_cmd("contract")
+        .args(["invoke", "--id=1", "--", "inc"])
+        .assert()
+        .success();
+    sandbox
+        .new_assert_cmd("contract")
+        .args(["read", "--id=1", "--key=COUNTER", "--durability=temporary"])
+        .assert()
+        .success()
+        .stdout("COUNTER,1\n");
+    sandbox
+        .new_assert_cmd("contract")
+        .args(["read", "--id=1", "--key=COUNTER", "--durability=temporary"])
+        .args(["--output=json", "--wasm"])
+        .arg(HELLO_WORLD.path())
+        .assert()
+        .success()
+        .stdout(predicates::str::ends_with(",1\n"));
+}
+
+#[test]
+fn contract_read_requires_the_spec_for_contract_key_types() {
+    let sandbox = TestEnv::default();
+    sandbox
+        .new_assert_cmd("contract")
+        .args(["read", "--id=1", "--key-type=DataKey", "--key=\"Admin\""])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "cannot get the spec of the contract to parse the key",
+        ));
+}
+
+#[test]
+fn contract_batch_feeds_outputs_to_later_steps() {
+    let sandbox = TestEnv::default();
+    let manifest = sandbox.dir().join("batch.yaml");
+    std::fs::write(
+        &manifest,
+        format!(
+            r#"
+steps:
+  - name: hash
+    install: ["--wasm", "{}"]
+  - name: hello
+    deploy: --wasm-hash ${{hash}} --id 1
+  - invoke: --id ${{hello}} -- hello --world batch
+  - invoke: --id ${{hello}} -- inc
+"#,
+            HELLO_WORLD.path().display()
+        ),
+    )
+    .unwrap();
+    sandbox
+        .new_assert_cmd("contract")
+        .arg("batch")
+        .arg("--manifest")
+        .arg(&manifest)
+        .assert()
+        .success()
+        .stdout(format!(
+            "{}\nCAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAD2KM\n[\"Hello\",\"batch\"]\n\n",
+            HELLO_WORLD.hash().unwrap()
+        ));
+}
@@ -1,17 +1,20 @@
 use crate::{
     commands::HEADING_SANDBOX,
-    rpc::{self, does_topic_match, Event},
+    rpc::{self, Event, EventFilter},
     toid,
 };
 use chrono::{DateTime, NaiveDateTime, Utc};
 use clap::arg;
+use fs2::FileExt;
 use soroban_env_host::{
     events,
     xdr::{self, WriteXdr},
 };
 use soroban_ledger_snapshot::LedgerSnapshot;
 use std::{
-    fs,
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
 };
 
@@ -28,6 +31,18 @@ pub struct Args {
         conflicts_with = "network",
     )]
     pub events_file: Option<PathBuf>,
+    /// Number of ledgers to keep the events of in the events file. Older events are dropped
+    /// from time to time as new ones are added. 0 keeps all events
+    #[arg(
+        long,
+        value_name = "LEDGERS",
+        env = "SOROBAN_EVENTS_RETENTION",
+        default_value = "17280",
+        help_heading = HEADING_SANDBOX,
+        conflicts_with = "rpc_url",
+        conflicts_with = "network",
+    )]
+    pub events_retention: u32,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -46,17 +61,50 @@ pub enum Error {
     InvalidTimestamp { ts: String },
 }
 
+/// The events file stores one event per line, exactly as they'd be returned by an RPC server, so
+/// that new events can be appended without rewriting the file. Files in the previous format, a
+/// single JSON response with all the events, are still read, and converted on the next commit.
 impl Args {
-    /// Returns a list of events from the on-disk event store, which stores events
-    /// exactly as they'd be returned by an RPC server.
+    /// Returns the list of events from the on-disk event store.
     pub fn read(&self, pwd: &Path) -> Result<rpc::GetEventsResponse, Error> {
         let path = self.path(pwd);
-        let reader = std::fs::OpenOptions::new().read(true).open(path)?;
-        Ok(serde_json::from_reader(reader)?)
+        let _lock = Lock::shared(&path)?;
+        let mut events = vec![];
+        let latest_ledger = scan(&path, |event| {
+            events.push(event);
+            true
+        })?;
+        Ok(rpc::GetEventsResponse {
+            events,
+            latest_ledger,
+        })
+    }
+
+    /// Returns the first `count` events after `start_cursor` that match one of `filters`.
+    pub fn query(
+        &self,
+        pwd: &Path,
+        start_cursor: (u64, i32),
+        filters: &[EventFilter],
+        count: usize,
+    ) -> Result<rpc::GetEventsResponse, Error> {
+        let path = self.path(pwd);
+        let _lock = Lock::shared(&path)?;
+        let mut events = vec![];
+        let latest_ledger = scan(&path, |event| {
+            if is_match(&event, &path, start_cursor, filters) {
+                events.push(event);
+            }
+            events.len() < count
+        })?;
+        Ok(rpc::GetEventsResponse {
+            events,
+            latest_ledger,
+        })
     }
 
-    /// Reads the existing event file, appends the new events, and writes it all to
-    /// disk. Note that this almost certainly isn't safe to call in parallel.
+    /// Appends the new events to the event file, and drops the events that are out of the
+    /// retention window. The file is locked while doing so, so this is safe to call in parallel.
     pub fn commit(
         &self,
         new_events: &[events::HostEvent],
@@ -72,14 +120,14 @@ impl Args {
             }
         }
 
-        let mut events: Vec<rpc::Event> = if output_file.exists() {
-            let mut file = fs::OpenOptions::new().read(true).open(&output_file)?;
-            let payload: rpc::GetEventsResponse = serde_json::from_reader(&mut file)?;
-            payload.events
-        } else {
-            vec![]
-        };
+        let _lock = Lock::exclusive(&output_file)?;
+        if output_file.exists() && is_legacy(&mut File::open(&output_file)?)? {
+            let file = File::open(&output_file)?;
+            let payload: rpc::GetEventsResponse = serde_json::from_reader(BufReader::new(file))?;
+            replace(&output_file, &payload.events)?;
+        }
 
+        let mut lines = String::new();
         for (i, event) in new_events.iter().enumerate() {
             let contract_event = &event.event;
             let topic = match &contract_event.body {
@@ -151,24 +199,61 @@ impl Args {
                 },
             };
 
-            events.push(cereal_event);
+            lines.push_str(&serde_json::to_string(&cereal_event)?);
+            lines.push('\n');
         }
 
-        let mut file = std::fs::OpenOptions::new()
+        let mut file = fs::OpenOptions::new()
             .create(true)
-            .write(true)
-            .truncate(true)
+            .read(true)
+            .append(true)
             .open(&output_file)?;
+        // Start on a new line if a previous commit was interrupted halfway through a line
+        if !ends_with_newline(&mut file)? {
+            lines.insert(0, '\n');
+        }
+        // A single write, so readers that don't wait for the lock see whole events
+        file.write_all(lines.as_bytes())?;
+
+        self.compact(&output_file, ledger_info.sequence_number)
+    }
 
-        serde_json::to_writer_pretty(
-            &mut file,
-            &rpc::GetEventsResponse {
-                events,
-                latest_ledger: ledger_info.sequence_number,
-            },
-        )?;
+    /// Drop the events older than the retention window. To avoid rewriting the file on every
+    /// commit, this only happens once the oldest event is a whole window older than the start of
+    /// the window.
+    fn compact(&self, path: &Path, latest_ledger: u32) -> Result<(), Error> {
+        if self.events_retention == 0 {
+            return Ok(());
+        }
+        // The oldest event isn't necessarily the first one, see `scan`
+        let mut oldest_ledger = None;
+        let latest_ledger = scan(path, |event| {
+            if let Ok(ledger) = event.ledger.parse::<u32>() {
+                oldest_ledger =
+                    Some(oldest_ledger.map_or(ledger, |oldest: u32| oldest.min(ledger)));
+            }
+            true
+        })?
+        .max(latest_ledger);
+        let window_start = latest_ledger.saturating_sub(self.events_retention);
+        if oldest_ledger.map_or(true, |oldest| {
+            oldest >= window_start.saturating_sub(self.events_retention)
+        }) {
+            return Ok(());
+        }
 
-        Ok(())
+        let mut events = vec![];
+        scan(path, |event| {
+            if event
+                .ledger
+                .parse::<u32>()
+                .map_or(true, |ledger| ledger >= window_start)
+            {
+                events.push(event);
+            }
+            true
+        })?;
+        replace(path, &events)
     }
 
     pub fn path(&self, pwd: &Path) -> PathBuf {
@@ -178,58 +263,132 @@ impl Args {
             pwd.join("events.json")
         }
     }
+}
 
-    pub fn filter_events(
-        events: &[Event],
-        path: &Path,
-        start_cursor: (u64, i32),
-        contract_ids: &[String],
-        topic_filters: &[String],
-        count: usize,
-    ) -> Vec<Event> {
-        events
-            .iter()
-            .filter(|evt| match evt.parse_cursor() {
-                Ok(event_cursor) => event_cursor > start_cursor,
-                Err(e) => {
-                    eprintln!("error parsing key 'ledger': {e:?}");
-                    eprintln!(
-                        "your sandbox events file ('{path:?}') may be corrupt, consider deleting it",
-                    );
-                    eprintln!("ignoring this event: {evt:#?}");
-
-                    false
+fn is_match(evt: &Event, path: &Path, start_cursor: (u64, i32), filters: &[EventFilter]) -> bool {
+    let is_after_start = match evt.parse_cursor() {
+        Ok(event_cursor) => event_cursor > start_cursor,
+        Err(e) => {
+            eprintln!("error parsing key 'ledger': {e:?}");
+            eprintln!("your sandbox events file ('{path:?}') may be corrupt, consider deleting it");
+            eprintln!("ignoring this event: {evt:#?}");
+
+            false
+        }
+    };
+    // Like the RPC server, no filters means pass everything through,
+    // and otherwise events only have to match one of the filters.
+    is_after_start && (filters.is_empty() || filters.iter().any(|f| f.matches(evt)))
+}
+
+/// Passes the events in the file at `path` to `f` in order, until it returns false, and returns
+/// the latest ledger of the events. Parallel commits can append events out of ledger order, so
+/// the rest of the file is still read to find the latest ledger after `f` returns false.
+fn scan(path: &Path, mut f: impl FnMut(Event) -> bool) -> Result<u32, Error> {
+    let mut file = File::open(path)?;
+    if is_legacy(&mut file)? {
+        let payload: rpc::GetEventsResponse = serde_json::from_reader(BufReader::new(file))?;
+        for event in payload.events {
+            if !f(event) {
+                break;
+            }
+        }
+        return Ok(payload.latest_ledger);
+    }
+
+    let mut latest_ledger = 0;
+    let mut done = false;
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Event>(&line) {
+            Ok(event) => {
+                if let Ok(ledger) = event.ledger.parse::<u32>() {
+                    latest_ledger = latest_ledger.max(ledger);
+                }
+                if !done {
+                    done = !f(event);
                 }
-            })
-            .filter(|evt| {
-                // Contract ID filter(s) are optional, so we should render all
-                // events if they're omitted.
-                contract_ids.is_empty() || contract_ids.iter().any(|id| *id == evt.contract_id)
-            })
-            .filter(|evt| {
-                // Like before, no topic filters means pass everything through.
-                topic_filters.is_empty() ||
-                // Reminder: All of the topic filters are part of a single
-                // filter object, and each one contains segments, so we need to
-                // apply all of them to the given event.
-                topic_filters
-                    .iter()
-                    // quadratic, but both are <= 5 long
-                    .any(|f| {
-                        does_topic_match(
-                            &evt.topic,
-                            // misc. Rust nonsense: make a copy over the given
-                            // split filter, because passing a slice of
-                            // references is too much for this language to
-                            // handle
-                            &f.split(',')
-                            .map(std::string::ToString::to_string)
-                            .collect::<Vec<String>>()
-                        )
-                    })
-            })
-            .take(count)
-            .cloned()
-            .collect()
+            }
+            // Likely a commit that was interrupted or is still being written
+            Err(e) => eprintln!("ignoring an invalid event in the events file ('{path:?}'): {e}"),
+        }
+    }
+    Ok(latest_ledger)
+}
+
+/// Whether the file is in the previous format, a single JSON response with all the events. The
+/// file is rewound afterwards.
+fn is_legacy(file: &mut File) -> Result<bool, Error> {
+    let mut first_line = String::new();
+    BufReader::new(&mut *file).read_line(&mut first_line)?;
+    file.rewind()?;
+    let first_line = first_line.trim();
+    Ok(!first_line.is_empty() && serde_json::from_str::<Event>(first_line).is_err())
+}
+
+fn ends_with_newline(file: &mut File) -> Result<bool, Error> {
+    if file.seek(SeekFrom::End(0))? == 0 {
+        return Ok(true);
+    }
+    file.seek(SeekFrom::End(-1))?;
+    let mut last = [0];
+    file.read_exact(&mut last)?;
+    Ok(last[0] == b'\n')
+}
+
+/// Replace the contents of the file at `path` with `events`, at once so that readers never see a
+/// partially written file
+fn replace(path: &Path, events: &[Event]) -> Result<(), Error> {
+    let tmp = path.with_extension("tmp");
+    let mut file = io::BufWriter::new(File::create(&tmp)?);
+    for event in events {
+        serde_json::to_writer(&mut file, event)?;
+        file.write_all(b"\n")?;
+    }
+    file.into_inner().map_err(io::IntoInnerError::into_error)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Lock of the events file. The lock is taken on a separate file, which is never replaced, so
+/// that it stays valid when the events file is replaced by a compaction.
+struct Lock(File);
+
+impl Lock {
+    fn path(path: &Path) -> PathBuf {
+        let mut lock_path = OsString::from(path);
+        lock_path.push(".lock");
+        PathBuf::from(lock_path)
+    }
+
+    /// Lock for reading. The lock file is only created by writers, so nothing needs to be locked
+    /// if it doesn't exist.
+    fn shared(path: &Path) -> Result<Option<Self>, Error> {
+        match File::open(Self::path(path)) {
+            Ok(file) => {
+                FileExt::lock_shared(&file)?;
+                Ok(Some(Self(file)))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn exclusive(path: &Path) -> Result<Self, Error> {
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .open(Self::path(path))?;
+        FileExt::lock_exclusive(&file)?;
+        Ok(Self(file))
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.0);
     }
 }
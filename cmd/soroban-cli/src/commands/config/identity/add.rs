@@ -25,8 +25,10 @@ pub struct Cmd {
 
 impl Cmd {
     pub fn run(&self) -> Result<(), Error> {
-        Ok(self
-            .config_locator
-            .write_identity(&self.name, &self.secrets.read_secret()?)?)
+        let mut secret = self.secrets.read_secret()?;
+        if self.secrets.encrypt {
+            secret = secret.encrypt(&secret::read_new_passphrase()?)?;
+        }
+        Ok(self.config_locator.write_identity(&self.name, &secret)?)
     }
 }
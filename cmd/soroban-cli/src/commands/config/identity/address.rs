@@ -1,7 +1,8 @@
-use crate::commands::config::secret::Secret;
+use crate::commands::{config::secret::Secret, global};
 
 use super::super::{locator, secret};
 use clap::arg;
+use serde_json::json;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -30,8 +31,13 @@ pub struct Cmd {
 }
 
 impl Cmd {
-    pub fn run(&self) -> Result<(), Error> {
-        println!("{}", self.public_key()?.to_string());
+    pub fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        let address = self.public_key()?.to_string();
+        if global_args.is_json() {
+            println!("{:#}", json!({ "address": address }));
+        } else {
+            println!("{address}");
+        }
         Ok(())
     }
 
@@ -41,6 +47,13 @@ impl Cmd {
         } else {
             Secret::test_seed_phrase()?
         };
+        // Encrypted and external identities keep the default public key in the clear, so there is
+        // no need to ask for the passphrase
+        if let Secret::Encrypted { public_key, .. } | Secret::External { public_key, .. } = &res {
+            if self.hd_path.unwrap_or_default() == 0 {
+                return Ok(stellar_strkey::ed25519::PublicKey::from_string(public_key)?);
+            }
+        }
         let key = res.key_pair(self.hd_path)?;
         Ok(stellar_strkey::ed25519::PublicKey::from_payload(
             key.public.as_bytes(),
@@ -2,7 +2,9 @@ use super::super::{
     locator,
     secret::{self, Secret},
 };
+use crate::commands::global;
 use clap::{arg, command};
+use serde_json::json;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -38,10 +40,15 @@ pub struct Cmd {
     /// Equivalent to --seed 0000000000000000
     #[arg(long, short = 'd', conflicts_with = "seed")]
     pub default_seed: bool,
+
+    /// Encrypt the identity with a passphrase, which is asked for whenever the identity is used.
+    /// Can provide with SOROBAN_IDENTITY_PASSPHRASE
+    #[arg(long)]
+    pub encrypt: bool,
 }
 
 impl Cmd {
-    pub fn run(&self) -> Result<(), Error> {
+    pub fn run(&self, global_args: &global::Args) -> Result<(), Error> {
         let seed_phrase = if self.default_seed {
             Secret::test_seed_phrase()
         } else {
@@ -55,7 +62,19 @@ impl Cmd {
         } else {
             seed_phrase
         };
+        let address = stellar_strkey::ed25519::PublicKey(secret.key_pair(None)?.public.to_bytes());
+        let secret = if self.encrypt {
+            secret.encrypt(&secret::read_new_passphrase()?)?
+        } else {
+            secret
+        };
         self.config_locator.write_identity(&self.name, &secret)?;
+        if global_args.is_json() {
+            println!(
+                "{:#}",
+                json!({ "name": self.name, "address": address.to_string() })
+            );
+        }
         Ok(())
     }
 }
@@ -1,5 +1,7 @@
 use super::super::locator;
+use crate::commands::global;
 use clap::command;
+use serde_json::json;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -15,8 +17,13 @@ pub struct Cmd {
 }
 
 impl Cmd {
-    pub fn run(&self) -> Result<(), Error> {
-        println!("{}", self.config_locator.list_identities()?.join("\n"));
+    pub fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        let identities = self.config_locator.list_identities()?;
+        if global_args.is_json() {
+            println!("{:#}", json!({ "identities": identities }));
+        } else {
+            println!("{}", identities.join("\n"));
+        }
         Ok(())
     }
 }
@@ -0,0 +1,60 @@
+use super::super::{
+    locator::{self, KeyType, Location},
+    secret::{self, Secret},
+};
+use clap::{arg, command};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Config(#[from] locator::Error),
+    #[error(transparent)]
+    Secret(#[from] secret::Error),
+}
+
+#[derive(Debug, clap::Parser, Clone)]
+#[group(skip)]
+pub struct Cmd {
+    /// Names of the identities to encrypt, all plaintext identities if omitted
+    pub names: Vec<String>,
+
+    #[command(flatten)]
+    pub config_locator: locator::Args,
+}
+
+impl Cmd {
+    pub fn run(&self) -> Result<(), Error> {
+        let dir = self.config_locator.config_dir()?;
+        let names = if self.names.is_empty() {
+            KeyType::Identity
+                .list(&Location::Local(dir.clone()))?
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect()
+        } else {
+            self.names.clone()
+        };
+        let mut plaintext = vec![];
+        for name in names {
+            match KeyType::Identity.read(&name, &dir)? {
+                secret @ (Secret::SecretKey { .. } | Secret::SeedPhrase { .. }) => {
+                    plaintext.push((name, secret));
+                }
+                Secret::Encrypted { .. } => tracing::info!("{name} is already encrypted"),
+                Secret::External { .. } => {
+                    tracing::info!("{name} signs with an external program, skipping");
+                }
+            }
+        }
+        if plaintext.is_empty() {
+            return Ok(());
+        }
+        let passphrase = secret::read_new_passphrase()?;
+        for (name, secret) in plaintext {
+            self.config_locator
+                .write_identity(&name, &secret.encrypt(&passphrase)?)?;
+            println!("{name}");
+        }
+        Ok(())
+    }
+}
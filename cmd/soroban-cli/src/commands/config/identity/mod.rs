@@ -1,9 +1,12 @@
 use clap::Parser;
 
+use crate::commands::global;
+
 pub mod add;
 pub mod address;
 pub mod generate;
 pub mod ls;
+pub mod migrate;
 pub mod rm;
 pub mod show;
 
@@ -17,6 +20,8 @@ pub enum Cmd {
     Generate(generate::Cmd),
     /// List identities
     Ls(ls::Cmd),
+    /// Encrypt plaintext identities with a passphrase
+    Migrate(migrate::Cmd),
     /// Remove an identity
     Rm(rm::Cmd),
     /// Given an identity return its private key
@@ -35,6 +40,8 @@ pub enum Error {
     Generate(#[from] generate::Error),
     #[error(transparent)]
     Ls(#[from] ls::Error),
+    #[error(transparent)]
+    Migrate(#[from] migrate::Error),
 
     #[error(transparent)]
     Rm(#[from] rm::Error),
@@ -43,14 +50,15 @@ pub enum Error {
 }
 
 impl Cmd {
-    pub fn run(&self) -> Result<(), Error> {
+    pub fn run(&self, global_args: &global::Args) -> Result<(), Error> {
         match self {
             Cmd::Add(cmd) => cmd.run()?,
-            Cmd::Address(cmd) => cmd.run()?,
+            Cmd::Address(cmd) => cmd.run(global_args)?,
             Cmd::Rm(cmd) => cmd.run()?,
-            Cmd::Ls(cmd) => cmd.run()?,
-            Cmd::Generate(cmd) => cmd.run()?,
-            Cmd::Show(cmd) => cmd.run()?,
+            Cmd::Ls(cmd) => cmd.run(global_args)?,
+            Cmd::Migrate(cmd) => cmd.run()?,
+            Cmd::Generate(cmd) => cmd.run(global_args)?,
+            Cmd::Show(cmd) => cmd.run(global_args)?,
         };
         Ok(())
     }
@@ -1,5 +1,7 @@
 use super::super::{locator, secret};
+use crate::commands::global;
 use clap::arg;
+use serde_json::json;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -28,8 +30,13 @@ pub struct Cmd {
 }
 
 impl Cmd {
-    pub fn run(&self) -> Result<(), Error> {
-        println!("{}", self.private_key()?.to_string());
+    pub fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        let secret_key = self.private_key()?.to_string();
+        if global_args.is_json() {
+            println!("{:#}", json!({ "secret_key": secret_key }));
+        } else {
+            println!("{secret_key}");
+        }
         Ok(())
     }
 
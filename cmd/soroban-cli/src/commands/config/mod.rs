@@ -4,9 +4,16 @@ use clap::{arg, command, Parser};
 use serde::{Deserialize, Serialize};
 use soroban_ledger_snapshot::LedgerSnapshot;
 
-use crate::Pwd;
+use crate::{
+    commands::global,
+    signer::{self, Signer},
+    Pwd,
+};
 
-use self::{network::Network, secret::Secret};
+use self::{
+    network::Network,
+    secret::{CachedSigner, Secret},
+};
 
 pub mod events_file;
 pub mod identity;
@@ -45,10 +52,10 @@ pub enum Error {
 }
 
 impl Cmd {
-    pub fn run(&self) -> Result<(), Error> {
+    pub fn run(&self, global_args: &global::Args) -> Result<(), Error> {
         match &self {
-            Cmd::Identity(identity) => identity.run()?,
-            Cmd::Network(network) => network.run()?,
+            Cmd::Identity(identity) => identity.run(global_args)?,
+            Cmd::Network(network) => network.run(global_args)?,
         }
         Ok(())
     }
@@ -77,13 +84,39 @@ pub struct Args {
 
 impl Args {
     pub fn key_pair(&self) -> Result<ed25519_dalek::Keypair, Error> {
-        let key = if let Some(source_account) = &self.source_account {
-            self.account(source_account)?
+        Ok(self.source()?.key_pair(self.hd_path)?)
+    }
+
+    /// Signer for the source account. Identities that don't keep their private key in the clear
+    /// only need it once they actually sign, and then only once per command.
+    pub fn signer(&self) -> Result<Box<dyn Signer>, Error> {
+        Ok(Box::new(CachedSigner::new(self.source()?, self.hd_path)))
+    }
+
+    fn source(&self) -> Result<Secret, Error> {
+        if let Some(source_account) = &self.source_account {
+            self.account(source_account)
         } else {
-            secret::Secret::test_seed_phrase()?
-        };
+            Ok(secret::Secret::test_seed_phrase()?)
+        }
+    }
 
-        Ok(key.key_pair(self.hd_path)?)
+    /// Signers for all the identities in the config, which can be used to sign the auth entries
+    /// of accounts other than the source account. Identities that can't be read are skipped, and
+    /// seed phrases, encrypted or not, use the key at `--hd-path`, as for the source account.
+    pub fn signers(&self) -> Result<Vec<Box<dyn Signer>>, signer::Error> {
+        let mut signers: Vec<Box<dyn Signer>> = vec![];
+        for name in self.locator.list_identities()? {
+            let secret = match self.locator.read_identity(&name) {
+                Ok(secret) => secret,
+                Err(error) => {
+                    tracing::debug!(%name, %error, "skipping identity");
+                    continue;
+                }
+            };
+            signers.push(Box::new(CachedSigner::new(secret, self.hd_path)));
+        }
+        Ok(signers)
     }
 
     pub fn account(&self, account_str: &str) -> Result<Secret, Error> {
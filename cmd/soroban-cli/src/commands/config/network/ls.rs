@@ -1,7 +1,8 @@
 use clap::command;
+use serde_json::json;
 
 use super::locator;
-use crate::commands::config::locator::Location;
+use crate::commands::{config::locator::Location, global};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -20,7 +21,11 @@ pub struct Cmd {
 }
 
 impl Cmd {
-    pub fn run(&self) -> Result<(), Error> {
+    pub fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        if global_args.is_json() {
+            println!("{:#}", json!({ "networks": self.ls_json()? }));
+            return Ok(());
+        }
         let res = if self.long { self.ls_l() } else { self.ls() }?.join("\n");
         println!("{res}");
         Ok(())
@@ -30,6 +35,26 @@ impl Cmd {
         Ok(self.config_locator.list_networks()?)
     }
 
+    /// Name, location and settings of every network
+    pub fn ls_json(&self) -> Result<Vec<serde_json::Value>, Error> {
+        Ok(self
+            .config_locator
+            .list_networks_long()?
+            .iter()
+            .filter(|(_, _, location)| {
+                !self.config_locator.global || matches!(location, Location::Global(_))
+            })
+            .map(|(name, network, location)| {
+                json!({
+                    "name": name,
+                    "location": location.to_string(),
+                    "rpc_url": network.rpc_url,
+                    "network_passphrase": network.network_passphrase,
+                })
+            })
+            .collect())
+    }
+
     pub fn ls_l(&self) -> Result<Vec<String>, Error> {
         Ok(self
             .config_locator
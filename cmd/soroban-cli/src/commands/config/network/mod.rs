@@ -1,7 +1,7 @@
 use clap::{arg, Parser};
 use serde::{Deserialize, Serialize};
 
-use crate::commands::HEADING_RPC;
+use crate::commands::{global, HEADING_RPC};
 
 use super::locator;
 
@@ -38,11 +38,11 @@ pub enum Error {
 }
 
 impl Cmd {
-    pub fn run(&self) -> Result<(), Error> {
+    pub fn run(&self, global_args: &global::Args) -> Result<(), Error> {
         match self {
             Cmd::Add(cmd) => cmd.run()?,
             Cmd::Rm(new) => new.run()?,
-            Cmd::Ls(cmd) => cmd.run()?,
+            Cmd::Ls(cmd) => cmd.run(global_args)?,
         };
         Ok(())
     }
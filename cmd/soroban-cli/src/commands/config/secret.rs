@@ -1,9 +1,26 @@
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
 use clap::arg;
+use hmac::Hmac;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
-use std::{io::Write, str::FromStr};
+use sha2::Sha256;
+use std::{cell::OnceCell, io::Write, str::FromStr};
 use stellar_strkey::ed25519::PrivateKey;
+use zeroize::Zeroizing;
 
-use crate::utils;
+use crate::{
+    signer::{self, Signer},
+    utils,
+};
+
+/// Environment variable read instead of prompting for the passphrase of encrypted identities
+pub const PASSPHRASE_ENV: &str = "SOROBAN_IDENTITY_PASSPHRASE";
+
+/// PBKDF2-HMAC-SHA256 iterations used to derive the key of newly encrypted identities
+const KDF_ITERATIONS: u32 = 600_000;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -21,6 +38,24 @@ pub enum Error {
     Ed25519(#[from] ed25519_dalek::SignatureError),
     #[error("Invalid address {0}")]
     InvalidAddress(String),
+    #[error("identity is already encrypted")]
+    AlreadyEncrypted,
+    #[error("passphrase can't be empty")]
+    EmptyPassphrase,
+    #[error("passphrases don't match")]
+    PassphraseMismatch,
+    #[error("failed to encrypt identity")]
+    Encryption,
+    #[error("failed to decrypt identity, wrong passphrase?")]
+    Decryption,
+    #[error("identity signs with an external program, so its private key isn't available")]
+    ExternalSigner,
+    #[error("invalid encrypted identity: {0}")]
+    InvalidEncryptedIdentity(String),
+    #[error(transparent)]
+    Hex(#[from] hex::FromHexError),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
 }
 
 #[derive(Debug, clap::Args, Clone)]
@@ -33,11 +68,33 @@ pub struct Args {
     /// Add using 12 word seed phrase to generate secret_key
     #[arg(long, conflicts_with = "secret_key")]
     pub seed_phrase: bool,
+    /// Encrypt the identity with a passphrase, which is asked for whenever the identity is used.
+    /// Can provide with SOROBAN_IDENTITY_PASSPHRASE
+    #[arg(long)]
+    pub encrypt: bool,
+    /// Add an identity that signs with an external program, e.g. `--signer-command "my-hsm sign"`.
+    /// The program gets `{"public_key": "G…", "hash": "<hex>"}` on stdin and must print
+    /// `{"signature": "<hex>"}`. Requires `--public-key`
+    #[arg(
+        long,
+        requires = "public_key",
+        conflicts_with_all = ["secret_key", "seed_phrase", "encrypt"]
+    )]
+    pub signer_command: Option<String>,
+    /// Public key of the account the `--signer-command` signs for
+    #[arg(long, requires = "signer_command")]
+    pub public_key: Option<String>,
 }
 
 impl Args {
     pub fn read_secret(&self) -> Result<Secret, Error> {
-        if let Ok(secret_key) = std::env::var("SOROBAN_SECRET_KEY") {
+        if let (Some(command), Some(public_key)) = (&self.signer_command, &self.public_key) {
+            Ok(Secret::External {
+                public_key: stellar_strkey::ed25519::PublicKey::from_string(public_key)?
+                    .to_string(),
+                command: command.clone(),
+            })
+        } else if let Ok(secret_key) = std::env::var("SOROBAN_SECRET_KEY") {
             Ok(Secret::SecretKey { secret_key })
         } else if self.secret_key {
             println!("Type a secret key: ");
@@ -70,8 +127,29 @@ impl Args {
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum Secret {
-    SecretKey { secret_key: String },
-    SeedPhrase { seed_phrase: String },
+    SecretKey {
+        secret_key: String,
+    },
+    SeedPhrase {
+        seed_phrase: String,
+    },
+    /// A secret key or seed phrase encrypted with ChaCha20-Poly1305, using a key derived from a
+    /// passphrase with PBKDF2-HMAC-SHA256. All binary fields are hex encoded.
+    Encrypted {
+        /// Public key at hd path 0, kept in the clear so the identity can be matched to an
+        /// account without asking for the passphrase
+        public_key: String,
+        kdf_iterations: u32,
+        salt: String,
+        nonce: String,
+        ciphertext: String,
+    },
+    /// An account whose signatures are produced by an external program, see
+    /// [`signer::External`]
+    External {
+        public_key: String,
+        command: String,
+    },
 }
 
 impl FromStr for Secret {
@@ -99,9 +177,85 @@ impl Secret {
             Secret::SeedPhrase { seed_phrase } => sep5::SeedPhrase::from_str(seed_phrase)?
                 .from_path_index(index.unwrap_or_default(), None)?
                 .private(),
+            Secret::Encrypted { public_key, .. } => {
+                let passphrase =
+                    Zeroizing::new(read_passphrase(&format!("Passphrase for {public_key}: "))?);
+                self.decrypt(&passphrase)?.private_key(index)?
+            }
+            Secret::External { .. } => return Err(Error::ExternalSigner),
+        })
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        matches!(self, Secret::Encrypted { .. })
+    }
+
+    pub fn encrypt(&self, passphrase: &str) -> Result<Self, Error> {
+        if self.is_encrypted() {
+            return Err(Error::AlreadyEncrypted);
+        }
+        let public_key =
+            stellar_strkey::ed25519::PublicKey(self.key_pair(None)?.public.to_bytes()).to_string();
+        let mut rng = rand::thread_rng();
+        let salt: [u8; 16] = rng.gen();
+        let nonce: [u8; 12] = rng.gen();
+        let plaintext = Zeroizing::new(serde_json::to_vec(self)?);
+        let ciphertext = cipher(passphrase, &salt, KDF_ITERATIONS)
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: public_key.as_bytes(),
+                },
+            )
+            .map_err(|_| Error::Encryption)?;
+        Ok(Secret::Encrypted {
+            public_key,
+            kdf_iterations: KDF_ITERATIONS,
+            salt: hex::encode(salt),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
         })
     }
 
+    pub fn decrypt(&self, passphrase: &str) -> Result<Self, Error> {
+        let Secret::Encrypted {
+            public_key,
+            kdf_iterations,
+            salt,
+            nonce,
+            ciphertext,
+        } = self
+        else {
+            return Err(Error::InvalidEncryptedIdentity("not encrypted".to_string()));
+        };
+        let nonce = hex::decode(nonce)?;
+        if nonce.len() != 12 {
+            return Err(Error::InvalidEncryptedIdentity(format!(
+                "nonce must be 12 bytes, found {}",
+                nonce.len()
+            )));
+        }
+        let plaintext = Zeroizing::new(
+            cipher(passphrase, &hex::decode(salt)?, *kdf_iterations)
+                .decrypt(
+                    Nonce::from_slice(&nonce),
+                    Payload {
+                        msg: &hex::decode(ciphertext)?,
+                        aad: public_key.as_bytes(),
+                    },
+                )
+                .map_err(|_| Error::Decryption)?,
+        );
+        let secret: Secret = serde_json::from_slice(&plaintext)?;
+        if secret.is_encrypted() {
+            return Err(Error::InvalidEncryptedIdentity(
+                "nested encryption".to_string(),
+            ));
+        }
+        Ok(secret)
+    }
+
     pub fn key_pair(&self, index: Option<usize>) -> Result<ed25519_dalek::Keypair, Error> {
         Ok(utils::into_key_pair(&self.private_key(index)?)?)
     }
@@ -122,7 +276,138 @@ impl Secret {
     }
 }
 
+/// Signs with the key at hd path 0. Encrypted identities only ask for their passphrase once they
+/// actually have to sign.
+impl Signer for Secret {
+    fn public_key(&self) -> Result<ed25519_dalek::PublicKey, signer::Error> {
+        match self {
+            Secret::Encrypted { public_key, .. } | Secret::External { public_key, .. } => {
+                let public_key = stellar_strkey::ed25519::PublicKey::from_string(public_key)?;
+                Ok(ed25519_dalek::PublicKey::from_bytes(&public_key.0)?)
+            }
+            Secret::SecretKey { .. } | Secret::SeedPhrase { .. } => Ok(self.key_pair(None)?.public),
+        }
+    }
+
+    fn sign(&self, hash: &[u8; 32]) -> Result<ed25519_dalek::Signature, signer::Error> {
+        match self {
+            Secret::External {
+                public_key,
+                command,
+            } => signer::External::new(public_key, command)?.sign(hash),
+            Secret::SecretKey { .. } | Secret::SeedPhrase { .. } | Secret::Encrypted { .. } => {
+                self.key_pair(None)?.sign(hash)
+            }
+        }
+    }
+}
+
+/// Signs with the key of a secret at an hd path, keeping its key pair once it's derived, so an
+/// encrypted identity asks for its passphrase at most once per command rather than for every
+/// signature
+pub struct CachedSigner {
+    secret: Secret,
+    hd_path: Option<usize>,
+    key_pair: OnceCell<ed25519_dalek::Keypair>,
+}
+
+impl CachedSigner {
+    pub fn new(secret: Secret, hd_path: Option<usize>) -> Self {
+        Self {
+            secret,
+            hd_path,
+            key_pair: OnceCell::new(),
+        }
+    }
+
+    fn key_pair(&self) -> Result<&ed25519_dalek::Keypair, Error> {
+        if self.key_pair.get().is_none() {
+            let _ = self.key_pair.set(self.secret.key_pair(self.hd_path)?);
+        }
+        Ok(self.key_pair.get().expect("key pair is set"))
+    }
+
+    /// Whether the secret's own public key is the one signed with, so it's known without
+    /// deriving the key pair. External signers have no hd paths.
+    fn uses_own_public_key(&self) -> bool {
+        matches!(self.secret, Secret::External { .. }) || self.hd_path.unwrap_or_default() == 0
+    }
+}
+
+impl Signer for CachedSigner {
+    fn public_key(&self) -> Result<ed25519_dalek::PublicKey, signer::Error> {
+        match self.key_pair.get() {
+            Some(key_pair) => Ok(key_pair.public),
+            None if self.uses_own_public_key() => self.secret.public_key(),
+            None => Ok(self.key_pair()?.public),
+        }
+    }
+
+    fn sign(&self, hash: &[u8; 32]) -> Result<ed25519_dalek::Signature, signer::Error> {
+        if matches!(self.secret, Secret::External { .. }) {
+            return self.secret.sign(hash);
+        }
+        self.key_pair()?.sign(hash)
+    }
+}
+
+/// Read the passphrase of an encrypted identity from `SOROBAN_IDENTITY_PASSPHRASE`, or prompt for it
+pub fn read_passphrase(prompt: &str) -> Result<String, Error> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    eprint!("{prompt}");
+    read_password()
+}
+
+/// Read a passphrase to encrypt identities with from `SOROBAN_IDENTITY_PASSPHRASE`, or prompt for
+/// it twice
+pub fn read_new_passphrase() -> Result<String, Error> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        if passphrase.is_empty() {
+            return Err(Error::EmptyPassphrase);
+        }
+        return Ok(passphrase);
+    }
+    eprint!("Type a passphrase to encrypt the identity with: ");
+    let passphrase = read_password()?;
+    if passphrase.is_empty() {
+        return Err(Error::EmptyPassphrase);
+    }
+    eprint!("Repeat the passphrase: ");
+    if read_password()? != passphrase {
+        return Err(Error::PassphraseMismatch);
+    }
+    Ok(passphrase)
+}
+
+fn cipher(passphrase: &str, salt: &[u8], iterations: u32) -> ChaCha20Poly1305 {
+    let mut key = Zeroizing::new([0; 32]);
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(passphrase.as_bytes(), salt, iterations, &mut *key);
+    ChaCha20Poly1305::new(Key::from_slice(&*key))
+}
+
 fn read_password() -> Result<String, Error> {
     std::io::stdout().flush().map_err(|_| Error::PasswordRead)?;
     rpassword::read_password().map_err(|_| Error::PasswordRead)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cached_signer_derives_encrypted_seed_phrases_at_the_hd_path() {
+        std::env::set_var(PASSPHRASE_ENV, "passphrase");
+        let secret = Secret::test_seed_phrase().unwrap();
+        for hd_path in [None, Some(1)] {
+            let expected = secret.key_pair(hd_path).unwrap();
+            let signer = CachedSigner::new(secret.encrypt("passphrase").unwrap(), hd_path);
+            assert_eq!(signer.public_key().unwrap(), expected.public);
+            assert_eq!(
+                signer.sign(&[1; 32]).unwrap(),
+                ed25519_dalek::Signer::sign(&expected, &[1; 32])
+            );
+        }
+    }
+}
@@ -0,0 +1,325 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io, iter,
+    path::PathBuf,
+};
+
+use clap::{arg, command, Parser};
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{bump, deploy, install, invoke, restore};
+use crate::{
+    commands::{config, global, txn_result::TxnResult},
+    fee,
+};
+
+#[derive(Parser, Debug, Clone)]
+#[group(skip)]
+pub struct Cmd {
+    /// YAML or JSON manifest listing the steps to run
+    #[arg(long)]
+    pub manifest: PathBuf,
+
+    #[command(flatten)]
+    pub config: config::Args,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("reading manifest {0:?}: {1}")]
+    CannotReadManifest(PathBuf, io::Error),
+    #[error("parsing manifest {0:?}: {1}")]
+    CannotParseManifest(PathBuf, serde_yaml::Error),
+    #[error("cannot split arguments {0}")]
+    CannotSplitArgs(String),
+    #[error("unknown step output ${{{0}}}, only the names of earlier steps can be used")]
+    UnknownOutput(String),
+    #[error("name {0} is used by more than one step")]
+    DuplicateName(String),
+    #[error("steps can't use --build-only")]
+    BuildOnly,
+    #[error("step {index} ({command}): {error}")]
+    Step {
+        index: usize,
+        command: &'static str,
+        error: Box<Error>,
+    },
+    #[error(transparent)]
+    Clap(#[from] clap::Error),
+    #[error(transparent)]
+    Install(#[from] install::Error),
+    #[error(transparent)]
+    Deploy(#[from] deploy::Error),
+    #[error(transparent)]
+    Invoke(#[from] invoke::Error),
+    #[error(transparent)]
+    Bump(#[from] bump::Error),
+    #[error(transparent)]
+    Restore(#[from] restore::Error),
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Manifest {
+    steps: Vec<Step>,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Step {
+    /// Name under which later steps can use the output of the step, as `${name}`
+    name: Option<String>,
+    #[serde(flatten)]
+    command: Command,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Command {
+    Install(StepArgs),
+    Deploy(StepArgs),
+    Invoke(StepArgs),
+    Bump(StepArgs),
+    Restore(StepArgs),
+}
+
+impl Command {
+    fn name(&self) -> &'static str {
+        match self {
+            Command::Install(_) => "install",
+            Command::Deploy(_) => "deploy",
+            Command::Invoke(_) => "invoke",
+            Command::Bump(_) => "bump",
+            Command::Restore(_) => "restore",
+        }
+    }
+
+    fn args(&self) -> &StepArgs {
+        match self {
+            Command::Install(args)
+            | Command::Deploy(args)
+            | Command::Invoke(args)
+            | Command::Bump(args)
+            | Command::Restore(args) => args,
+        }
+    }
+}
+
+/// Arguments of a step, either as a command line or already split
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(untagged)]
+enum StepArgs {
+    Line(String),
+    List(Vec<String>),
+}
+
+impl StepArgs {
+    /// Arguments with the outputs of earlier steps substituted
+    fn resolve(&self, outputs: &HashMap<String, String>) -> Result<Vec<String>, Error> {
+        let args = match self {
+            StepArgs::Line(line) => {
+                shlex::split(line).ok_or_else(|| Error::CannotSplitArgs(line.clone()))?
+            }
+            StepArgs::List(args) => args.clone(),
+        };
+        args.iter().map(|arg| substitute(arg, outputs)).collect()
+    }
+}
+
+/// Replace every `${name}` in `arg` with the output of the step called `name`
+fn substitute(arg: &str, outputs: &HashMap<String, String>) -> Result<String, Error> {
+    let mut resolved = String::new();
+    let mut rest = arg;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 2..start + len];
+        let output = outputs
+            .get(name)
+            .ok_or_else(|| Error::UnknownOutput(name.to_string()))?;
+        resolved.push_str(&rest[..start]);
+        resolved.push_str(output);
+        rest = &rest[start + len + 1..];
+    }
+    resolved.push_str(rest);
+    Ok(resolved)
+}
+
+impl Cmd {
+    pub async fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        let contents = fs::read_to_string(&self.manifest)
+            .map_err(|e| Error::CannotReadManifest(self.manifest.clone(), e))?;
+        let manifest: Manifest = serde_yaml::from_str(&contents)
+            .map_err(|e| Error::CannotParseManifest(self.manifest.clone(), e))?;
+        let mut names = HashSet::new();
+        for name in manifest.steps.iter().filter_map(|step| step.name.as_ref()) {
+            if !names.insert(name) {
+                return Err(Error::DuplicateName(name.clone()));
+            }
+        }
+
+        // Steps are submitted one after the other by the same accounts, so sequence numbers are
+        // only fetched once and then counted locally
+        let sequence = fee::Sequence::default();
+        let mut outputs = HashMap::new();
+        let mut results = vec![];
+        for (index, step) in manifest.steps.iter().enumerate() {
+            let command = step.command.name();
+            let output = self
+                .run_step(step, &outputs, &sequence)
+                .await
+                .map_err(|error| Error::Step {
+                    index: index + 1,
+                    command,
+                    error: Box::new(error),
+                })?;
+            if global_args.is_json() {
+                results.push(json!({ "name": step.name, "command": command, "output": output }));
+            } else {
+                println!("{output}");
+            }
+            if let Some(name) = &step.name {
+                outputs.insert(name.clone(), output);
+            }
+        }
+        if global_args.is_json() {
+            println!("{:#}", json!({ "steps": results }));
+        }
+        Ok(())
+    }
+
+    async fn run_step(
+        &self,
+        step: &Step,
+        outputs: &HashMap<String, String>,
+        sequence: &fee::Sequence,
+    ) -> Result<String, Error> {
+        let command = step.command.name();
+        let args = iter::once(command.to_string()).chain(step.command.args().resolve(outputs)?);
+        let prepare = |config: &mut config::Args, fee: &mut fee::Args| {
+            self.inherit(config);
+            fee.sequence = Some(sequence.clone());
+            if fee.build_only {
+                Err(Error::BuildOnly)
+            } else {
+                Ok(())
+            }
+        };
+        let output = match step.command {
+            Command::Install(_) => {
+                let mut cmd = install::Cmd::try_parse_from(args)?;
+                prepare(&mut cmd.config, &mut cmd.fee)?;
+                cmd.run_and_get_hash().await?.map(hex::encode)
+            }
+            Command::Deploy(_) => {
+                let mut cmd = deploy::Cmd::try_parse_from(args)?;
+                prepare(&mut cmd.config, &mut cmd.fee)?;
+                cmd.run_and_get_contract_id().await?
+            }
+            Command::Invoke(_) => {
+                let mut cmd = invoke::Cmd::try_parse_from(args)?;
+                prepare(&mut cmd.config, &mut cmd.fee)?;
+                cmd.invoke().await?
+            }
+            Command::Bump(_) => {
+                let mut cmd = bump::Cmd::try_parse_from(args)?;
+                prepare(&mut cmd.config, &mut cmd.fee)?;
+                cmd.run_and_get_expiration_ledger()
+                    .await?
+                    .map(|ledger| ledger.to_string())
+            }
+            Command::Restore(_) => {
+                let mut cmd = restore::Cmd::try_parse_from(args)?;
+                prepare(&mut cmd.config, &mut cmd.fee)?;
+                cmd.run_and_get_expiration_ledger()
+                    .await?
+                    .map(|ledger| ledger.to_string())
+            }
+        };
+        Ok(match output {
+            TxnResult::Res(output) | TxnResult::Sent(output, _) | TxnResult::Sandbox(output, _) => {
+                output
+            }
+            TxnResult::Hash(hash) => hash,
+            TxnResult::Txn(_) => return Err(Error::BuildOnly),
+        })
+    }
+
+    /// Use the options of the batch for the ones a step doesn't set
+    fn inherit(&self, config: &mut config::Args) {
+        let batch = &self.config;
+        if config.network.rpc_url.is_none() && config.network.network.is_none() {
+            config.network = batch.network.clone();
+        }
+        if config.source_account.is_none() {
+            config.source_account = batch.source_account.clone();
+            config.hd_path = config.hd_path.or(batch.hd_path);
+        }
+        if config.ledger_file.ledger_file.is_none() {
+            config.ledger_file = batch.ledger_file.clone();
+        }
+        if config.locator.config_dir.is_none() {
+            config.locator.config_dir = batch.locator.config_dir.clone();
+        }
+        config.locator.global |= batch.locator.global;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest: Manifest = serde_yaml::from_str(
+            r#"
+steps:
+  - name: token
+    deploy: --wasm token.wasm
+  - invoke: ["--id", "${token}", "--", "initialize", "--name", "My Token"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            manifest,
+            Manifest {
+                steps: vec![
+                    Step {
+                        name: Some("token".to_string()),
+                        command: Command::Deploy(StepArgs::Line("--wasm token.wasm".to_string())),
+                    },
+                    Step {
+                        name: None,
+                        command: Command::Invoke(StepArgs::List(
+                            ["--id", "${token}", "--", "initialize", "--name", "My Token"]
+                                .map(ToString::to_string)
+                                .to_vec()
+                        )),
+                    },
+                ]
+            }
+        );
+
+        // JSON is also YAML
+        let manifest: Manifest =
+            serde_yaml::from_str(r#"{"steps": [{"bump": "--id 1 --ledgers-to-expire 10"}]}"#)
+                .unwrap();
+        assert_eq!(manifest.steps[0].command.name(), "bump");
+    }
+
+    #[test]
+    fn test_resolve_args() {
+        let outputs = HashMap::from([("token".to_string(), "CABC".to_string())]);
+        assert_eq!(
+            StepArgs::Line("--id ${token} -- hello --world 'big ${token}'".to_string())
+                .resolve(&outputs)
+                .unwrap(),
+            vec!["--id", "CABC", "--", "hello", "--world", "big CABC"]
+        );
+        assert!(matches!(
+            StepArgs::Line("--id ${other}".to_string()).resolve(&outputs),
+            Err(Error::UnknownOutput(name)) if name == "other"
+        ));
+        assert_eq!(substitute("${unclosed", &outputs).unwrap(), "${unclosed");
+    }
+}
@@ -5,34 +5,56 @@ use std::{
 };
 
 use clap::{command, Parser};
+use serde_json::json;
 use soroban_env_host::xdr::{
     BumpFootprintExpirationOp, ContractCodeEntry, ContractDataEntry, ContractEntryBodyType,
     Error as XdrError, ExtensionPoint, Hash, LedgerEntry, LedgerEntryChange, LedgerEntryData,
     LedgerFootprint, LedgerKey, LedgerKeyContractData, Memo, MuxedAccount, Operation,
-    OperationBody, Preconditions, ReadXdr, ScAddress, ScSpecTypeDef, ScVal, SequenceNumber,
-    SorobanResources, SorobanTransactionData, Transaction, TransactionExt, TransactionMeta,
-    TransactionMetaV3, Uint256,
+    OperationBody, Preconditions, ReadXdr, ScAddress, ScVal, SequenceNumber, SorobanResources,
+    SorobanTransactionData, Transaction, TransactionExt, TransactionMeta, TransactionMetaV3,
+    Uint256,
 };
 use stellar_strkey::DecodeError;
 
 use crate::{
-    commands::config,
-    commands::contract::Durability,
+    commands::{
+        config,
+        contract::{footprint, key, Durability},
+        global,
+        txn_result::{self, Applied, TxnResult},
+    },
     rpc::{self, Client},
-    utils, wasm, Pwd,
+    signer::{self, Signer},
+    utils::{self, archival},
+    wasm, Pwd,
 };
 
 #[derive(Parser, Debug, Clone)]
 #[group(skip)]
 pub struct Cmd {
     /// Contract ID to which owns the data entries
-    #[arg(long = "id", required_unless_present = "wasm")]
+    #[arg(long = "id", required_unless_present_any = ["wasm", "keys_file"])]
     contract_id: Option<String>,
-    /// Storage key (symbols only)
-    #[arg(long = "key", conflicts_with = "key_xdr")]
+    /// Storage key (symbols, unless `--key-type` is set)
+    #[arg(
+        long = "key",
+        conflicts_with = "key_xdr",
+        requires = "contract_id",
+        requires = "durability"
+    )]
     key: Option<String>,
+    /// Type of `--key`: the name of a type defined in the contract, with the key given as JSON,
+    /// e.g. `--key-type DataKey --key '{"Balance":"G..."}'`, or one of sym, str, addr, bool, u32,
+    /// i32, u64, i64, u128, i128, u256, i256 and bytes
+    #[arg(long, requires = "key")]
+    key_type: Option<String>,
     /// Storage key (base64-encoded XDR)
-    #[arg(long = "key-xdr", conflicts_with = "key")]
+    #[arg(
+        long = "key-xdr",
+        conflicts_with = "key",
+        requires = "contract_id",
+        requires = "durability"
+    )]
     key_xdr: Option<String>,
     /// Path to Wasm file of contract code to bump
     #[arg(
@@ -42,16 +64,19 @@ pub struct Cmd {
         conflicts_with = "key_xdr"
     )]
     wasm: Option<PathBuf>,
-    /// Storage entry durability
-    #[arg(long, value_enum, required = true)]
-    durability: Durability,
+    #[command(flatten)]
+    footprint: footprint::Args,
+    /// Storage entry durability. Required with `--key` and `--key-xdr`, otherwise entries of both
+    /// durabilities are bumped unless it's set
+    #[arg(long, value_enum)]
+    durability: Option<Durability>,
 
     /// Number of ledgers to extend the entries
     #[arg(long, required = true)]
     ledgers_to_expire: u32,
 
     #[command(flatten)]
-    config: config::Args,
+    pub config: config::Args,
     #[command(flatten)]
     pub fee: crate::fee::Args,
 }
@@ -84,51 +109,100 @@ pub enum Error {
     CannotParseContractId(String, DecodeError),
     #[error(transparent)]
     Config(#[from] config::Error),
-    #[error("either `--key` or `--key-xdr` are required")]
+    #[error(transparent)]
+    Key(#[from] key::Error),
+    #[error(transparent)]
+    Footprint(#[from] footprint::Error),
+    #[error(
+        "no entries, pass `--key`, `--key-xdr`, `--wasm`, `--keys-file`, `--all` or an invocation"
+    )]
     KeyIsRequired,
+    #[error("`--key` and `--key-xdr` require `--durability`")]
+    DurabilityRequired,
     #[error("xdr processing error: {0}")]
     Xdr(#[from] XdrError),
     #[error("Ledger entry not found")]
     LedgerEntryNotFound,
+    #[error("{0} is archived, restore it with `soroban contract restore` before bumping it")]
+    EntryArchived(String),
     #[error("missing operation result")]
     MissingOperationResult,
     #[error(transparent)]
     Rpc(#[from] rpc::Error),
     #[error(transparent)]
+    Signer(#[from] signer::Error),
+    #[error(transparent)]
     Wasm(#[from] wasm::Error),
+    #[error(transparent)]
+    TxnResult(#[from] txn_result::Error),
+    #[error(transparent)]
+    Fee(#[from] crate::fee::Error),
 }
 
 impl Cmd {
     #[allow(clippy::too_many_lines)]
-    pub async fn run(&self) -> Result<(), Error> {
-        let expiration_ledger_seq = if self.config.is_no_network() {
-            self.run_in_sandbox()?
-        } else {
-            self.run_against_rpc_server().await?
-        };
+    pub async fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        let res = self.run_and_get_expiration_ledger().await?;
 
-        println!("New expiration ledger: {expiration_ledger_seq}");
+        if global_args.is_json() {
+            println!(
+                "{:#}",
+                res.to_json(|seq| json!({ "expiration_ledger": seq }))?
+            );
+            return Ok(());
+        }
+        match res {
+            TxnResult::Res(expiration_ledger_seq)
+            | TxnResult::Sent(expiration_ledger_seq, _)
+            | TxnResult::Sandbox(expiration_ledger_seq, _) => {
+                println!("New expiration ledger: {expiration_ledger_seq}");
+            }
+            TxnResult::Txn(_) | TxnResult::Hash(_) => println!("{res}"),
+        }
 
         Ok(())
     }
 
-    async fn run_against_rpc_server(&self) -> Result<u32, Error> {
+    pub async fn run_and_get_expiration_ledger(&self) -> Result<TxnResult<u32>, Error> {
+        let entry_keys = self.entry_keys().await?;
+        if self.config.is_no_network() {
+            self.fee.check_sandbox()?;
+            let footprint = LedgerFootprint {
+                read_only: entry_keys.clone().try_into()?,
+                read_write: vec![].try_into()?,
+            };
+            Ok(TxnResult::Sandbox(
+                self.run_in_sandbox(&entry_keys)?,
+                Box::new(Applied::new(footprint, &[])),
+            ))
+        } else {
+            self.run_against_rpc_server(entry_keys).await
+        }
+    }
+
+    async fn run_against_rpc_server(
+        &self,
+        entry_keys: Vec<LedgerKey>,
+    ) -> Result<TxnResult<u32>, Error> {
         let network = self.config.get_network()?;
         tracing::trace!(?network);
-        let needle = self.parse_key()?;
         let network = &self.config.get_network()?;
-        let client = Client::new(&network.rpc_url)?;
-        let key = self.config.key_pair()?;
+        let client = Client::new(&network.rpc_url)?.with_wait(self.fee.wait.wait()?);
+        footprint::check_limit(&client, &entry_keys, false).await?;
+        let key = self.config.signer()?;
+        let public_key = key.public_key()?;
 
         // Get the account sequence number
-        let public_strkey = stellar_strkey::ed25519::PublicKey(key.public.to_bytes()).to_string();
-        let account_details = client.get_account(&public_strkey).await?;
-        let sequence: i64 = account_details.seq_num.into();
+        let public_strkey = stellar_strkey::ed25519::PublicKey(public_key.to_bytes()).to_string();
+        let sequence = self
+            .fee
+            .next_sequence(&client, &network.network_passphrase, &public_strkey)
+            .await?;
 
         let tx = Transaction {
-            source_account: MuxedAccount::Ed25519(Uint256(key.public.to_bytes())),
+            source_account: MuxedAccount::Ed25519(Uint256(public_key.to_bytes())),
             fee: self.fee.fee,
-            seq_num: SequenceNumber(sequence + 1),
+            seq_num: SequenceNumber(sequence),
             cond: Preconditions::None,
             memo: Memo::None,
             operations: vec![Operation {
@@ -143,7 +217,7 @@ impl Cmd {
                 ext: ExtensionPoint::V0,
                 resources: SorobanResources {
                     footprint: LedgerFootprint {
-                        read_only: vec![needle].try_into()?,
+                        read_only: entry_keys.try_into()?,
                         read_write: vec![].try_into()?,
                     },
                     instructions: 0,
@@ -155,35 +229,53 @@ impl Cmd {
             }),
         };
 
-        let (result, meta, events) = client
-            .prepare_and_send_transaction(&tx, &key, &network.network_passphrase, None)
+        if self.fee.build_only {
+            return Ok(TxnResult::Txn(client.prepare_transaction(&tx, None).await?));
+        }
+        if self.fee.no_wait {
+            let tx = client
+                .prepare_and_sign_transaction(
+                    &tx,
+                    &key,
+                    || Ok(vec![]),
+                    &network.network_passphrase,
+                    None,
+                )
+                .await?;
+            return Ok(TxnResult::Hash(client.submit_transaction(&tx).await?));
+        }
+
+        let sent = client
+            .prepare_and_send_transaction(
+                &tx,
+                &key,
+                || Ok(vec![]),
+                &network.network_passphrase,
+                None,
+            )
             .await?;
 
-        tracing::debug!(?result);
-        tracing::debug!(?meta);
-        if !events.is_empty() {
-            tracing::debug!(?events);
+        tracing::debug!(result = ?sent.result);
+        tracing::debug!(meta = ?sent.meta);
+        if !sent.events.is_empty() {
+            tracing::debug!(events = ?sent.events);
         }
 
         // The transaction from core will succeed regardless of whether it actually found & bumped
-        // the entry, so we have to inspect the result meta to tell if it worked or not.
-        let TransactionMeta::V3(TransactionMetaV3 { operations, .. }) = meta else {
+        // the entries, so we have to inspect the result meta to tell if it worked or not.
+        let TransactionMeta::V3(TransactionMetaV3 { operations, .. }) = &sent.meta else {
             return Err(Error::LedgerEntryNotFound);
         };
-
-        // Simply check if there is exactly one entry here. We only support bumping a single
-        // entry via this command (which we should fix separately, but).
-        if operations.len() == 0 {
+        let Some(operation) = operations.first() else {
             return Err(Error::LedgerEntryNotFound);
-        }
-
-        if operations[0].changes.len() != 2 {
-            return Err(Error::LedgerEntryNotFound);
-        }
+        };
 
-        match (&operations[0].changes[0], &operations[0].changes[1]) {
-            (
-                LedgerEntryChange::State(_),
+        // Every bumped entry shows up as its previous state followed by its update. Entries that
+        // were missing or already expire later are left out, so report the earliest expiration
+        let expiration_ledger_seq = operation
+            .changes
+            .iter()
+            .filter_map(|change| match change {
                 LedgerEntryChange::Updated(LedgerEntry {
                     data:
                         LedgerEntryData::ContractData(ContractDataEntry {
@@ -195,39 +287,37 @@ impl Cmd {
                             ..
                         }),
                     ..
-                }),
-            ) => Ok(*expiration_ledger_seq),
-            _ => Err(Error::LedgerEntryNotFound),
-        }
+                }) => Some(*expiration_ledger_seq),
+                _ => None,
+            })
+            .min()
+            .ok_or(Error::LedgerEntryNotFound)?;
+        Ok(TxnResult::Sent(expiration_ledger_seq, Box::new(sent)))
     }
 
-    fn run_in_sandbox(&self) -> Result<u32, Error> {
-        let needle = self.parse_key()?;
-
+    fn run_in_sandbox(&self, entry_keys: &[LedgerKey]) -> Result<u32, Error> {
         // Initialize storage and host
         // TODO: allow option to separate input and output file
         let mut state = self.config.get_state()?;
+        let next_ledger = archival::next_ledger(&state);
+        for (k, entry) in &state.ledger_entries {
+            if entry_keys.contains(k) && archival::is_archived(entry, next_ledger) {
+                return Err(Error::EntryArchived(archival::key_to_string(k)));
+            }
+        }
 
-        // Update all matching entries
+        // Update all matching entries, like on the network keys without an entry are skipped
         let mut expiration_ledger_seq = None;
-        state.ledger_entries = state
-            .ledger_entries
-            .iter()
-            .map(|(k, v)| {
-                let new_k = k.as_ref().clone();
-                let new_v = v.as_ref().clone();
-                (
-                    Box::new(new_k.clone()),
-                    Box::new(if needle == new_k {
-                        let (new_v, new_expiration) = bump_entry(&new_v, self.ledgers_to_expire);
-                        expiration_ledger_seq = Some(new_expiration);
-                        new_v
-                    } else {
-                        new_v
-                    }),
-                )
-            })
-            .collect::<Vec<_>>();
+        for (k, entry) in &mut state.ledger_entries {
+            if entry_keys.contains(k) {
+                let (new_entry, new_expiration) = bump_entry(entry, self.ledgers_to_expire);
+                **entry = new_entry;
+                expiration_ledger_seq = Some(
+                    expiration_ledger_seq
+                        .map_or(new_expiration, |min: u32| min.min(new_expiration)),
+                );
+            }
+        }
 
         self.config.set_state(&mut state)?;
 
@@ -243,32 +333,57 @@ impl Cmd {
             .map_err(|e| Error::CannotParseContractId(self.contract_id.clone().unwrap(), e))
     }
 
-    fn parse_key(&self) -> Result<LedgerKey, Error> {
+    /// Keys of the entries to bump, from all the options, in a single footprint
+    async fn entry_keys(&self) -> Result<Vec<LedgerKey>, Error> {
+        let contract_id = if self.contract_id.is_some() {
+            Some(self.contract_id()?)
+        } else {
+            None
+        };
+        let durability = self.durability.map(Into::into);
+        let mut keys = self
+            .footprint
+            .keys(contract_id, durability, &self.config)
+            .await?;
+        if let Some(wasm) = &self.wasm {
+            keys.push(crate::wasm::Args { wasm: wasm.clone() }.try_into()?);
+        }
+        if let Some(key) = self.parse_key().await? {
+            keys.push(key);
+        }
+        keys.sort();
+        keys.dedup();
+
+        if keys.is_empty() {
+            if self.footprint.is_set() {
+                return Err(Error::LedgerEntryNotFound);
+            }
+            return Err(Error::KeyIsRequired);
+        }
+        Ok(keys)
+    }
+
+    async fn parse_key(&self) -> Result<Option<LedgerKey>, Error> {
         let key = if let Some(key) = &self.key {
-            soroban_spec_tools::from_string_primitive(key, &ScSpecTypeDef::Symbol).map_err(|e| {
-                Error::CannotParseKey {
-                    key: key.clone(),
-                    error: e,
-                }
-            })?
+            let key_type = key::key_type(self.key_type.as_deref())?;
+            let spec = key::spec(&key_type, self.contract_id()?, &self.config).await?;
+            key::parse(key, &key_type, &spec)?
         } else if let Some(key) = &self.key_xdr {
             ScVal::from_xdr_base64(key).map_err(|e| Error::CannotParseXdrKey {
                 key: key.clone(),
                 error: e,
             })?
-        } else if let Some(wasm) = &self.wasm {
-            return Ok(crate::wasm::Args { wasm: wasm.clone() }.try_into()?);
         } else {
-            return Err(Error::KeyIsRequired);
+            return Ok(None);
         };
         let contract_id = self.contract_id()?;
 
-        Ok(LedgerKey::ContractData(LedgerKeyContractData {
+        Ok(Some(LedgerKey::ContractData(LedgerKeyContractData {
             contract: ScAddress::Contract(Hash(contract_id)),
-            durability: self.durability.into(),
+            durability: self.durability.ok_or(Error::DurabilityRequired)?.into(),
             body_type: ContractEntryBodyType::DataEntry,
             key,
-        }))
+        })))
     }
 }
 
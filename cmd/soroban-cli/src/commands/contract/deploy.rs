@@ -4,21 +4,29 @@ use std::num::ParseIntError;
 
 use clap::{arg, command, Parser};
 use rand::Rng;
+use serde_json::json;
 use sha2::{Digest, Sha256};
 use soroban_env_host::{
     xdr::{
         AccountId, ContractExecutable, ContractIdPreimage, ContractIdPreimageFromAddress,
         CreateContractArgs, Error as XdrError, Hash, HashIdPreimage, HashIdPreimageContractId,
-        HostFunction, InvokeHostFunctionOp, Memo, MuxedAccount, Operation, OperationBody,
-        Preconditions, PublicKey, ScAddress, SequenceNumber, Transaction, TransactionExt, Uint256,
-        VecM, WriteXdr,
+        HostFunction, InvokeHostFunctionOp, LedgerFootprint, Memo, MuxedAccount, Operation,
+        OperationBody, Preconditions, PublicKey, ScAddress, SequenceNumber, Transaction,
+        TransactionExt, Uint256, VecM, WriteXdr,
     },
     HostError,
 };
 
 use crate::{
-    commands::{config, contract::install, HEADING_RPC, HEADING_SANDBOX},
+    commands::{
+        config,
+        contract::install,
+        global,
+        txn_result::{self, Applied, TxnResult},
+        HEADING_RPC, HEADING_SANDBOX,
+    },
     rpc::{self, Client},
+    signer::{self, Signer},
     utils, wasm,
 };
 
@@ -31,7 +39,7 @@ use crate::{
 #[group(skip)]
 pub struct Cmd {
     /// WASM file to deploy
-    #[arg(long, group = "wasm_src")]
+    #[arg(long, group = "wasm_src", conflicts_with = "build_only")]
     wasm: Option<std::path::PathBuf>,
 
     /// Hash of the already installed/deployed WASM file
@@ -53,7 +61,7 @@ pub struct Cmd {
     )]
     salt: Option<String>,
     #[command(flatten)]
-    config: config::Args,
+    pub config: config::Args,
     #[command(flatten)]
     pub fee: crate::fee::Args,
 }
@@ -62,8 +70,8 @@ pub struct Cmd {
 pub enum Error {
     #[error(transparent)]
     Install(#[from] install::Error),
-    #[error(transparent)]
-    Host(#[from] HostError),
+    #[error("{0}")]
+    Failed(Box<utils::host_error::Report>),
     #[error("error parsing int: {0}")]
     ParseIntError(#[from] ParseIntError),
     #[error("internal conversion error: {0}")]
@@ -89,27 +97,53 @@ pub enum Error {
     #[error(transparent)]
     Rpc(#[from] rpc::Error),
     #[error(transparent)]
+    Signer(#[from] signer::Error),
+    #[error(transparent)]
     Config(#[from] config::Error),
     #[error(transparent)]
     StrKey(#[from] stellar_strkey::DecodeError),
+    #[error(transparent)]
+    TxnResult(#[from] txn_result::Error),
+    #[error(transparent)]
+    Fee(#[from] crate::fee::Error),
+    #[error("installing the contract code didn't produce its hash")]
+    WasmHashMissing,
+}
+
+impl From<HostError> for Error {
+    fn from(error: HostError) -> Self {
+        Error::Failed(Box::new(utils::host_error::Report::from_host_error(&error)))
+    }
 }
 
 impl Cmd {
-    pub async fn run(&self) -> Result<(), Error> {
-        let res_str = self.run_and_get_contract_id().await?;
-        println!("{res_str}");
+    pub async fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        let res = self.run_and_get_contract_id().await?;
+        if global_args.is_json() {
+            println!(
+                "{:#}",
+                res.to_json(|contract_id| json!({ "contract_id": contract_id }))?
+            );
+        } else {
+            println!("{res}");
+        }
         Ok(())
     }
 
-    pub async fn run_and_get_contract_id(&self) -> Result<String, Error> {
+    pub async fn run_and_get_contract_id(&self) -> Result<TxnResult<String>, Error> {
         let wasm_hash = if let Some(wasm) = &self.wasm {
             let hash = install::Cmd {
                 wasm: wasm::Args { wasm: wasm.clone() },
                 config: self.config.clone(),
-                fee: self.fee.clone(),
+                fee: crate::fee::Args {
+                    no_wait: false,
+                    ..self.fee.clone()
+                },
             }
             .run_and_get_hash()
-            .await?;
+            .await?
+            .into_result()
+            .ok_or(Error::WasmHashMissing)?;
             hex::encode(hash)
         } else {
             self.wasm_hash
@@ -126,14 +160,14 @@ impl Cmd {
         })?);
 
         if self.config.is_no_network() {
+            self.fee.check_sandbox()?;
             self.run_in_sandbox(hash)
         } else {
             self.run_against_rpc_server(hash).await
         }
     }
 
-    #[allow(clippy::needless_pass_by_value)]
-    pub fn run_in_sandbox(&self, wasm_hash: Hash) -> Result<String, Error> {
+    pub fn run_in_sandbox(&self, wasm_hash: Hash) -> Result<TxnResult<String>, Error> {
         let contract_id: [u8; 32] = match &self.contract_id {
             Some(id) => {
                 utils::contract_id_from_str(id).map_err(|e| Error::CannotParseContractId {
@@ -145,17 +179,25 @@ impl Cmd {
         };
 
         let mut state = self.config.get_state()?;
+        let expiration_ledger_seq = utils::archival::new_persistent_expiration(&state);
         utils::add_contract_to_ledger_entries(
             &mut state.ledger_entries,
             contract_id,
             wasm_hash.0,
-            state.min_persistent_entry_expiration,
+            expiration_ledger_seq,
         );
         self.config.set_state(&mut state)?;
-        Ok(stellar_strkey::Contract(contract_id).to_string())
+        let footprint = LedgerFootprint {
+            read_only: vec![utils::contract_code_key(wasm_hash)].try_into()?,
+            read_write: vec![utils::contract_instance_key(contract_id)].try_into()?,
+        };
+        Ok(TxnResult::Sandbox(
+            stellar_strkey::Contract(contract_id).to_string(),
+            Box::new(Applied::new(footprint, &[])),
+        ))
     }
 
-    async fn run_against_rpc_server(&self, wasm_hash: Hash) -> Result<String, Error> {
+    async fn run_against_rpc_server(&self, wasm_hash: Hash) -> Result<TxnResult<String>, Error> {
         let network = self.config.get_network()?;
         let salt: [u8; 32] = match &self.salt {
             Some(h) => soroban_spec_tools::utils::padded_hex_from_str(h, 32)
@@ -165,29 +207,56 @@ impl Cmd {
             None => rand::thread_rng().gen::<[u8; 32]>(),
         };
 
-        let client = Client::new(&network.rpc_url)?;
+        let client = Client::new(&network.rpc_url)?.with_wait(self.fee.wait.wait()?);
         client
             .verify_network_passphrase(Some(&network.network_passphrase))
             .await?;
-        let key = self.config.key_pair()?;
+        let key = self.config.signer()?;
+        let public_key = key.public_key()?;
 
         // Get the account sequence number
-        let public_strkey = stellar_strkey::ed25519::PublicKey(key.public.to_bytes()).to_string();
+        let public_strkey = stellar_strkey::ed25519::PublicKey(public_key.to_bytes()).to_string();
 
-        let account_details = client.get_account(&public_strkey).await?;
-        let sequence: i64 = account_details.seq_num.into();
+        let sequence = self
+            .fee
+            .next_sequence(&client, &network.network_passphrase, &public_strkey)
+            .await?;
         let (tx, contract_id) = build_create_contract_tx(
             wasm_hash,
-            sequence + 1,
+            sequence,
             self.fee.fee,
             &network.network_passphrase,
             salt,
-            &key,
+            &public_key,
         )?;
-        client
-            .prepare_and_send_transaction(&tx, &key, &network.network_passphrase, None)
+        if self.fee.build_only {
+            return Ok(TxnResult::Txn(client.prepare_transaction(&tx, None).await?));
+        }
+        if self.fee.no_wait {
+            let tx = client
+                .prepare_and_sign_transaction(
+                    &tx,
+                    &key,
+                    || Ok(vec![]),
+                    &network.network_passphrase,
+                    None,
+                )
+                .await?;
+            return Ok(TxnResult::Hash(client.submit_transaction(&tx).await?));
+        }
+        let sent = client
+            .prepare_and_send_transaction(
+                &tx,
+                &key,
+                || Ok(vec![]),
+                &network.network_passphrase,
+                None,
+            )
             .await?;
-        Ok(stellar_strkey::Contract(contract_id.0).to_string())
+        Ok(TxnResult::Sent(
+            stellar_strkey::Contract(contract_id.0).to_string(),
+            Box::new(sent),
+        ))
     }
 }
 
@@ -197,11 +266,9 @@ fn build_create_contract_tx(
     fee: u32,
     network_passphrase: &str,
     salt: [u8; 32],
-    key: &ed25519_dalek::Keypair,
+    key: &ed25519_dalek::PublicKey,
 ) -> Result<(Transaction, Hash), Error> {
-    let source_account = AccountId(PublicKey::PublicKeyTypeEd25519(
-        key.public.to_bytes().into(),
-    ));
+    let source_account = AccountId(PublicKey::PublicKeyTypeEd25519(key.to_bytes().into()));
 
     let contract_id_preimage = ContractIdPreimage::Address(ContractIdPreimageFromAddress {
         address: ScAddress::Account(source_account),
@@ -220,7 +287,7 @@ fn build_create_contract_tx(
         }),
     };
     let tx = Transaction {
-        source_account: MuxedAccount::Ed25519(Uint256(key.public.to_bytes())),
+        source_account: MuxedAccount::Ed25519(Uint256(key.to_bytes())),
         fee,
         seq_num: SequenceNumber(sequence),
         cond: Preconditions::None,
@@ -266,7 +333,8 @@ mod tests {
             "Public Global Stellar Network ; September 2015",
             [0u8; 32],
             &utils::parse_secret_key("SBFGFF27Y64ZUGFAIG5AMJGQODZZKV2YQKAVUUN4HNE24XZXD2OEUVUP")
-                .unwrap(),
+                .unwrap()
+                .public,
         );
 
         assert!(result.is_ok());
@@ -6,6 +6,7 @@ use std::str::FromStr;
 use std::{fmt::Debug, fs, io, rc::Rc};
 
 use clap::{arg, command, Parser};
+use serde_json::json;
 use soroban_env_host::{
     budget::Budget,
     storage::Storage,
@@ -25,6 +26,7 @@ use super::super::config::{self, locator};
 use crate::commands::config::ledger_file;
 use crate::commands::config::network::{self, Network};
 use crate::{
+    commands::global,
     rpc::{self, Client},
     utils, Pwd,
 };
@@ -101,7 +103,7 @@ impl From<Infallible> for Error {
 }
 
 impl Cmd {
-    pub async fn run(&self) -> Result<(), Error> {
+    pub async fn run(&self, global_args: &global::Args) -> Result<(), Error> {
         let bytes = self.get_bytes().await?;
         if let Some(out_file) = &self.out_file {
             if let Some(parent) = out_file.parent() {
@@ -110,15 +112,28 @@ impl Cmd {
                         .map_err(|_| Error::CannotCreateContractDir(out_file.clone()))?;
                 }
             }
-            fs::write(out_file, bytes)
-                .map_err(|io| Error::CannotWriteContractFile(out_file.clone(), io))
-        } else {
+            fs::write(out_file, &bytes)
+                .map_err(|io| Error::CannotWriteContractFile(out_file.clone(), io))?;
+        }
+        if global_args.is_json() {
+            let mut json = json!({
+                "wasm_hash": hex::encode(utils::contract_hash(&bytes)?.0),
+                "size": bytes.len(),
+            });
+            // Without `--out-file` the Wasm is embedded in the object rather than written raw
+            if let Some(out_file) = &self.out_file {
+                json["out_file"] = json!(out_file);
+            } else {
+                json["wasm"] = json!(base64::encode(&bytes));
+            }
+            println!("{json:#}");
+        } else if self.out_file.is_none() {
             let stdout = std::io::stdout();
             let mut handle = stdout.lock();
             handle.write_all(&bytes)?;
             handle.flush()?;
-            Ok(())
         }
+        Ok(())
     }
 
     pub async fn get_bytes(&self) -> Result<Vec<u8>, Error> {
@@ -0,0 +1,248 @@
+use std::{
+    ffi::OsString,
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use clap::arg;
+use soroban_env_host::xdr::{
+    ConfigSettingContractLedgerCostV0, ConfigSettingEntry, ConfigSettingId, ContractDataDurability,
+    Error as XdrError, Hash, LedgerEntryData, LedgerKey, LedgerKeyConfigSetting,
+    LedgerKeyContractData, ReadXdr, ScAddress, ScVal,
+};
+
+use crate::{
+    commands::{config, contract::invoke},
+    rpc::{self, Client},
+    utils,
+};
+
+/// Ways to select many ledger entries at once, for `bump` and `restore` to put in the footprint of
+/// a single transaction
+#[derive(Debug, clap::Args, Clone)]
+#[group(skip)]
+pub struct Args {
+    /// File of ledger keys, one base64-encoded XDR LedgerKey per line, or `-` to read them from
+    /// stdin
+    #[arg(long)]
+    pub keys_file: Option<PathBuf>,
+    /// Every storage entry of the contract in the sandbox, along with its code
+    #[arg(long, requires = "contract_id")]
+    pub all: bool,
+    /// Invocation of the contract to simulate to find the entries, e.g. `-- transfer --from alice
+    /// --to bob --amount 10`. All the entries in the footprint of the simulation are selected
+    #[arg(last = true, id = "CONTRACT_FN_AND_ARGS", requires = "contract_id")]
+    pub slop: Vec<OsString>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("reading keys file {0:?}: {1}")]
+    CannotRead(PathBuf, io::Error),
+    #[error("parsing ledger key {key} in {path:?}: {error}")]
+    CannotParseKey {
+        path: PathBuf,
+        key: String,
+        error: XdrError,
+    },
+    #[error("`--all` only works with the sandbox, pass the keys with `--keys-file` instead")]
+    AllRequiresSandbox,
+    #[error("simulating an invocation requires a network, pass `--network` or `--rpc-url`")]
+    NetworkNotProvided,
+    #[error("`--all` and invocations require `--id`")]
+    ContractIdRequired,
+    #[error("{count} ledger entries are more than the {limit} a transaction can {access} on the network, split them across several commands")]
+    TooManyEntries {
+        count: usize,
+        limit: u32,
+        access: &'static str,
+    },
+    #[error(transparent)]
+    Rpc(#[from] rpc::Error),
+    #[error(transparent)]
+    Xdr(#[from] XdrError),
+    #[error(transparent)]
+    Config(#[from] config::Error),
+    #[error(transparent)]
+    Invoke(#[from] invoke::Error),
+}
+
+impl Args {
+    /// Whether any of the options is set
+    pub fn is_set(&self) -> bool {
+        self.keys_file.is_some() || self.all || !self.slop.is_empty()
+    }
+
+    /// Keys of the contract code and contract data entries selected by the options, leaving out
+    /// contract data entries of a durability other than `durability` when it's set
+    pub async fn keys(
+        &self,
+        contract_id: Option<[u8; 32]>,
+        durability: Option<ContractDataDurability>,
+        config: &config::Args,
+    ) -> Result<Vec<LedgerKey>, Error> {
+        let mut keys = vec![];
+        if let Some(path) = &self.keys_file {
+            keys.extend(read_keys(path)?);
+        }
+        if self.all {
+            if !config.is_no_network() {
+                return Err(Error::AllRequiresSandbox);
+            }
+            let contract = ScAddress::Contract(Hash(contract_id.ok_or(Error::ContractIdRequired)?));
+            for (key, entry) in config.get_state()?.ledger_entries {
+                let LedgerKey::ContractData(data) = key.as_ref() else {
+                    continue;
+                };
+                if data.contract != contract {
+                    continue;
+                }
+                if data.key == ScVal::LedgerKeyContractInstance {
+                    keys.extend(utils::instance_code_key(&entry));
+                }
+                keys.push(*key);
+            }
+        }
+        if !self.slop.is_empty() {
+            if config.is_no_network() {
+                return Err(Error::NetworkNotProvided);
+            }
+            let contract_id = contract_id.ok_or(Error::ContractIdRequired)?;
+            let footprint = invoke::Cmd {
+                contract_id: stellar_strkey::Contract(contract_id).to_string(),
+                slop: self.slop.clone(),
+                config: config.clone(),
+                ..Default::default()
+            }
+            .simulate_footprint()
+            .await?;
+            keys.extend(footprint.read_only.iter().cloned());
+            keys.extend(footprint.read_write.iter().cloned());
+        }
+        Ok(keys
+            .into_iter()
+            .filter(|key| match key {
+                LedgerKey::ContractCode(_) => true,
+                LedgerKey::ContractData(LedgerKeyContractData { durability: d, .. }) => {
+                    durability.map_or(true, |durability| *d == durability)
+                }
+                _ => false,
+            })
+            .collect())
+    }
+}
+
+/// Error if there are more `keys` than the footprint of a transaction can have on the network of
+/// `client`. Every entry in a footprint counts towards the entries a transaction can read, and the
+/// ones that are `written` towards the entries it can write too. The check is skipped when the
+/// limits can't be looked up, leaving it to the network to reject the transaction.
+pub async fn check_limit(client: &Client, keys: &[LedgerKey], written: bool) -> Result<(), Error> {
+    let (read_limit, write_limit) = match ledger_entry_limits(client).await {
+        Ok(Some(limits)) => limits,
+        Ok(None) => return Ok(()),
+        Err(error) => {
+            tracing::warn!(%error, "skipping the check of the footprint size");
+            return Ok(());
+        }
+    };
+    let mut limits = vec![(read_limit, "read")];
+    if written {
+        limits.push((write_limit, "write"));
+    }
+    for (limit, access) in limits {
+        if usize::try_from(limit).map_or(false, |limit| keys.len() > limit) {
+            return Err(Error::TooManyEntries {
+                count: keys.len(),
+                limit,
+                access,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Number of ledger entries a transaction can read and write on the network of `client`
+async fn ledger_entry_limits(client: &Client) -> Result<Option<(u32, u32)>, Error> {
+    let response = client
+        .get_ledger_entries(vec![LedgerKey::ConfigSetting(LedgerKeyConfigSetting {
+            config_setting_id: ConfigSettingId::ContractLedgerCostV0,
+        })])
+        .await?;
+    let Some(entry) = response.entries.unwrap_or_default().into_iter().next() else {
+        return Ok(None);
+    };
+    let LedgerEntryData::ConfigSetting(ConfigSettingEntry::ContractLedgerCostV0(
+        ConfigSettingContractLedgerCostV0 {
+            tx_max_read_ledger_entries,
+            tx_max_write_ledger_entries,
+            ..
+        },
+    )) = LedgerEntryData::from_xdr_base64(&entry.xdr)?
+    else {
+        return Ok(None);
+    };
+    Ok(Some((
+        tx_max_read_ledger_entries,
+        tx_max_write_ledger_entries,
+    )))
+}
+
+fn read_keys(path: &Path) -> Result<Vec<LedgerKey>, Error> {
+    let mut contents = String::new();
+    if path.as_os_str() == "-" {
+        io::stdin().read_to_string(&mut contents)
+    } else {
+        fs::File::open(path).and_then(|mut file| file.read_to_string(&mut contents))
+    }
+    .map_err(|e| Error::CannotRead(path.to_path_buf(), e))?;
+    parse_keys(path, &contents)
+}
+
+fn parse_keys(path: &Path, contents: &str) -> Result<Vec<LedgerKey>, Error> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|key| {
+            LedgerKey::from_xdr_base64(key).map_err(|error| Error::CannotParseKey {
+                path: path.to_path_buf(),
+                key: key.to_string(),
+                error,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use soroban_env_host::xdr::{ContractEntryBodyType, LedgerKeyContractCode, WriteXdr};
+
+    use super::*;
+
+    #[test]
+    fn test_parse_keys() {
+        let code = LedgerKey::ContractCode(LedgerKeyContractCode {
+            hash: Hash([1; 32]),
+            body_type: ContractEntryBodyType::DataEntry,
+        });
+        let data = LedgerKey::ContractData(LedgerKeyContractData {
+            contract: ScAddress::Contract(Hash([2; 32])),
+            key: ScVal::LedgerKeyContractInstance,
+            durability: ContractDataDurability::Persistent,
+            body_type: ContractEntryBodyType::DataEntry,
+        });
+        let path = Path::new("keys");
+
+        let contents = format!(
+            "{}\n\n  {}\n",
+            code.to_xdr_base64().unwrap(),
+            data.to_xdr_base64().unwrap()
+        );
+        assert_eq!(parse_keys(path, &contents).unwrap(), vec![code, data]);
+        assert!(matches!(
+            parse_keys(path, "not a key"),
+            Err(Error::CannotParseKey { key, .. }) if key == "not a key"
+        ));
+    }
+}
@@ -1,7 +1,11 @@
 use clap::{command, Parser};
+use serde_json::json;
 use std::fmt::Debug;
 
-use crate::{commands::config::locator, wasm};
+use crate::{
+    commands::{config::locator, global},
+    wasm,
+};
 
 #[derive(Parser, Debug, Clone)]
 #[group(skip)]
@@ -16,12 +20,25 @@ pub struct Cmd {
 pub enum Error {
     #[error(transparent)]
     Wasm(#[from] wasm::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
 }
 
 impl Cmd {
-    pub fn run(&self) -> Result<(), Error> {
+    pub fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        let spec = self.wasm.parse()?;
+        if global_args.is_json() {
+            let json = json!({
+                "file": self.wasm.wasm,
+                "env_meta": serde_json::to_value(&spec.env_meta)?,
+                "meta": serde_json::to_value(&spec.meta)?,
+                "spec": serde_json::to_value(&spec.spec)?,
+            });
+            println!("{json:#}");
+            return Ok(());
+        }
         println!("File: {}", self.wasm.wasm.to_string_lossy());
-        println!("{}", self.wasm.parse()?);
+        println!("{spec}");
         Ok(())
     }
 }
@@ -2,12 +2,19 @@ use std::array::TryFromSliceError;
 use std::fmt::Debug;
 use std::num::ParseIntError;
 
+use crate::commands::{
+    global,
+    txn_result::{self, Applied, TxnResult},
+};
 use crate::rpc::{self, Client};
+use crate::signer::{self, Signer};
 use crate::{commands::config, utils, wasm};
 use clap::{command, Parser};
+use serde_json::json;
 use soroban_env_host::xdr::{
-    Error as XdrError, Hash, HostFunction, InvokeHostFunctionOp, Memo, MuxedAccount, Operation,
-    OperationBody, Preconditions, SequenceNumber, Transaction, TransactionExt, Uint256, VecM,
+    Error as XdrError, Hash, HostFunction, InvokeHostFunctionOp, LedgerFootprint, Memo,
+    MuxedAccount, Operation, OperationBody, Preconditions, SequenceNumber, Transaction,
+    TransactionExt, Uint256, VecM,
 };
 
 #[derive(Parser, Debug, Clone)]
@@ -34,68 +41,114 @@ pub enum Error {
     #[error(transparent)]
     Rpc(#[from] rpc::Error),
     #[error(transparent)]
+    Signer(#[from] signer::Error),
+    #[error(transparent)]
     Config(#[from] config::Error),
     #[error(transparent)]
     Wasm(#[from] wasm::Error),
     #[error("unexpected ({length}) simulate transaction result length")]
     UnexpectedSimulateTransactionResultSize { length: usize },
+    #[error(transparent)]
+    TxnResult(#[from] txn_result::Error),
+    #[error(transparent)]
+    Fee(#[from] crate::fee::Error),
 }
 
 impl Cmd {
-    pub async fn run(&self) -> Result<(), Error> {
-        let res_str = hex::encode(self.run_and_get_hash().await?);
-        println!("{res_str}");
+    pub async fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        let res = self.run_and_get_hash().await?;
+        if global_args.is_json() {
+            println!(
+                "{:#}",
+                res.to_json(|hash| json!({ "wasm_hash": hex::encode(hash) }))?
+            );
+        } else {
+            println!("{}", res.map(hex::encode));
+        }
         Ok(())
     }
 
-    pub async fn run_and_get_hash(&self) -> Result<Hash, Error> {
+    pub async fn run_and_get_hash(&self) -> Result<TxnResult<Hash>, Error> {
         let contract = self.wasm.read()?;
         if self.config.is_no_network() {
+            self.fee.check_sandbox()?;
             self.run_in_sandbox(contract)
         } else {
             self.run_against_rpc_server(contract).await
         }
     }
 
-    pub fn run_in_sandbox(&self, contract: Vec<u8>) -> Result<Hash, Error> {
+    pub fn run_in_sandbox(&self, contract: Vec<u8>) -> Result<TxnResult<Hash>, Error> {
         let mut state = self.config.get_state()?;
+        let expiration_ledger_seq = utils::archival::new_persistent_expiration(&state);
         let wasm_hash = utils::add_contract_code_to_ledger_entries(
             &mut state.ledger_entries,
             contract,
-            state.min_persistent_entry_expiration,
+            expiration_ledger_seq,
         )?;
 
         self.config.set_state(&mut state)?;
 
-        Ok(wasm_hash)
+        let footprint = LedgerFootprint {
+            read_only: VecM::default(),
+            read_write: vec![utils::contract_code_key(wasm_hash.clone())].try_into()?,
+        };
+        Ok(TxnResult::Sandbox(
+            wasm_hash,
+            Box::new(Applied::new(footprint, &[])),
+        ))
     }
 
-    async fn run_against_rpc_server(&self, contract: Vec<u8>) -> Result<Hash, Error> {
+    async fn run_against_rpc_server(&self, contract: Vec<u8>) -> Result<TxnResult<Hash>, Error> {
         let network = self.config.get_network()?;
-        let client = Client::new(&network.rpc_url)?;
+        let client = Client::new(&network.rpc_url)?.with_wait(self.fee.wait.wait()?);
         client
             .verify_network_passphrase(Some(&network.network_passphrase))
             .await?;
-        let key = self.config.key_pair()?;
+        let key = self.config.signer()?;
+        let public_key = key.public_key()?;
 
         // Get the account sequence number
-        let public_strkey = stellar_strkey::ed25519::PublicKey(key.public.to_bytes()).to_string();
-        let account_details = client.get_account(&public_strkey).await?;
-        let sequence: i64 = account_details.seq_num.into();
+        let public_strkey = stellar_strkey::ed25519::PublicKey(public_key.to_bytes()).to_string();
+        let sequence = self
+            .fee
+            .next_sequence(&client, &network.network_passphrase, &public_strkey)
+            .await?;
 
         let (tx_without_preflight, hash) =
-            build_install_contract_code_tx(contract.clone(), sequence + 1, self.fee.fee, &key)?;
+            build_install_contract_code_tx(contract.clone(), sequence, self.fee.fee, &public_key)?;
+
+        if self.fee.build_only {
+            return Ok(TxnResult::Txn(
+                client
+                    .prepare_transaction(&tx_without_preflight, None)
+                    .await?,
+            ));
+        }
+        if self.fee.no_wait {
+            let tx = client
+                .prepare_and_sign_transaction(
+                    &tx_without_preflight,
+                    &key,
+                    || Ok(vec![]),
+                    &network.network_passphrase,
+                    None,
+                )
+                .await?;
+            return Ok(TxnResult::Hash(client.submit_transaction(&tx).await?));
+        }
 
-        client
+        let sent = client
             .prepare_and_send_transaction(
                 &tx_without_preflight,
                 &key,
+                || Ok(vec![]),
                 &network.network_passphrase,
                 None,
             )
             .await?;
 
-        Ok(hash)
+        Ok(TxnResult::Sent(hash, Box::new(sent)))
     }
 }
 
@@ -103,12 +156,12 @@ pub(crate) fn build_install_contract_code_tx(
     source_code: Vec<u8>,
     sequence: i64,
     fee: u32,
-    key: &ed25519_dalek::Keypair,
+    key: &ed25519_dalek::PublicKey,
 ) -> Result<(Transaction, Hash), XdrError> {
     let hash = utils::contract_hash(&source_code)?;
 
     let op = Operation {
-        source_account: Some(MuxedAccount::Ed25519(Uint256(key.public.to_bytes()))),
+        source_account: Some(MuxedAccount::Ed25519(Uint256(key.to_bytes()))),
         body: OperationBody::InvokeHostFunction(InvokeHostFunctionOp {
             host_function: HostFunction::UploadContractWasm(source_code.try_into()?),
             auth: VecM::default(),
@@ -116,7 +169,7 @@ pub(crate) fn build_install_contract_code_tx(
     };
 
     let tx = Transaction {
-        source_account: MuxedAccount::Ed25519(Uint256(key.public.to_bytes())),
+        source_account: MuxedAccount::Ed25519(Uint256(key.to_bytes())),
         fee,
         seq_num: SequenceNumber(sequence),
         cond: Preconditions::None,
@@ -139,7 +192,8 @@ mod tests {
             300,
             1,
             &utils::parse_secret_key("SBFGFF27Y64ZUGFAIG5AMJGQODZZKV2YQKAVUUN4HNE24XZXD2OEUVUP")
-                .unwrap(),
+                .unwrap()
+                .public,
         );
 
         assert!(result.is_ok());
@@ -4,10 +4,11 @@ use std::ffi::OsString;
 use std::num::ParseIntError;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::{fmt::Debug, fs, io, rc::Rc};
+use std::{fmt::Debug, fs, io, io::Read, rc::Rc};
 
 use clap::{arg, command, Parser};
 use heck::ToKebabCase;
+use serde_json::{json, Value};
 use soroban_env_host::{
     budget::Budget,
     events::HostEvent,
@@ -15,9 +16,10 @@ use soroban_env_host::{
     xdr::{
         self, AccountId, Error as XdrError, Hash, HostFunction, InvokeHostFunctionOp,
         LedgerEntryData, LedgerFootprint, LedgerKey, LedgerKeyAccount, Memo, MuxedAccount,
-        Operation, OperationBody, Preconditions, PublicKey, ScAddress, ScSpecEntry,
+        Operation, OperationBody, Preconditions, PublicKey, ReadXdr, ScAddress, ScSpecEntry,
         ScSpecFunctionV0, ScSpecTypeDef, ScVal, ScVec, SequenceNumber, SorobanAddressCredentials,
-        SorobanAuthorizationEntry, SorobanCredentials, Transaction, TransactionExt, Uint256, VecM,
+        SorobanAuthorizationEntry, SorobanCredentials, SorobanTransactionData, Transaction,
+        TransactionEnvelope, TransactionExt, TransactionV1Envelope, Uint256, VecM,
     },
     DiagnosticLevel, Host, HostError,
 };
@@ -30,9 +32,17 @@ use super::super::{
     events,
 };
 use crate::{
-    commands::HEADING_SANDBOX,
+    commands::{
+        global, sandbox,
+        txn_result::{self, Applied, TxnResult},
+        HEADING_SANDBOX,
+    },
     rpc::{self, Client},
-    utils::{self, contract_spec, create_ledger_footprint, default_account_ledger_entry},
+    signer::{self, Signer},
+    utils::{
+        self, archival, contract_spec, create_ledger_footprint, default_account_ledger_entry,
+        host_error,
+    },
     Pwd,
 };
 use soroban_spec_tools::Spec;
@@ -57,6 +67,16 @@ pub struct Cmd {
           conflicts_with = "network",
           help_heading = HEADING_SANDBOX)]
     pub unlimited_budget: bool,
+    /// Only simulate the invocation and print the result, along with its footprint, authorizations
+    /// and cost. No transaction is signed or submitted, and in sandbox mode the ledger and events
+    /// files are left untouched
+    #[arg(long, conflicts_with = "build_only")]
+    pub sim_only: bool,
+    /// JSON object with the arguments of the function by name, read from a file, or from stdin
+    /// with `-`. String values are parsed like arguments passed on the command line, which take
+    /// precedence over the ones in the file
+    #[arg(long)]
+    pub args_file: Option<PathBuf>,
 
     // Function name as subcommand, then arguments for that function as `--arg-name value`
     #[arg(last = true, id = "CONTRACT_FN_AND_ARGS")]
@@ -67,6 +87,8 @@ pub struct Cmd {
     #[command(flatten)]
     pub events_file: events_file::Args,
     #[command(flatten)]
+    pub ledger: sandbox::ledger::Args,
+    #[command(flatten)]
     pub fee: crate::fee::Args,
 }
 
@@ -92,12 +114,24 @@ pub enum Error {
         arg: String,
         error: soroban_spec_tools::Error,
     },
+    #[error("reading file {path:?} for argument {arg}: {error}")]
+    CannotReadArgFile {
+        arg: String,
+        path: PathBuf,
+        error: io::Error,
+    },
+    #[error("reading arguments file {0:?}: {1}")]
+    CannotReadArgsFile(PathBuf, io::Error),
+    #[error("parsing arguments file {0:?}: {1}")]
+    CannotParseArgsFile(PathBuf, serde_json::Error),
+    #[error("arguments file {0:?} must contain a JSON object of arguments by name")]
+    ArgsFileNotAnObject(PathBuf),
+    #[error("function {function} has no argument {arg}")]
+    UnknownArgument { function: String, arg: String },
     #[error("cannot add contract to ledger entries: {0}")]
     CannotAddContractToLedgerEntries(XdrError),
-    #[error(transparent)]
-    // TODO: the Display impl of host errors is pretty user-unfriendly
-    //       (it just calls Debug). I think we can do better than that
-    Host(#[from] HostError),
+    #[error("{0}")]
+    Failed(Box<host_error::Report>),
     #[error("reading file {0:?}: {1}")]
     CannotReadContractFile(PathBuf, io::Error),
     #[error("committing file {filepath}: {error}")]
@@ -127,6 +161,8 @@ pub enum Error {
     ParseIntError(#[from] ParseIntError),
     #[error(transparent)]
     Rpc(#[from] rpc::Error),
+    #[error(transparent)]
+    Signer(#[from] signer::Error),
     #[error("unexpected contract code data type: {0:?}")]
     UnexpectedContractCodeDataType(LedgerEntryData),
     #[error("missing operation result")]
@@ -147,12 +183,26 @@ pub enum Error {
     Events(#[from] events_file::Error),
     #[error(transparent)]
     Locator(#[from] locator::Error),
-    #[error("Contract Error\n{0}: {1}")]
-    ContractInvoke(String, String),
     #[error(transparent)]
     StrKey(#[from] stellar_strkey::DecodeError),
     #[error(transparent)]
     ContractSpec(#[from] contract_spec::Error),
+    #[error(transparent)]
+    TxnResult(#[from] txn_result::Error),
+    #[error(transparent)]
+    Fee(#[from] crate::fee::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Ledger(#[from] sandbox::ledger::Error),
+    #[error("{0} is archived, restore it with `soroban contract restore` before using it")]
+    EntryArchived(String),
+}
+
+impl From<HostError> for Error {
+    fn from(error: HostError) -> Self {
+        Error::Failed(Box::new(host_error::Report::from_host_error(&error)))
+    }
 }
 
 impl From<Infallible> for Error {
@@ -181,6 +231,18 @@ impl Cmd {
         let (function, matches_) = &matches_.remove_subcommand().unwrap();
 
         let func = spec.find_function(function)?;
+        let args_file = self.read_args_file()?;
+        if let Some(arg) = args_file.keys().find(|arg| {
+            !func
+                .inputs
+                .iter()
+                .any(|i| i.name.to_string_lossy() == **arg)
+        }) {
+            return Err(Error::UnknownArgument {
+                function: function.clone(),
+                arg: arg.clone(),
+            });
+        }
         // create parsed_args in same order as the inputs to func
         let parsed_args = func
             .inputs
@@ -188,19 +250,17 @@ impl Cmd {
             .map(|i| {
                 let name = i.name.to_string().unwrap();
                 if let Some(mut val) = matches_.get_raw(&name) {
-                    let mut s = val.next().unwrap().to_string_lossy().to_string();
-                    if matches!(i.type_, ScSpecTypeDef::Address) {
-                        let cmd = crate::commands::config::identity::address::Cmd {
-                            name: Some(s.clone()),
-                            hd_path: Some(0),
-                            locator: self.config.locator.clone(),
-                        };
-                        if let Ok(address) = cmd.public_key() {
-                            s = address.to_string();
-                        }
+                    let s = val.next().unwrap().to_string_lossy().to_string();
+                    self.parse_arg(&spec, name, &s, &i.type_)
+                } else if let Some(path) = matches_.get_one::<PathBuf>(&fmt_arg_file_name(&name)) {
+                    self.parse_arg_file(&spec, name, path, &i.type_)
+                } else if let Some(val) = args_file.get(&name) {
+                    match val {
+                        Value::String(s) => self.parse_arg(&spec, name, s, &i.type_),
+                        val => spec
+                            .from_json(val, &i.type_)
+                            .map_err(|error| Error::CannotParseArg { arg: name, error }),
                     }
-                    spec.from_string(&s, &i.type_)
-                        .map_err(|error| Error::CannotParseArg { arg: name, error })
                 } else if matches!(i.type_, ScSpecTypeDef::Option(_)) {
                     Ok(ScVal::Void)
                 } else {
@@ -233,34 +293,139 @@ impl Cmd {
         ))
     }
 
-    pub async fn run(&self) -> Result<(), Error> {
-        let res = self.invoke().await?;
-        println!("{res}");
+    /// Parse an argument given as a string, resolving identity names to their address
+    fn parse_arg(
+        &self,
+        spec: &Spec,
+        name: String,
+        s: &str,
+        type_: &ScSpecTypeDef,
+    ) -> Result<ScVal, Error> {
+        let mut s = s.to_string();
+        if matches!(type_, ScSpecTypeDef::Address) {
+            let cmd = crate::commands::config::identity::address::Cmd {
+                name: Some(s.clone()),
+                hd_path: Some(0),
+                locator: self.config.locator.clone(),
+            };
+            if let Ok(address) = cmd.public_key() {
+                s = address.to_string();
+            }
+        }
+        spec.from_string(&s, type_)
+            .map_err(|error| Error::CannotParseArg { arg: name, error })
+    }
+
+    /// Parse an argument read from a file. `Bytes` and `BytesN` arguments are the raw contents
+    /// of the file, other arguments are parsed like on the command line.
+    fn parse_arg_file(
+        &self,
+        spec: &Spec,
+        name: String,
+        path: &Path,
+        type_: &ScSpecTypeDef,
+    ) -> Result<ScVal, Error> {
+        let cannot_read = |error| Error::CannotReadArgFile {
+            arg: name.clone(),
+            path: path.to_path_buf(),
+            error,
+        };
+        if matches!(type_, ScSpecTypeDef::Bytes | ScSpecTypeDef::BytesN(_)) {
+            let bytes = fs::read(path).map_err(cannot_read)?;
+            let val = ScVal::Bytes(bytes.try_into()?);
+            if !spec.is_of_type(&val, type_) {
+                return Err(Error::CannotParseArg {
+                    arg: name,
+                    error: soroban_spec_tools::Error::InvalidValue(Some(type_.clone())),
+                });
+            }
+            Ok(val)
+        } else {
+            let s = fs::read_to_string(path).map_err(cannot_read)?;
+            self.parse_arg(spec, name, s.trim_end(), type_)
+        }
+    }
+
+    /// Arguments from `--args-file`, by name
+    fn read_args_file(&self) -> Result<serde_json::Map<String, Value>, Error> {
+        let Some(path) = &self.args_file else {
+            return Ok(serde_json::Map::new());
+        };
+        let mut contents = String::new();
+        if path.as_os_str() == "-" {
+            io::stdin().read_to_string(&mut contents)
+        } else {
+            fs::File::open(path).and_then(|mut file| file.read_to_string(&mut contents))
+        }
+        .map_err(|e| Error::CannotReadArgsFile(path.clone(), e))?;
+        match serde_json::from_str(&contents)
+            .map_err(|e| Error::CannotParseArgsFile(path.clone(), e))?
+        {
+            Value::Object(args) => Ok(args),
+            _ => Err(Error::ArgsFileNotAnObject(path.clone())),
+        }
+    }
+
+    pub async fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        let (res, simulation) = self.invoke_and_simulate().await?;
+        let simulation = simulation.as_ref().map(serde_json::to_value).transpose()?;
+        if global_args.is_json() {
+            // The result is already JSON, unless the function doesn't return anything
+            let mut json = res.to_json(|res| json!({ "result": result_to_json(res) }))?;
+            if let (Some(Value::Object(simulation)), Value::Object(object)) =
+                (simulation, &mut json)
+            {
+                object.extend(simulation);
+            }
+            println!("{json:#}");
+        } else {
+            if let Some(Value::Object(simulation)) = simulation {
+                for (name, value) in simulation {
+                    eprintln!("{name}: {value}");
+                }
+            }
+            println!("{res}");
+        }
         Ok(())
     }
 
-    pub async fn invoke(&self) -> Result<String, Error> {
+    pub async fn invoke(&self) -> Result<TxnResult<String>, Error> {
+        Ok(self.invoke_and_simulate().await?.0)
+    }
+
+    /// Invoke the contract, along with what the simulation found out when `--sim-only` is set
+    async fn invoke_and_simulate(&self) -> Result<(TxnResult<String>, Option<Simulation>), Error> {
         if self.config.is_no_network() {
-            self.run_in_sandbox()
+            self.fee.check_sandbox()?;
+            self.invoke_in_sandbox()
         } else {
             self.run_against_rpc_server().await
         }
     }
 
-    pub async fn run_against_rpc_server(&self) -> Result<String, Error> {
+    pub async fn run_against_rpc_server(
+        &self,
+    ) -> Result<(TxnResult<String>, Option<Simulation>), Error> {
         let network = self.config.get_network()?;
         tracing::trace!(?network);
         let contract_id = self.contract_id()?;
-        let client = Client::new(&network.rpc_url)?;
+        let client = Client::new(&network.rpc_url)?.with_wait(self.fee.wait.wait()?);
         client
             .verify_network_passphrase(Some(&network.network_passphrase))
             .await?;
-        let key = self.config.key_pair()?;
+        let key = self.config.signer()?;
+        let public_key = key.public_key()?;
 
         // Get the account sequence number
-        let public_strkey = stellar_strkey::ed25519::PublicKey(key.public.to_bytes()).to_string();
-        let account_details = client.get_account(&public_strkey).await?;
-        let sequence: i64 = account_details.seq_num.into();
+        let public_strkey = stellar_strkey::ed25519::PublicKey(public_key.to_bytes()).to_string();
+        // A simulated transaction isn't submitted, so it doesn't use up a sequence number
+        let sequence = if self.sim_only {
+            i64::from(client.get_account(&public_strkey).await?.seq_num) + 1
+        } else {
+            self.fee
+                .next_sequence(&client, &network.network_passphrase, &public_strkey)
+                .await?
+        };
 
         // Get the contract
         let spec_entries = if let Some(spec) = self.spec_entries()? {
@@ -274,28 +439,104 @@ impl Cmd {
             self.build_host_function_parameters(contract_id, &spec_entries)?;
         let tx = build_invoke_contract_tx(
             host_function_params.clone(),
-            sequence + 1,
+            sequence,
             self.fee.fee,
-            &key,
+            &public_key,
         )?;
 
-        let (result, meta, events) = client
-            .prepare_and_send_transaction(&tx, &key, &network.network_passphrase, Some(log_events))
-            .await?;
+        if self.sim_only {
+            let (res, simulation) = simulate(&client, &tx, &contract_id, &spec, &function).await?;
+            return Ok((TxnResult::Res(res), Some(simulation)));
+        }
+        if self.fee.build_only {
+            return Ok((
+                TxnResult::Txn(
+                    client
+                        .prepare_transaction(&tx, Some(log_events))
+                        .await
+                        .map_err(|e| decode_rpc_error(e, &contract_id, &spec))?,
+                ),
+                None,
+            ));
+        }
 
-        tracing::debug!(?result);
-        if !events.is_empty() {
-            tracing::debug!(?events);
+        // Any identity may need to sign an auth entry, e.g. the `from` of a `transfer_from`
+        let signers = || self.config.signers();
+        if self.fee.no_wait {
+            let tx = client
+                .prepare_and_sign_transaction(
+                    &tx,
+                    &key,
+                    signers,
+                    &network.network_passphrase,
+                    Some(log_events),
+                )
+                .await
+                .map_err(|e| decode_rpc_error(e, &contract_id, &spec))?;
+            return Ok((TxnResult::Hash(client.submit_transaction(&tx).await?), None));
         }
+        let sent = client
+            .prepare_and_send_transaction(
+                &tx,
+                &key,
+                signers,
+                &network.network_passphrase,
+                Some(log_events),
+            )
+            .await
+            .map_err(|e| decode_rpc_error(e, &contract_id, &spec))?;
 
-        let xdr::TransactionMeta::V3(xdr::TransactionMetaV3{soroban_meta: Some(xdr::SorobanTransactionMeta{return_value, ..}), ..}) = meta else {
-            return Err(Error::MissingOperationResult);
+        tracing::debug!(result = ?sent.result);
+        if !sent.events.is_empty() {
+            tracing::debug!(events = ?sent.events);
+        }
+
+        let return_value = sent.return_value().ok_or(Error::MissingOperationResult)?;
+        let res = output_to_string(&spec, return_value, &function)?;
+        Ok((TxnResult::Sent(res, Box::new(sent)), None))
+    }
+
+    /// Ledger entries the invocation reads and writes, as simulated by the network
+    pub async fn simulate_footprint(&self) -> Result<LedgerFootprint, Error> {
+        let network = self.config.get_network()?;
+        let contract_id = self.contract_id()?;
+        let client = Client::new(&network.rpc_url)?;
+        let public_key = self.config.signer()?.public_key()?;
+
+        let public_strkey = stellar_strkey::ed25519::PublicKey(public_key.to_bytes()).to_string();
+        let sequence: i64 = client.get_account(&public_strkey).await?.seq_num.into();
+        let spec_entries = if let Some(spec) = self.spec_entries()? {
+            spec
+        } else {
+            client.get_remote_contract_spec(&contract_id).await?
         };
 
-        output_to_string(&spec, &return_value, &function)
+        let (_, spec, host_function_params) =
+            self.build_host_function_parameters(contract_id, &spec_entries)?;
+        let tx = build_invoke_contract_tx(
+            host_function_params,
+            sequence + 1,
+            self.fee.fee,
+            &public_key,
+        )?;
+        let sim_response = client
+            .simulate_transaction(&TransactionEnvelope::Tx(TransactionV1Envelope {
+                tx,
+                signatures: VecM::default(),
+            }))
+            .await
+            .map_err(|e| decode_rpc_error(e, &contract_id, &spec))?;
+        let transaction_data =
+            SorobanTransactionData::from_xdr_base64(&sim_response.transaction_data)?;
+        Ok(transaction_data.resources.footprint)
     }
 
     pub fn run_in_sandbox(&self) -> Result<String, Error> {
+        let (res, _) = self.invoke_in_sandbox()?;
+        res.into_result().ok_or(Error::MissingResult)
+    }
+
+    fn invoke_in_sandbox(&self) -> Result<(TxnResult<String>, Option<Simulation>), Error> {
         let contract_id = self.contract_id()?;
         // Initialize storage and host
         // TODO: allow option to separate input and output file
@@ -306,7 +547,7 @@ impl Cmd {
 
         // Create source account, adding it to the ledger if not already present.
         let source_account = AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(
-            self.config.key_pair()?.public.to_bytes(),
+            self.config.signer()?.public_key()?.to_bytes(),
         )));
         let source_account_ledger_key = LedgerKey::Account(LedgerKeyAccount {
             account_id: source_account.clone(),
@@ -322,13 +563,21 @@ impl Cmd {
             ));
         }
 
-        let snap = Rc::new(state.clone());
-        let mut storage = Storage::with_recording_footprint(snap);
+        // Each invocation happens in the next ledger, 5 seconds later, unless told otherwise
+        let mut ledger_info = state.ledger_info();
+        self.ledger.apply(&mut ledger_info, 1, 5)?;
+        archival::evict_expired_temporary(&mut state.ledger_entries, ledger_info.sequence_number);
+
+        let snap = Rc::new(archival::ArchivalSnapshot::new(
+            state.clone(),
+            ledger_info.sequence_number,
+        ));
+        let mut storage = Storage::with_recording_footprint(snap.clone());
         let spec_entries = if let Some(spec) = self.spec_entries()? {
             spec
         } else {
             utils::get_contract_spec_from_storage(&mut storage, &state.sequence_number, contract_id)
-                .map_err(Error::CannotParseContractSpec)?
+                .map_err(|e| archived_error(&snap).unwrap_or(Error::CannotParseContractSpec(e)))?
         };
         let budget = Budget::default();
         if self.unlimited_budget {
@@ -337,10 +586,6 @@ impl Cmd {
         let h = Host::with_storage_and_budget(storage, budget);
         h.switch_to_recording_auth();
         h.set_source_account(source_account);
-
-        let mut ledger_info = state.ledger_info();
-        ledger_info.sequence_number += 1;
-        ledger_info.timestamp += 5;
         h.set_ledger_info(ledger_info.clone());
 
         let (function, spec, host_function_params) =
@@ -349,11 +594,14 @@ impl Cmd {
         let resv = h
             .invoke_function(HostFunction::InvokeContract(host_function_params))
             .map_err(|host_error| {
-                if let Ok(error) = spec.find_error_type(host_error.error.get_code()) {
-                    Error::ContractInvoke(error.name.to_string_lossy(), error.doc.to_string_lossy())
-                } else {
-                    host_error.into()
+                if let Some(error) = archived_error(&snap) {
+                    return error;
                 }
+                let events = h.get_events().map(|events| events.0).unwrap_or_default();
+                let mut report =
+                    host_error::Report::from_host_error(&host_error).with_events(&events);
+                report.decode(&contract_id, &spec);
+                Error::Failed(Box::new(report))
             })?;
 
         let res_str = output_to_string(&spec, &resv, &function)?;
@@ -382,11 +630,22 @@ impl Cmd {
         let footprint = &create_ledger_footprint(&storage.footprint);
         log_events(
             footprint,
-            &[contract_auth.try_into()?],
+            &[contract_auth.clone().try_into()?],
             &events.0,
             Some(&budget),
         );
 
+        if self.sim_only {
+            let simulation = Simulation {
+                footprint: footprint.clone(),
+                auth: contract_auth,
+                cpu_insns: budget.get_cpu_insns_consumed()?,
+                mem_bytes: budget.get_mem_bytes_consumed()?,
+                min_resource_fee: None,
+            };
+            return Ok((TxnResult::Res(res_str), Some(simulation)));
+        }
+
         utils::bump_ledger_entry_expirations(&mut state.ledger_entries, &expiration_ledger_bumps);
 
         self.config.set_state(&mut state)?;
@@ -394,7 +653,8 @@ impl Cmd {
             self.events_file
                 .commit(&events.0, &state, &self.config.locator.config_dir()?)?;
         }
-        Ok(res_str)
+        let applied = Applied::new(footprint.clone(), &events.0);
+        Ok((TxnResult::Sandbox(res_str, Box::new(applied)), None))
     }
 
     pub fn deploy_contract_in_sandbox(
@@ -403,10 +663,11 @@ impl Cmd {
         contract_id: &[u8; 32],
     ) -> Result<(), Error> {
         if let Some(contract) = self.read_wasm()? {
+            let expiration_ledger_seq = archival::new_persistent_expiration(state);
             let wasm_hash = utils::add_contract_code_to_ledger_entries(
                 &mut state.ledger_entries,
                 contract,
-                state.min_persistent_entry_expiration,
+                expiration_ledger_seq,
             )
             .map_err(Error::CannotAddContractToLedgerEntries)?
             .0;
@@ -414,7 +675,7 @@ impl Cmd {
                 &mut state.ledger_entries,
                 *contract_id,
                 wasm_hash,
-                state.min_persistent_entry_expiration,
+                expiration_ledger_seq,
             );
         }
         Ok(())
@@ -444,6 +705,75 @@ impl Cmd {
     }
 }
 
+/// Footprint, authorizations and cost of a simulated invocation, shown by `--sim-only`
+#[derive(serde::Serialize, Debug)]
+pub struct Simulation {
+    pub footprint: LedgerFootprint,
+    pub auth: Vec<SorobanAuthorizationEntry>,
+    pub cpu_insns: u64,
+    pub mem_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_resource_fee: Option<u32>,
+}
+
+async fn simulate(
+    client: &Client,
+    tx: &Transaction,
+    contract_id: &[u8; 32],
+    spec: &Spec,
+    function: &str,
+) -> Result<(String, Simulation), Error> {
+    let sim_response = client
+        .simulate_transaction(&TransactionEnvelope::Tx(TransactionV1Envelope {
+            tx: tx.clone(),
+            signatures: VecM::default(),
+        }))
+        .await
+        .map_err(|e| decode_rpc_error(e, contract_id, spec))?;
+    let [rpc::SimulateHostFunctionResult { auth, xdr }] = sim_response.results.as_slice() else {
+        return Err(Error::UnexpectedSimulateTransactionResultSize {
+            length: sim_response.results.len(),
+        });
+    };
+
+    crate::log::events(&sim_response.events()?);
+
+    let transaction_data = SorobanTransactionData::from_xdr_base64(&sim_response.transaction_data)?;
+    let footprint = transaction_data.resources.footprint;
+    crate::log::footprint(&footprint);
+    let auth = auth
+        .iter()
+        .map(SorobanAuthorizationEntry::from_xdr_base64)
+        .collect::<Result<Vec<_>, _>>()?;
+    crate::log::auth(&[auth.clone().try_into()?]);
+    let rpc::Cost {
+        cpu_insns,
+        mem_bytes,
+    } = &sim_response.cost;
+
+    let res = output_to_string(spec, &ScVal::from_xdr_base64(xdr)?, function)?;
+    Ok((
+        res,
+        Simulation {
+            footprint,
+            auth,
+            cpu_insns: cpu_insns.parse()?,
+            mem_bytes: mem_bytes.parse()?,
+            min_resource_fee: Some(sim_response.min_resource_fee),
+        },
+    ))
+}
+
+/// Decode the contract error and call arguments of a failed invocation with the contract spec
+fn decode_rpc_error(mut error: rpc::Error, contract_id: &[u8; 32], spec: &Spec) -> rpc::Error {
+    match &mut error {
+        rpc::Error::TransactionFailed(failure) => failure.decode(contract_id, spec),
+        rpc::Error::TransactionSimulationHostError(report) => report.decode(contract_id, spec),
+        _ => (),
+    }
+    error
+}
+
 fn log_events(
     footprint: &LedgerFootprint,
     auth: &[VecM<SorobanAuthorizationEntry>],
@@ -458,6 +788,21 @@ fn log_events(
     }
 }
 
+/// Error for the first archived entry the invocation tried to access, if any
+fn archived_error(snapshot: &archival::ArchivalSnapshot) -> Option<Error> {
+    snapshot
+        .archived_access()
+        .map(|key| Error::EntryArchived(archival::key_to_string(&key)))
+}
+
+fn result_to_json(res: &str) -> serde_json::Value {
+    if res.is_empty() {
+        serde_json::Value::Null
+    } else {
+        serde_json::from_str(res).unwrap_or_else(|_| serde_json::Value::String(res.to_string()))
+    }
+}
+
 pub fn output_to_string(spec: &Spec, res: &ScVal, function: &str) -> Result<String, Error> {
     let mut res_str = String::new();
     if let Some(output) = spec.find_function(function)?.outputs.get(0) {
@@ -476,7 +821,7 @@ fn build_invoke_contract_tx(
     parameters: ScVec,
     sequence: i64,
     fee: u32,
-    key: &ed25519_dalek::Keypair,
+    key: &ed25519_dalek::PublicKey,
 ) -> Result<Transaction, Error> {
     let op = Operation {
         source_account: None,
@@ -486,7 +831,7 @@ fn build_invoke_contract_tx(
         }),
     };
     Ok(Transaction {
-        source_account: MuxedAccount::Ed25519(Uint256(key.public.to_bytes())),
+        source_account: MuxedAccount::Ed25519(Uint256(key.to_bytes())),
         fee,
         seq_num: SequenceNumber(sequence),
         cond: Preconditions::None,
@@ -545,6 +890,28 @@ fn build_custom_cmd(name: &str, spec: &Spec) -> Result<clap::Command, Error> {
         };
 
         cmd = cmd.arg(arg);
+
+        let file_arg_name = fmt_arg_file_name(name);
+        let file_help = if matches!(type_, ScSpecTypeDef::Bytes | ScSpecTypeDef::BytesN(_)) {
+            format!("Path to a file whose contents are the bytes of --{name}")
+        } else {
+            format!("Path to a file with the value of --{name}, e.g. a JSON document")
+        };
+        cmd = cmd.arg(
+            clap::Arg::new(file_arg_name.clone())
+                .long(file_arg_name.clone())
+                .alias(file_arg_name.to_kebab_case())
+                .num_args(1)
+                .value_name("PATH")
+                .value_parser(clap::value_parser!(PathBuf))
+                .conflicts_with(name)
+                .help(file_help),
+        );
     }
     Ok(cmd)
 }
+
+/// Name of the argument to read the value of argument `name` from a file
+fn fmt_arg_file_name(name: &str) -> String {
+    format!("{name}-file-path")
+}
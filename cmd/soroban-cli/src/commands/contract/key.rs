@@ -0,0 +1,127 @@
+use soroban_env_host::xdr::{ScSpecTypeDef, ScSpecTypeUdt, ScVal};
+use soroban_spec::read::FromWasmError;
+use soroban_spec_tools::Spec;
+
+use crate::{
+    commands::config,
+    rpc::{self, Client},
+    utils,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("parsing key {key}: {error}")]
+    CannotParseKey {
+        key: String,
+        error: soroban_spec_tools::Error,
+    },
+    #[error(
+        "unknown key type {0}, expected the name of a type defined in the contract or one of: {}",
+        utils::primitive_type_names()
+    )]
+    UnknownKeyType(String),
+    #[error("cannot get the spec of the contract to parse the key: {0}")]
+    CannotGetSpec(FromWasmError),
+    #[error(transparent)]
+    Config(#[from] config::Error),
+    #[error(transparent)]
+    Rpc(#[from] rpc::Error),
+}
+
+/// Type that `--key` values are parsed as: symbols by default, otherwise the primitive or
+/// contract-defined type named by `--key-type`
+pub fn key_type(name: Option<&str>) -> Result<ScSpecTypeDef, Error> {
+    let Some(name) = name else {
+        return Ok(ScSpecTypeDef::Symbol);
+    };
+    if let Some((_, type_)) = utils::PRIMITIVE_TYPES.iter().find(|(n, _)| *n == name) {
+        return Ok(type_.clone());
+    }
+    Ok(ScSpecTypeDef::Udt(ScSpecTypeUdt {
+        name: name
+            .try_into()
+            .map_err(|_| Error::UnknownKeyType(name.to_string()))?,
+    }))
+}
+
+/// Spec of the contract, from the sandbox or the network, when it's needed to parse keys of
+/// type `type_`
+pub async fn spec(
+    type_: &ScSpecTypeDef,
+    contract_id: [u8; 32],
+    config: &config::Args,
+) -> Result<Spec, Error> {
+    if !matches!(type_, ScSpecTypeDef::Udt(_)) {
+        return Ok(Spec::default());
+    }
+    let entries = if config.is_no_network() {
+        utils::get_contract_spec_from_state(&config.get_state()?, contract_id)
+            .map_err(Error::CannotGetSpec)?
+    } else {
+        Client::new(&config.get_network()?.rpc_url)?
+            .get_remote_contract_spec(&contract_id)
+            .await?
+    };
+    Ok(Spec::new(entries))
+}
+
+/// Parse a `--key` as a value of `type_`, e.g. JSON like `{"Balance":"G..."}` for a union
+/// defined in the contract
+pub fn parse(key: &str, type_: &ScSpecTypeDef, spec: &Spec) -> Result<ScVal, Error> {
+    spec.from_string(key, type_)
+        .map_err(|error| Error::CannotParseKey {
+            key: key.to_string(),
+            error,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use soroban_env_host::xdr::{
+        ScSpecEntry, ScSpecUdtUnionCaseTupleV0, ScSpecUdtUnionCaseV0, ScSpecUdtUnionCaseVoidV0,
+        ScSpecUdtUnionV0, ScSymbol, ScVec,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_parse_typed_keys() {
+        let spec = Spec::new(vec![ScSpecEntry::UdtUnionV0(ScSpecUdtUnionV0 {
+            doc: "".try_into().unwrap(),
+            lib: "".try_into().unwrap(),
+            name: "DataKey".try_into().unwrap(),
+            cases: vec![
+                ScSpecUdtUnionCaseV0::VoidV0(ScSpecUdtUnionCaseVoidV0 {
+                    doc: "".try_into().unwrap(),
+                    name: "Admin".try_into().unwrap(),
+                }),
+                ScSpecUdtUnionCaseV0::TupleV0(ScSpecUdtUnionCaseTupleV0 {
+                    doc: "".try_into().unwrap(),
+                    name: "Balance".try_into().unwrap(),
+                    type_: vec![ScSpecTypeDef::U32].try_into().unwrap(),
+                }),
+            ]
+            .try_into()
+            .unwrap(),
+        })]);
+        let symbol = |s: &str| ScVal::Symbol(ScSymbol(s.try_into().unwrap()));
+
+        let type_ = key_type(None).unwrap();
+        assert_eq!(parse("COUNTER", &type_, &spec).unwrap(), symbol("COUNTER"));
+        let type_ = key_type(Some("u32")).unwrap();
+        assert_eq!(parse("7", &type_, &spec).unwrap(), ScVal::U32(7));
+
+        let type_ = key_type(Some("DataKey")).unwrap();
+        assert_eq!(
+            parse(r#"{"Balance":7}"#, &type_, &spec).unwrap(),
+            ScVal::Vec(Some(
+                ScVec::try_from(vec![symbol("Balance"), ScVal::U32(7)]).unwrap()
+            ))
+        );
+        assert_eq!(
+            parse("Admin", &type_, &spec).unwrap(),
+            ScVal::Vec(Some(ScVec::try_from(vec![symbol("Admin")]).unwrap()))
+        );
+        assert!(parse("{}", &key_type(Some("Missing")).unwrap(), &spec).is_err());
+    }
+}
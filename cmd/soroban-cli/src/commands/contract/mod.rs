@@ -1,24 +1,46 @@
+use crate::commands::global;
+
+pub mod batch;
 pub mod bindings;
 pub mod build;
 pub mod bump;
 pub mod deploy;
 pub mod fetch;
+pub mod footprint;
 pub mod inspect;
 pub mod install;
 pub mod invoke;
+pub mod key;
 pub mod optimize;
 pub mod read;
 pub mod restore;
+pub mod storage;
 
 #[derive(Debug, clap::Subcommand)]
 pub enum Cmd {
+    /// Run a manifest of install, deploy, invoke, bump and restore steps, one after the other
+    ///
+    /// The manifest, in YAML or JSON, has a list of `steps`, each of which runs one of the
+    /// commands with its arguments, as a single string or a list. A step with a `name` makes its
+    /// output, e.g. the ID of a deployed contract, available to later steps as `${name}`:
+    ///
+    ///     steps:
+    ///       - name: token
+    ///         deploy: --wasm token.wasm
+    ///       - invoke: --id ${token} -- initialize --admin alice --name Token
+    ///
+    /// The network, source account and config options of the batch apply to every step that
+    /// doesn't set its own. Against a network, the sequence number of the source account is only
+    /// fetched once and then counted locally.
+    Batch(batch::Cmd),
+
     /// Generate code client bindings for a contract
     #[command(subcommand)]
     Bindings(bindings::Cmd),
 
     Build(build::Cmd),
 
-    /// Extend the expiry ledger of a contract-data ledger entry
+    /// Extend the expiry ledger of contract-data ledger entries
     Bump(bump::Cmd),
 
     /// Deploy a contract
@@ -49,12 +71,20 @@ pub enum Cmd {
     /// Print the current value of a contract-data ledger entry
     Read(read::Cmd),
 
-    /// Restore an evicted value for a contract-data legder entry
+    /// Restore evicted values for contract-data ledger entries
     Restore(restore::Cmd),
+
+    /// List the instance, persistent and temporary storage of a contract, with the values decoded
+    /// and the ledger each entry expires after. Against a network, persistent and temporary
+    /// entries are looked up by keys derived from the contract spec
+    Storage(storage::Cmd),
 }
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
+    #[error(transparent)]
+    Batch(#[from] batch::Error),
+
     #[error(transparent)]
     Bindings(#[from] bindings::Error),
 
@@ -87,22 +117,27 @@ pub enum Error {
 
     #[error(transparent)]
     Restore(#[from] restore::Error),
+
+    #[error(transparent)]
+    Storage(#[from] storage::Error),
 }
 
 impl Cmd {
-    pub async fn run(&self) -> Result<(), Error> {
+    pub async fn run(&self, global_args: &global::Args) -> Result<(), Error> {
         match &self {
+            Cmd::Batch(batch) => batch.run(global_args).await?,
             Cmd::Bindings(bindings) => bindings.run()?,
             Cmd::Build(build) => build.run()?,
-            Cmd::Bump(bump) => bump.run().await?,
-            Cmd::Deploy(deploy) => deploy.run().await?,
-            Cmd::Inspect(inspect) => inspect.run()?,
-            Cmd::Install(install) => install.run().await?,
-            Cmd::Invoke(invoke) => invoke.run().await?,
+            Cmd::Bump(bump) => bump.run(global_args).await?,
+            Cmd::Deploy(deploy) => deploy.run(global_args).await?,
+            Cmd::Inspect(inspect) => inspect.run(global_args)?,
+            Cmd::Install(install) => install.run(global_args).await?,
+            Cmd::Invoke(invoke) => invoke.run(global_args).await?,
             Cmd::Optimize(optimize) => optimize.run()?,
-            Cmd::Fetch(fetch) => fetch.run().await?,
-            Cmd::Read(read) => read.run().await?,
-            Cmd::Restore(restore) => restore.run().await?,
+            Cmd::Fetch(fetch) => fetch.run(global_args).await?,
+            Cmd::Read(read) => read.run(global_args).await?,
+            Cmd::Restore(restore) => restore.run(global_args).await?,
+            Cmd::Storage(storage) => storage.run(global_args).await?,
         }
         Ok(())
     }
@@ -2,9 +2,11 @@ use std::{
     convert::Into,
     fmt::Debug,
     io::{self, stdout},
+    path::PathBuf,
 };
 
 use clap::{command, Parser, ValueEnum};
+use serde_json::json;
 use soroban_env_host::{
     xdr::{
         self, ContractDataDurability, ContractDataEntry, ContractDataEntryBody,
@@ -13,12 +15,14 @@ use soroban_env_host::{
     },
     HostError,
 };
+use soroban_spec_tools::Spec;
 
 use crate::{
     commands::config,
-    commands::contract::Durability,
+    commands::contract::{key, Durability},
+    commands::global,
     rpc::{self, Client},
-    utils,
+    utils, wasm,
 };
 
 #[derive(Parser, Debug, Clone)]
@@ -27,15 +31,24 @@ pub struct Cmd {
     /// Contract ID to invoke
     #[arg(long = "id")]
     contract_id: String,
-    /// Storage key (symbols only)
+    /// Storage key (symbols, unless `--key-type` is set)
     #[arg(long = "key", conflicts_with = "key_xdr")]
     key: Option<String>,
+    /// Type of `--key`: the name of a type defined in the contract, with the key given as JSON,
+    /// e.g. `--key-type DataKey --key '{"Balance":"G..."}'`, or one of sym, str, addr, bool, u32,
+    /// i32, u64, i64, u128, i128, u256, i256 and bytes
+    #[arg(long, requires = "key")]
+    key_type: Option<String>,
     /// Storage key (base64-encoded XDR ScVal)
     #[arg(long = "key-xdr", conflicts_with = "key")]
     key_xdr: Option<String>,
     /// Storage entry durability
     #[arg(long, value_enum)]
     durability: Option<Durability>,
+    /// Wasm file of the contract, whose spec is used to decode keys and values instead of the spec
+    /// of the deployed contract
+    #[arg(long)]
+    wasm: Option<PathBuf>,
 
     /// Type of output to generate
     #[arg(long, value_enum, default_value("string"))]
@@ -85,20 +98,28 @@ pub enum Error {
     CannotPrintFlush { error: io::Error },
     #[error(transparent)]
     Config(#[from] config::Error),
+    #[error(transparent)]
+    Key(#[from] key::Error),
+    #[error(transparent)]
+    Wasm(#[from] wasm::Error),
     #[error("either `--key` or `--key-xdr` are required when querying a network")]
     KeyIsRequired,
     #[error(transparent)]
     Rpc(#[from] rpc::Error),
     #[error("xdr processing error: {0}")]
     Xdr(#[from] XdrError),
-    #[error(transparent)]
-    // TODO: the Display impl of host errors is pretty user-unfriendly
-    //       (it just calls Debug). I think we can do better than that
-    Host(#[from] HostError),
+    #[error("{0}")]
+    Failed(Box<utils::host_error::Report>),
+}
+
+impl From<HostError> for Error {
+    fn from(error: HostError) -> Self {
+        Error::Failed(Box::new(utils::host_error::Report::from_host_error(&error)))
+    }
 }
 
 impl Cmd {
-    pub async fn run(&self) -> Result<(), Error> {
+    pub async fn run(&self, global_args: &global::Args) -> Result<(), Error> {
         let contract_id: [u8; 32] =
             utils::contract_id_from_str(&self.contract_id).map_err(|e| {
                 Error::CannotParseContractId {
@@ -106,15 +127,15 @@ impl Cmd {
                     error: e,
                 }
             })?;
-        let key = if let Some(key) = &self.key {
-            Some(
-                soroban_spec_tools::from_string_primitive(key, &ScSpecTypeDef::Symbol).map_err(
-                    |e| Error::CannotParseKey {
-                        key: key.clone(),
-                        error: e,
-                    },
-                )?,
-            )
+        let key_type = self
+            .key
+            .as_ref()
+            .map(|_| key::key_type(self.key_type.as_deref()))
+            .transpose()?;
+        let decode = global_args.is_json() || self.output != Output::Xdr;
+        let spec = self.spec(contract_id, key_type.as_ref(), decode).await?;
+        let key = if let (Some(key), Some(key_type)) = (&self.key, &key_type) {
+            Some(key::parse(key, key_type, &spec)?)
         } else if let Some(key) = &self.key_xdr {
             Some(
                 ScVal::from_xdr_base64(key).map_err(|e| Error::CannotParseXdrKey {
@@ -131,7 +152,44 @@ impl Cmd {
         } else {
             self.run_against_rpc_server(contract_id, key).await?
         };
-        self.output_entries(&entries)
+        if global_args.is_json() {
+            return output_json(&entries, &spec);
+        }
+        self.output_entries(&entries, &spec)
+    }
+
+    /// Spec of the contract, used to parse a `--key` of a type defined in the contract, and to
+    /// decode keys and values as the types defined in the contract when `decode` is set, like
+    /// `invoke` does for results. It's only looked up when needed for either. Failing to get it is
+    /// an error when parsing the key needs it; otherwise, e.g. for the Stellar Asset Contract,
+    /// keys and values are decoded as plain vectors and maps.
+    async fn spec(
+        &self,
+        contract_id: [u8; 32],
+        key_type: Option<&ScSpecTypeDef>,
+        decode: bool,
+    ) -> Result<Spec, Error> {
+        let key_type = key_type.filter(|type_| matches!(type_, ScSpecTypeDef::Udt(_)));
+        if key_type.is_none() && !decode {
+            return Ok(Spec::default());
+        }
+        if let Some(wasm) = &self.wasm {
+            let spec = wasm::Args { wasm: wasm.clone() }.parse()?.spec;
+            return Ok(Spec::new(spec));
+        }
+        if let Some(key_type) = key_type {
+            return Ok(key::spec(key_type, contract_id, &self.config).await?);
+        }
+        let spec = if self.config.is_no_network() {
+            utils::get_contract_spec_from_state(&self.config.get_state()?, contract_id).ok()
+        } else {
+            let network = self.config.get_network()?;
+            Client::new(&network.rpc_url)?
+                .get_remote_contract_spec(&contract_id)
+                .await
+                .ok()
+        };
+        Ok(spec.map(Spec::new).unwrap_or_default())
     }
 
     async fn run_against_rpc_server(
@@ -245,49 +303,31 @@ impl Cmd {
             .collect::<Vec<_>>())
     }
 
-    fn output_entries(&self, raw_entries: &[(LedgerKey, LedgerEntryData)]) -> Result<(), Error> {
-        let entries = raw_entries
-            .iter()
-            .filter_map(|(_k, data)| {
-                if let LedgerEntryData::ContractData(ContractDataEntry {
-                    key,
-                    body: ContractDataEntryBody::DataEntry(ContractDataEntryData { val, .. }),
-                    ..
-                }) = &data
-                {
-                    Some((key.clone(), val.clone()))
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>();
+    fn output_entries(
+        &self,
+        raw_entries: &[(LedgerKey, LedgerEntryData)],
+        spec: &Spec,
+    ) -> Result<(), Error> {
+        let entries = data_entries(raw_entries);
 
         let mut out = csv::Writer::from_writer(stdout());
         for (key, val) in entries {
             let output = match self.output {
                 Output::String => [
-                    soroban_spec_tools::to_string(&key).map_err(|e| Error::CannotPrintResult {
-                        result: key.clone(),
-                        error: e,
-                    })?,
-                    soroban_spec_tools::to_string(&val).map_err(|e| Error::CannotPrintResult {
-                        result: val.clone(),
-                        error: e,
-                    })?,
-                ],
-                Output::Json => [
-                    serde_json::to_string_pretty(&key).map_err(|e| {
-                        Error::CannotPrintJsonResult {
+                    spec.to_string_inferred(&key)
+                        .map_err(|e| Error::CannotPrintResult {
                             result: key.clone(),
                             error: e,
-                        }
-                    })?,
-                    serde_json::to_string_pretty(&val).map_err(|e| {
-                        Error::CannotPrintJsonResult {
+                        })?,
+                    spec.to_string_inferred(&val)
+                        .map_err(|e| Error::CannotPrintResult {
                             result: val.clone(),
                             error: e,
-                        }
-                    })?,
+                        })?,
+                ],
+                Output::Json => [
+                    json_to_string_pretty(&key, spec)?,
+                    json_to_string_pretty(&val, spec)?,
                 ],
                 Output::Xdr => [key.to_xdr_base64()?, val.to_xdr_base64()?],
             };
@@ -300,3 +340,52 @@ impl Cmd {
         Ok(())
     }
 }
+
+fn data_entries(raw_entries: &[(LedgerKey, LedgerEntryData)]) -> Vec<(ScVal, ScVal)> {
+    raw_entries
+        .iter()
+        .filter_map(|(_k, data)| {
+            if let LedgerEntryData::ContractData(ContractDataEntry {
+                key,
+                body: ContractDataEntryBody::DataEntry(ContractDataEntryData { val, .. }),
+                ..
+            }) = &data
+            {
+                Some((key.clone(), val.clone()))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// The value decoded with the types of `spec`, as pretty-printed JSON
+fn json_to_string_pretty(val: &ScVal, spec: &Spec) -> Result<String, Error> {
+    let json = spec
+        .xdr_to_json_inferred(val)
+        .map_err(|e| Error::CannotPrintResult {
+            result: val.clone(),
+            error: e,
+        })?;
+    serde_json::to_string_pretty(&json).map_err(|e| Error::CannotPrintJsonResult {
+        result: val.clone(),
+        error: e,
+    })
+}
+
+/// Print all the entries as a single JSON object, for `--output json`
+fn output_json(raw_entries: &[(LedgerKey, LedgerEntryData)], spec: &Spec) -> Result<(), Error> {
+    let to_json = |val: &ScVal| {
+        spec.xdr_to_json_inferred(val)
+            .map_err(|e| Error::CannotPrintResult {
+                result: val.clone(),
+                error: e,
+            })
+    };
+    let entries = data_entries(raw_entries)
+        .into_iter()
+        .map(|(key, val)| Ok(json!({ "key": to_json(&key)?, "value": to_json(&val)? })))
+        .collect::<Result<Vec<_>, Error>>()?;
+    println!("{:#}", json!({ "entries": entries }));
+    Ok(())
+}
@@ -5,40 +5,65 @@ use std::{
 };
 
 use clap::{command, Parser};
+use serde_json::json;
 use soroban_env_host::xdr::{
     ContractCodeEntry, ContractDataDurability, ContractDataEntry, ContractEntryBodyType,
     Error as XdrError, ExtensionPoint, Hash, LedgerEntry, LedgerEntryChange, LedgerEntryData,
     LedgerFootprint, LedgerKey, LedgerKeyContractData, Memo, MuxedAccount, Operation,
-    OperationBody, Preconditions, ReadXdr, RestoreFootprintOp, ScAddress, ScSpecTypeDef, ScVal,
-    SequenceNumber, SorobanResources, SorobanTransactionData, Transaction, TransactionExt,
-    TransactionMeta, TransactionMetaV3, Uint256,
+    OperationBody, Preconditions, ReadXdr, RestoreFootprintOp, ScAddress, ScVal, SequenceNumber,
+    SorobanResources, SorobanTransactionData, Transaction, TransactionExt, TransactionMeta,
+    TransactionMetaV3, Uint256,
 };
 use stellar_strkey::DecodeError;
 
 use crate::{
-    commands::config::{self, locator},
+    commands::{
+        config::{self, locator},
+        contract::{footprint, key},
+        global,
+        txn_result::{self, Applied, TxnResult},
+    },
     rpc::{self, Client},
-    utils, wasm, Pwd,
+    signer::{self, Signer},
+    utils::{self, archival},
+    wasm, Pwd,
 };
 
 #[derive(Parser, Debug, Clone)]
 #[group(skip)]
 pub struct Cmd {
     /// Contract ID to which owns the data entries
-    #[arg(long = "id", required_unless_present = "wasm")]
+    #[arg(long = "id", required_unless_present_any = ["wasm", "keys_file"])]
     contract_id: Option<String>,
-    /// Storage key (symbols only)
+    /// Storage key (symbols, unless `--key-type` is set)
     #[arg(
         long = "key",
-        required_unless_present = "key_xdr",
-        required_unless_present = "wasm"
+        required_unless_present_any = [
+            "key_xdr",
+            "wasm",
+            "keys_file",
+            "all",
+            "CONTRACT_FN_AND_ARGS"
+        ],
+        requires = "contract_id"
     )]
     key: Vec<String>,
+    /// Type of `--key`: the name of a type defined in the contract, with the key given as JSON,
+    /// e.g. `--key-type DataKey --key '{"Balance":"G..."}'`, or one of sym, str, addr, bool, u32,
+    /// i32, u64, i64, u128, i128, u256, i256 and bytes
+    #[arg(long, requires = "key")]
+    key_type: Option<String>,
     /// Storage key (base64-encoded XDR)
     #[arg(
         long = "key-xdr",
-        required_unless_present = "key",
-        required_unless_present = "wasm"
+        required_unless_present_any = [
+            "key",
+            "wasm",
+            "keys_file",
+            "all",
+            "CONTRACT_FN_AND_ARGS"
+        ],
+        requires = "contract_id"
     )]
     key_xdr: Vec<String>,
     /// Path to Wasm file of contract code to restore
@@ -49,9 +74,11 @@ pub struct Cmd {
         conflicts_with = "contract_id"
     )]
     wasm: Option<PathBuf>,
+    #[command(flatten)]
+    footprint: footprint::Args,
 
     #[command(flatten)]
-    config: config::Args,
+    pub config: config::Args,
     #[command(flatten)]
     pub fee: crate::fee::Args,
 }
@@ -84,7 +111,13 @@ pub enum Error {
     CannotParseContractId(String, DecodeError),
     #[error(transparent)]
     Config(#[from] config::Error),
-    #[error("either `--key` or `--key-xdr` are required")]
+    #[error(transparent)]
+    Key(#[from] key::Error),
+    #[error(transparent)]
+    Footprint(#[from] footprint::Error),
+    #[error(
+        "no entries, pass `--key`, `--key-xdr`, `--wasm`, `--keys-file`, `--all` or an invocation"
+    )]
     KeyIsRequired,
     #[error("xdr processing error: {0}")]
     Xdr(#[from] XdrError),
@@ -97,45 +130,79 @@ pub enum Error {
     #[error(transparent)]
     Rpc(#[from] rpc::Error),
     #[error(transparent)]
+    Signer(#[from] signer::Error),
+    #[error(transparent)]
     Wasm(#[from] wasm::Error),
+    #[error(transparent)]
+    TxnResult(#[from] txn_result::Error),
+    #[error(transparent)]
+    Fee(#[from] crate::fee::Error),
 }
 
 impl Cmd {
     #[allow(clippy::too_many_lines)]
-    pub async fn run(&self) -> Result<(), Error> {
-        let expiration_ledger_seq = if self.config.is_no_network() {
-            self.run_in_sandbox()?
-        } else {
-            self.run_against_rpc_server().await?
-        };
+    pub async fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        let res = self.run_and_get_expiration_ledger().await?;
 
-        println!("New expiration ledger: {expiration_ledger_seq}");
+        if global_args.is_json() {
+            println!(
+                "{:#}",
+                res.to_json(|seq| json!({ "expiration_ledger": seq }))?
+            );
+            return Ok(());
+        }
+        match res {
+            TxnResult::Res(expiration_ledger_seq)
+            | TxnResult::Sent(expiration_ledger_seq, _)
+            | TxnResult::Sandbox(expiration_ledger_seq, _) => {
+                println!("New expiration ledger: {expiration_ledger_seq}");
+            }
+            TxnResult::Txn(_) | TxnResult::Hash(_) => println!("{res}"),
+        }
 
         Ok(())
     }
 
-    async fn run_against_rpc_server(&self) -> Result<u32, Error> {
+    pub async fn run_and_get_expiration_ledger(&self) -> Result<TxnResult<u32>, Error> {
+        let entry_keys = self.entry_keys().await?;
+        if self.config.is_no_network() {
+            self.fee.check_sandbox()?;
+            let footprint = LedgerFootprint {
+                read_only: vec![].try_into()?,
+                read_write: entry_keys.clone().try_into()?,
+            };
+            Ok(TxnResult::Sandbox(
+                self.run_in_sandbox(&entry_keys)?,
+                Box::new(Applied::new(footprint, &[])),
+            ))
+        } else {
+            self.run_against_rpc_server(entry_keys).await
+        }
+    }
+
+    async fn run_against_rpc_server(
+        &self,
+        entry_keys: Vec<LedgerKey>,
+    ) -> Result<TxnResult<u32>, Error> {
         let network = self.config.get_network()?;
         tracing::trace!(?network);
-        let entry_keys = if let Some(wasm) = &self.wasm {
-            vec![crate::wasm::Args { wasm: wasm.clone() }.try_into()?]
-        } else {
-            let contract_id = self.contract_id()?;
-            self.parse_keys(contract_id)?
-        };
         let network = &self.config.get_network()?;
-        let client = Client::new(&network.rpc_url)?;
-        let key = self.config.key_pair()?;
+        let client = Client::new(&network.rpc_url)?.with_wait(self.fee.wait.wait()?);
+        footprint::check_limit(&client, &entry_keys, true).await?;
+        let key = self.config.signer()?;
+        let public_key = key.public_key()?;
 
         // Get the account sequence number
-        let public_strkey = stellar_strkey::ed25519::PublicKey(key.public.to_bytes()).to_string();
-        let account_details = client.get_account(&public_strkey).await?;
-        let sequence: i64 = account_details.seq_num.into();
+        let public_strkey = stellar_strkey::ed25519::PublicKey(public_key.to_bytes()).to_string();
+        let sequence = self
+            .fee
+            .next_sequence(&client, &network.network_passphrase, &public_strkey)
+            .await?;
 
         let tx = Transaction {
-            source_account: MuxedAccount::Ed25519(Uint256(key.public.to_bytes())),
+            source_account: MuxedAccount::Ed25519(Uint256(public_key.to_bytes())),
             fee: self.fee.fee,
-            seq_num: SequenceNumber(sequence + 1),
+            seq_num: SequenceNumber(sequence),
             cond: Preconditions::None,
             memo: Memo::None,
             operations: vec![Operation {
@@ -161,64 +228,140 @@ impl Cmd {
             }),
         };
 
-        let (result, meta, events) = client
-            .prepare_and_send_transaction(&tx, &key, &network.network_passphrase, None)
+        if self.fee.build_only {
+            return Ok(TxnResult::Txn(client.prepare_transaction(&tx, None).await?));
+        }
+        if self.fee.no_wait {
+            let tx = client
+                .prepare_and_sign_transaction(
+                    &tx,
+                    &key,
+                    || Ok(vec![]),
+                    &network.network_passphrase,
+                    None,
+                )
+                .await?;
+            return Ok(TxnResult::Hash(client.submit_transaction(&tx).await?));
+        }
+
+        let sent = client
+            .prepare_and_send_transaction(
+                &tx,
+                &key,
+                || Ok(vec![]),
+                &network.network_passphrase,
+                None,
+            )
             .await?;
 
-        tracing::debug!(?result);
-        tracing::debug!(?meta);
-        if !events.is_empty() {
-            tracing::debug!(?events);
+        tracing::debug!(result = ?sent.result);
+        tracing::debug!(meta = ?sent.meta);
+        if !sent.events.is_empty() {
+            tracing::debug!(events = ?sent.events);
         }
 
         // The transaction from core will succeed regardless of whether it actually found &
-        // restored the entry, so we have to inspect the result meta to tell if it worked or not.
-        let TransactionMeta::V3(TransactionMetaV3 { operations, .. }) = meta else {
+        // restored the entries, so we have to inspect the result meta to tell if it worked or not.
+        let TransactionMeta::V3(TransactionMetaV3 { operations, .. }) = &sent.meta else {
             return Err(Error::LedgerEntryNotFound);
         };
-
-        // Simply check if there is exactly one entry here. We only support bumping a single
-        // entry via this command (which we should fix separately, but).
-        if operations.len() == 0 {
+        let Some(operation) = operations.first() else {
             return Err(Error::LedgerEntryNotFound);
-        }
+        };
 
-        if operations[0].changes.len() != 1 {
-            return Err(Error::LedgerEntryNotFound);
-        }
-        match operations[0].changes[0] {
-            LedgerEntryChange::Updated(LedgerEntry {
-                data:
-                    LedgerEntryData::ContractData(ContractDataEntry {
-                        expiration_ledger_seq,
-                        ..
-                    })
-                    | LedgerEntryData::ContractCode(ContractCodeEntry {
-                        expiration_ledger_seq,
-                        ..
-                    }),
-                ..
+        // Every restored entry shows up as a single change. Entries that were missing or still
+        // live are left out, so report the earliest expiration
+        let expiration_ledger_seq = operation
+            .changes
+            .iter()
+            .filter_map(|change| match *change {
+                LedgerEntryChange::Updated(LedgerEntry {
+                    data:
+                        LedgerEntryData::ContractData(ContractDataEntry {
+                            expiration_ledger_seq,
+                            ..
+                        })
+                        | LedgerEntryData::ContractCode(ContractCodeEntry {
+                            expiration_ledger_seq,
+                            ..
+                        }),
+                    ..
+                })
+                | LedgerEntryChange::Created(LedgerEntry {
+                    data:
+                        LedgerEntryData::ContractData(ContractDataEntry {
+                            expiration_ledger_seq,
+                            ..
+                        })
+                        | LedgerEntryData::ContractCode(ContractCodeEntry {
+                            expiration_ledger_seq,
+                            ..
+                        }),
+                    ..
+                }) => Some(expiration_ledger_seq),
+                _ => None,
             })
-            | LedgerEntryChange::Created(LedgerEntry {
-                data:
-                    LedgerEntryData::ContractData(ContractDataEntry {
-                        expiration_ledger_seq,
-                        ..
-                    })
-                    | LedgerEntryData::ContractCode(ContractCodeEntry {
-                        expiration_ledger_seq,
-                        ..
-                    }),
-                ..
-            }) => Ok(expiration_ledger_seq),
-            _ => Err(Error::LedgerEntryNotFound),
+            .min()
+            .ok_or(Error::LedgerEntryNotFound)?;
+        Ok(TxnResult::Sent(expiration_ledger_seq, Box::new(sent)))
+    }
+
+    fn run_in_sandbox(&self, entry_keys: &[LedgerKey]) -> Result<u32, Error> {
+        let mut state = self.config.get_state()?;
+        let next_ledger = archival::next_ledger(&state);
+        let new_expiration_ledger_seq = archival::new_persistent_expiration(&state);
+
+        // Like on the network, entries that are still live are left as they are and keys without
+        // an entry are skipped
+        let mut expiration_ledger_seq = None;
+        for (k, entry) in &mut state.ledger_entries {
+            if !entry_keys.contains(k) {
+                continue;
+            }
+            if archival::is_archived(entry, next_ledger) {
+                archival::set_expiration_ledger_seq(entry, new_expiration_ledger_seq);
+            }
+            if let Some(seq) = archival::expiration_ledger_seq(entry) {
+                expiration_ledger_seq =
+                    Some(expiration_ledger_seq.map_or(seq, |min: u32| min.min(seq)));
+            }
         }
+
+        self.config.set_state(&mut state)?;
+        expiration_ledger_seq.ok_or(Error::LedgerEntryNotFound)
     }
 
-    fn run_in_sandbox(&self) -> Result<u32, Error> {
-        // TODO: Implement this. This means we need to store ledger entries somewhere, and handle
-        // eviction, and restoration with that evicted state store.
-        todo!("Restoring ledger entries is not supported in the local sandbox mode");
+    /// Keys of the entries to restore, from all the options, in a single footprint
+    async fn entry_keys(&self) -> Result<Vec<LedgerKey>, Error> {
+        let contract_id = if self.contract_id.is_some() {
+            Some(self.contract_id()?)
+        } else {
+            None
+        };
+        let mut keys = self
+            .footprint
+            .keys(
+                contract_id,
+                Some(ContractDataDurability::Persistent),
+                &self.config,
+            )
+            .await?;
+        if let Some(wasm) = &self.wasm {
+            keys.push(crate::wasm::Args { wasm: wasm.clone() }.try_into()?);
+        }
+        if let Some(contract_id) = contract_id {
+            keys.extend(self.parse_keys(contract_id).await?);
+        }
+        keys.sort();
+        keys.dedup();
+
+        if keys.is_empty() {
+            if self.footprint.is_set() {
+                return Err(Error::LedgerEntryNotFound);
+            }
+            return Err(Error::KeyIsRequired);
+        }
+        Ok(keys)
     }
 
     fn contract_id(&self) -> Result<[u8; 32], Error> {
@@ -226,17 +369,14 @@ impl Cmd {
             .map_err(|e| Error::CannotParseContractId(self.contract_id.clone().unwrap(), e))
     }
 
-    fn parse_keys(&self, contract_id: [u8; 32]) -> Result<Vec<LedgerKey>, Error> {
+    async fn parse_keys(&self, contract_id: [u8; 32]) -> Result<Vec<LedgerKey>, Error> {
         let mut keys: Vec<ScVal> = vec![];
-        for key in &self.key {
-            keys.push(
-                soroban_spec_tools::from_string_primitive(key, &ScSpecTypeDef::Symbol).map_err(
-                    |e| Error::CannotParseKey {
-                        key: key.clone(),
-                        error: e,
-                    },
-                )?,
-            );
+        if !self.key.is_empty() {
+            let key_type = key::key_type(self.key_type.as_deref())?;
+            let spec = key::spec(&key_type, contract_id, &self.config).await?;
+            for key in &self.key {
+                keys.push(key::parse(key, &key_type, &spec)?);
+            }
         }
         for key in &self.key_xdr {
             keys.push(
@@ -247,10 +387,6 @@ impl Cmd {
             );
         }
 
-        if keys.is_empty() {
-            return Err(Error::KeyIsRequired);
-        };
-
         Ok(keys
             .iter()
             .map(|key| {
@@ -0,0 +1,449 @@
+use std::collections::BTreeSet;
+
+use clap::{arg, command, Parser};
+use serde_json::json;
+use soroban_env_host::xdr::{
+    ContractDataDurability, ContractDataEntry, ContractDataEntryBody, ContractDataEntryData,
+    ContractEntryBodyType, Error as XdrError, Hash, LedgerEntryData, LedgerKey,
+    LedgerKeyContractData, ReadXdr, ScAddress, ScContractInstance, ScMapEntry, ScSpecEntry,
+    ScSpecTypeDef, ScSpecUdtEnumV0, ScSpecUdtUnionCaseTupleV0, ScSpecUdtUnionCaseV0,
+    ScSpecUdtUnionCaseVoidV0, ScSpecUdtUnionV0, ScSymbol, ScVal, ScVec, StringM,
+};
+use soroban_spec_tools::Spec;
+
+use crate::{
+    commands::{config, global},
+    rpc::{self, Client},
+    utils,
+};
+
+#[derive(Parser, Debug, Clone)]
+#[group(skip)]
+pub struct Cmd {
+    /// Contract ID to list the storage of
+    #[arg(long = "id")]
+    pub contract_id: String,
+    /// Storage key to look up when querying a network, in addition to the keys derived from the
+    /// contract spec (symbols only). Can be passed multiple times
+    #[arg(long = "key")]
+    pub key: Vec<String>,
+    /// Storage key to look up when querying a network, in addition to the keys derived from the
+    /// contract spec (base64-encoded XDR ScVal). Can be passed multiple times
+    #[arg(long = "key-xdr")]
+    pub key_xdr: Vec<String>,
+
+    #[command(flatten)]
+    pub config: config::Args,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("cannot parse contract ID {contract_id}: {error}")]
+    CannotParseContractId {
+        contract_id: String,
+        error: stellar_strkey::DecodeError,
+    },
+    #[error("parsing key {key}: {error}")]
+    CannotParseKey {
+        key: String,
+        error: soroban_spec_tools::Error,
+    },
+    #[error("parsing XDR key {key}: {error}")]
+    CannotParseXdrKey { key: String, error: XdrError },
+    #[error("contract {0} not found")]
+    ContractNotFound(String),
+    #[error(transparent)]
+    Config(#[from] config::Error),
+    #[error(transparent)]
+    Rpc(#[from] rpc::Error),
+    #[error(transparent)]
+    Xdr(#[from] XdrError),
+    #[error(transparent)]
+    Spec(#[from] soroban_spec_tools::Error),
+}
+
+/// A value in the storage of a contract
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Entry {
+    storage: Storage,
+    key: ScVal,
+    val: ScVal,
+    expiration_ledger: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Storage {
+    Instance,
+    Persistent,
+    Temporary,
+}
+
+impl Storage {
+    fn name(self) -> &'static str {
+        match self {
+            Storage::Instance => "instance",
+            Storage::Persistent => "persistent",
+            Storage::Temporary => "temporary",
+        }
+    }
+}
+
+/// Maximum number of keys derived from a single union case, whose arguments are combinations of
+/// the values found in storage
+const MAX_KEYS_PER_CASE: usize = 1000;
+
+impl Cmd {
+    pub async fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        let contract_id = utils::contract_id_from_str(&self.contract_id).map_err(|e| {
+            Error::CannotParseContractId {
+                contract_id: self.contract_id.clone(),
+                error: e,
+            }
+        })?;
+        let (mut entries, spec) = if self.config.is_no_network() {
+            self.run_in_sandbox(contract_id)?
+        } else {
+            self.run_against_rpc_server(contract_id).await?
+        };
+        entries.sort();
+
+        if global_args.is_json() {
+            let entries = entries
+                .iter()
+                .map(|entry| {
+                    Ok(json!({
+                        "storage": entry.storage.name(),
+                        "key": spec.xdr_to_json_inferred(&entry.key)?,
+                        "value": spec.xdr_to_json_inferred(&entry.val)?,
+                        "expiration_ledger": entry.expiration_ledger,
+                    }))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            println!("{:#}", json!({ "entries": entries }));
+        } else {
+            for entry in &entries {
+                println!(
+                    "{} {}: {}, expires after ledger {}",
+                    entry.storage.name(),
+                    spec.to_string_inferred(&entry.key)?,
+                    spec.xdr_to_json_inferred(&entry.val)?,
+                    entry.expiration_ledger,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn run_in_sandbox(&self, contract_id: [u8; 32]) -> Result<(Vec<Entry>, Spec), Error> {
+        let state = self.config.get_state()?;
+        let contract = ScAddress::Contract(Hash(contract_id));
+        if !state
+            .ledger_entries
+            .iter()
+            .any(|(key, _)| is_instance_key(key, &contract))
+        {
+            return Err(Error::ContractNotFound(self.contract_id.clone()));
+        }
+        let entries = state
+            .ledger_entries
+            .iter()
+            .filter_map(|(_, entry)| match &entry.data {
+                LedgerEntryData::ContractData(data) if data.contract == contract => Some(data),
+                _ => None,
+            })
+            .flat_map(storage_entries)
+            .collect::<Vec<_>>();
+        let spec = utils::get_contract_spec_from_state(&state, contract_id)
+            .map(Spec::new)
+            .unwrap_or_default();
+        Ok((entries, spec))
+    }
+
+    /// The RPC server can't list the entries of a contract, so entries are looked up by key: the
+    /// keys passed with `--key` and `--key-xdr`, and the keys derived from the contract spec
+    async fn run_against_rpc_server(
+        &self,
+        contract_id: [u8; 32],
+    ) -> Result<(Vec<Entry>, Spec), Error> {
+        let network = self.config.get_network()?;
+        let client = Client::new(&network.rpc_url)?;
+        let instance = client.get_contract_data(&contract_id).await?;
+        let spec = client
+            .get_remote_contract_spec(&contract_id)
+            .await
+            .map(Spec::new)
+            .unwrap_or_default();
+
+        let mut entries = storage_entries(&instance);
+        let mut candidates = self.parse_keys()?;
+        let mut probed = BTreeSet::new();
+        // Keys derived in the first round can only take arguments from the instance storage, and
+        // in the second round from the entries found in the first round too
+        for _ in 0..2 {
+            let mut values = BTreeSet::new();
+            for entry in &entries {
+                collect_values(&entry.key, &mut values);
+                collect_values(&entry.val, &mut values);
+            }
+            candidates.extend(key_candidates(&spec, &values));
+            let keys = candidates
+                .drain(..)
+                .filter(|key| probed.insert(key.clone()))
+                .flat_map(|key| {
+                    [
+                        ContractDataDurability::Persistent,
+                        ContractDataDurability::Temporary,
+                    ]
+                    .map(|durability| {
+                        LedgerKey::ContractData(LedgerKeyContractData {
+                            contract: ScAddress::Contract(Hash(contract_id)),
+                            key: key.clone(),
+                            durability,
+                            body_type: ContractEntryBodyType::DataEntry,
+                        })
+                    })
+                })
+                .collect::<Vec<_>>();
+            for chunk in keys.chunks(rpc::LEDGER_KEYS_PER_REQUEST) {
+                for result in client
+                    .get_ledger_entries(chunk.to_vec())
+                    .await?
+                    .entries
+                    .unwrap_or_default()
+                {
+                    if let LedgerEntryData::ContractData(data) =
+                        LedgerEntryData::from_xdr_base64(&result.xdr)?
+                    {
+                        entries.extend(storage_entries(&data));
+                    }
+                }
+            }
+        }
+        eprintln!(
+            "Listed the instance storage, and the persistent and temporary entries found looking \
+            up {} keys derived from the contract spec or passed with `--key`. Entries with other \
+            keys aren't listed.",
+            probed.len()
+        );
+        Ok((entries, spec))
+    }
+
+    fn parse_keys(&self) -> Result<Vec<ScVal>, Error> {
+        let mut keys = vec![];
+        for key in &self.key {
+            keys.push(
+                soroban_spec_tools::from_string_primitive(key, &ScSpecTypeDef::Symbol).map_err(
+                    |e| Error::CannotParseKey {
+                        key: key.clone(),
+                        error: e,
+                    },
+                )?,
+            );
+        }
+        for key in &self.key_xdr {
+            keys.push(
+                ScVal::from_xdr_base64(key).map_err(|e| Error::CannotParseXdrKey {
+                    key: key.clone(),
+                    error: e,
+                })?,
+            );
+        }
+        Ok(keys)
+    }
+}
+
+fn is_instance_key(key: &LedgerKey, contract: &ScAddress) -> bool {
+    matches!(
+        key,
+        LedgerKey::ContractData(LedgerKeyContractData {
+            contract: c,
+            key: ScVal::LedgerKeyContractInstance,
+            ..
+        }) if c == contract
+    )
+}
+
+/// Storage entries of a contract data ledger entry: the entries of the instance storage for the
+/// contract instance, otherwise the entry itself
+fn storage_entries(data: &ContractDataEntry) -> Vec<Entry> {
+    let ContractDataEntry {
+        key,
+        durability,
+        body: ContractDataEntryBody::DataEntry(ContractDataEntryData { val, .. }),
+        expiration_ledger_seq,
+        ..
+    } = data
+    else {
+        return vec![];
+    };
+    if let ScVal::ContractInstance(ScContractInstance { storage, .. }) = val {
+        return storage
+            .iter()
+            .flat_map(|map| map.iter())
+            .map(|ScMapEntry { key, val }| Entry {
+                storage: Storage::Instance,
+                key: key.clone(),
+                val: val.clone(),
+                expiration_ledger: *expiration_ledger_seq,
+            })
+            .collect();
+    }
+    vec![Entry {
+        storage: match durability {
+            ContractDataDurability::Persistent => Storage::Persistent,
+            ContractDataDurability::Temporary => Storage::Temporary,
+        },
+        key: key.clone(),
+        val: val.clone(),
+        expiration_ledger: *expiration_ledger_seq,
+    }]
+}
+
+/// Add `val` and the values nested in it to `values`
+fn collect_values(val: &ScVal, values: &mut BTreeSet<ScVal>) {
+    if !values.insert(val.clone()) {
+        return;
+    }
+    match val {
+        ScVal::Vec(Some(vec)) => {
+            for val in vec.iter() {
+                collect_values(val, values);
+            }
+        }
+        ScVal::Map(Some(map)) => {
+            for ScMapEntry { key, val } in map.iter() {
+                collect_values(key, values);
+                collect_values(val, values);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Keys contracts are likely to store entries under, derived from the spec: the cases of the
+/// enums and unions it defines, e.g. `DataKey::Admin`, with the arguments of union cases taken
+/// from the `values` of the right type found in storage, e.g. `DataKey::Balance(admin)`
+fn key_candidates(spec: &Spec, values: &BTreeSet<ScVal>) -> Vec<ScVal> {
+    let mut keys = vec![];
+    for entry in spec.0.iter().flatten() {
+        match entry {
+            ScSpecEntry::UdtEnumV0(ScSpecUdtEnumV0 { cases, .. }) => {
+                keys.extend(cases.iter().map(|case| ScVal::U32(case.value)));
+            }
+            ScSpecEntry::UdtUnionV0(ScSpecUdtUnionV0 { cases, .. }) => {
+                for case in cases.iter() {
+                    match case {
+                        ScSpecUdtUnionCaseV0::VoidV0(ScSpecUdtUnionCaseVoidV0 { name, .. }) => {
+                            keys.extend(union_key(name, vec![]));
+                        }
+                        ScSpecUdtUnionCaseV0::TupleV0(ScSpecUdtUnionCaseTupleV0 {
+                            name,
+                            type_,
+                            ..
+                        }) => {
+                            let mut args = vec![vec![]];
+                            for type_ in type_.iter() {
+                                let matching = values
+                                    .iter()
+                                    .filter(|val| spec.is_of_type(val, type_))
+                                    .collect::<Vec<_>>();
+                                args = args
+                                    .iter()
+                                    .flat_map(|prefix: &Vec<ScVal>| {
+                                        matching.iter().map(move |&val| {
+                                            let mut args = prefix.clone();
+                                            args.push(val.clone());
+                                            args
+                                        })
+                                    })
+                                    .take(MAX_KEYS_PER_CASE)
+                                    .collect();
+                            }
+                            keys.extend(args.into_iter().filter_map(|args| union_key(name, args)));
+                        }
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    keys
+}
+
+/// Value of the union case `name` with arguments `args`
+fn union_key(name: &StringM<60>, args: Vec<ScVal>) -> Option<ScVal> {
+    let name = ScSymbol(name.to_string_lossy().as_bytes().try_into().ok()?);
+    let vec: ScVec = std::iter::once(ScVal::Symbol(name))
+        .chain(args)
+        .collect::<Vec<_>>()
+        .try_into()
+        .ok()?;
+    Some(ScVal::Vec(Some(vec)))
+}
+
+#[cfg(test)]
+mod tests {
+    use soroban_env_host::xdr::ScSpecUdtEnumCaseV0;
+
+    use super::*;
+
+    fn symbol(s: &str) -> ScVal {
+        ScVal::Symbol(ScSymbol(s.try_into().unwrap()))
+    }
+
+    fn vec(vals: Vec<ScVal>) -> ScVal {
+        ScVal::Vec(Some(vals.try_into().unwrap()))
+    }
+
+    #[test]
+    fn test_key_candidates() {
+        let spec = Spec::new(vec![
+            ScSpecEntry::UdtUnionV0(ScSpecUdtUnionV0 {
+                doc: "".try_into().unwrap(),
+                lib: "".try_into().unwrap(),
+                name: "DataKey".try_into().unwrap(),
+                cases: vec![
+                    ScSpecUdtUnionCaseV0::VoidV0(ScSpecUdtUnionCaseVoidV0 {
+                        doc: "".try_into().unwrap(),
+                        name: "Admin".try_into().unwrap(),
+                    }),
+                    ScSpecUdtUnionCaseV0::TupleV0(ScSpecUdtUnionCaseTupleV0 {
+                        doc: "".try_into().unwrap(),
+                        name: "Counter".try_into().unwrap(),
+                        type_: vec![ScSpecTypeDef::Symbol, ScSpecTypeDef::U32]
+                            .try_into()
+                            .unwrap(),
+                    }),
+                ]
+                .try_into()
+                .unwrap(),
+            }),
+            ScSpecEntry::UdtEnumV0(ScSpecUdtEnumV0 {
+                doc: "".try_into().unwrap(),
+                lib: "".try_into().unwrap(),
+                name: "Kind".try_into().unwrap(),
+                cases: vec![ScSpecUdtEnumCaseV0 {
+                    doc: "".try_into().unwrap(),
+                    name: "A".try_into().unwrap(),
+                    value: 7,
+                }]
+                .try_into()
+                .unwrap(),
+            }),
+        ]);
+        let mut values = BTreeSet::new();
+        collect_values(&vec(vec![symbol("a"), ScVal::U32(1)]), &mut values);
+        collect_values(&ScVal::U32(2), &mut values);
+
+        let keys = key_candidates(&spec, &values);
+        assert_eq!(
+            keys,
+            vec![
+                vec(vec![symbol("Admin")]),
+                vec(vec![symbol("Counter"), symbol("a"), ScVal::U32(1)]),
+                vec(vec![symbol("Counter"), symbol("a"), ScVal::U32(2)]),
+                ScVal::U32(7),
+            ]
+        );
+    }
+}
@@ -1,32 +1,72 @@
 use clap::{arg, command, Parser};
-use std::io;
-
-use soroban_env_host::xdr::{self, ReadXdr};
-
-use super::config::{events_file, locator, network};
+use serde_json::{json, Value};
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use soroban_env_host::xdr::{self, ReadXdr, ScSpecTypeDef, WriteXdr};
+use soroban_spec_tools::Spec;
+
+use super::{
+    config::{events_file, ledger_file, locator, network},
+    global,
+};
 use crate::{rpc, toid, utils};
 
+pub mod export;
+
 #[derive(Parser, Debug, Clone)]
 #[group(skip)]
 pub struct Cmd {
     /// The first ledger sequence number in the range to pull events (required
     /// if not in sandbox mode).
     /// https://developers.stellar.org/docs/encyclopedia/ledger-headers#ledger-sequence
-    #[arg(long, conflicts_with = "cursor", required_unless_present = "cursor")]
+    #[arg(
+        long,
+        conflicts_with = "cursor",
+        required_unless_present_any = ["cursor", "cursor_file"]
+    )]
     start_ledger: Option<u32>,
 
     /// The cursor corresponding to the start of the event range.
     #[arg(
         long,
         conflicts_with = "start_ledger",
-        required_unless_present = "start_ledger"
+        required_unless_present_any = ["start_ledger", "cursor_file"]
     )]
     cursor: Option<String>,
 
+    /// Keep polling for new events and print them as they arrive, until interrupted
+    #[arg(long)]
+    follow: bool,
+
+    /// Seconds to wait between polls for new events when following
+    #[arg(long, default_value = "5", value_parser = clap::value_parser!(u64).range(1..))]
+    poll_interval: u64,
+
+    /// File to persist the cursor of the last event printed to. If the file exists, events are
+    /// pulled starting after its cursor instead of `--start-ledger` or `--cursor`, so a restarted
+    /// watcher resumes where it left off.
+    #[arg(long)]
+    cursor_file: Option<PathBuf>,
+
     /// Output formatting options for event stream
     #[arg(long, value_enum, default_value = "pretty")]
     output: OutputFormat,
 
+    /// Write the events to `--export-file` in this format instead of printing
+    /// them, with their topics and value decoded. Events are appended to the
+    /// file, and events already in a SQLite database are skipped.
+    #[arg(long, value_enum, requires = "export_file")]
+    export: Option<export::Format>,
+
+    /// File to export the events to
+    #[arg(long, requires = "export")]
+    export_file: Option<PathBuf>,
+
     /// The maximum number of events to display (specify "0" to show all events
     /// when using sandbox, or to defer to the server-defined limit if using
     /// RPC).
@@ -37,9 +77,8 @@ pub struct Cmd {
     /// be passed multiple times, e.g. `--id abc --id def`, or passed with
     /// multiple parameters, e.g. `--id abd def`.
     ///
-    /// Though the specification supports multiple filter objects (i.e.
-    /// combinations of type, IDs, and topics), only one set can be specified on
-    /// the command-line today, though that set can have multiple IDs/topics.
+    /// To pull events matching one of several independent filter objects
+    /// (i.e. combinations of type, IDs, and topics), use `--filter` instead.
     #[arg(
         long = "id",
         num_args = 1..=6,
@@ -51,13 +90,20 @@ pub struct Cmd {
     /// topic filter can contain 1-4 different segment filters, separated by
     /// commas, with an asterisk (* character) indicating a wildcard segment.
     ///
+    /// Each segment is either a typed value, written as `<type>:<value>` with
+    /// one of the types `sym`, `str`, `addr`, `bool`, `u32`, `i32`, `u64`,
+    /// `i64`, `u128`, `i128`, `u256`, `i256` and `bytes` (hex), a JSON
+    /// `ScVal`, e.g. `{"u32":5}`, or a base64-encoded XDR `ScVal`. Commas
+    /// inside a JSON `ScVal` or a double-quoted value, e.g. `str:"a,b"`, don't
+    /// separate segments.
+    ///
     /// For example, this is one topic filter with two segments:
     ///
-    ///     --topic "AAAABQAAAAdDT1VOVEVSAA==,*"
+    ///     --topic "sym:transfer,*"
     ///
     /// This is two topic filters with one and two segments each:
     ///
-    ///     --topic "AAAABQAAAAdDT1VOVEVSAA==" --topic '*,*'
+    ///     --topic "sym:COUNTER" --topic '*,*'
     ///
     /// Note that all of these topic filters are combined with the contract IDs
     /// into a single filter (i.e. combination of type, IDs, and topics).
@@ -77,6 +123,32 @@ pub struct Cmd {
     )]
     event_type: rpc::EventType,
 
+    /// A filter object, written as space-separated `type=<TYPE>`, `id=<ID>` and
+    /// `topic=<TOPIC>` pairs, each of which is optional and can be repeated
+    /// except for the type. This parameter can be passed multiple times, and
+    /// events matching any of the filters are pulled.
+    ///
+    /// For example, to watch the transfers of a token and the swaps of an
+    /// exchange in one stream:
+    ///
+    ///     --filter "id=C... topic=sym:transfer,*,*" --filter "id=C... topic=sym:swap"
+    ///
+    /// Pairs are quoted like shell arguments, so a topic with spaces can be
+    /// written as `topic='str:"a b",*'`. It replaces `--type`, `--id` and
+    /// `--topic`, which make up a single filter object.
+    #[arg(
+        long = "filter",
+        conflicts_with_all = ["contract_ids", "topic_filters", "event_type"],
+        help_heading = "FILTERS"
+    )]
+    filters: Vec<String>,
+
+    /// Wasm file of the contract that emitted the events, used to decode their topics and value.
+    /// By default the spec of each contract is read from the sandbox ledger or fetched from the
+    /// network.
+    #[arg(long)]
+    wasm: Option<PathBuf>,
+
     #[command(flatten)]
     locator: locator::Args,
 
@@ -85,6 +157,9 @@ pub struct Cmd {
 
     #[command(flatten)]
     events_file: events_file::Args,
+
+    #[command(flatten)]
+    ledger_file: ledger_file::Args,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -108,6 +183,23 @@ pub enum Error {
         error: xdr::Error,
     },
 
+    #[error("invalid filter ({filter}): {error}")]
+    InvalidFilter { filter: String, error: String },
+
+    #[error("invalid segment ({segment}) in topic filter ({topic}): {error}")]
+    InvalidTypedSegment {
+        topic: String,
+        segment: String,
+        error: soroban_spec_tools::Error,
+    },
+
+    #[error("unknown type {type_} of segment ({segment}) in topic filter ({topic}), expected one of: {}", utils::primitive_type_names())]
+    UnknownSegmentType {
+        topic: String,
+        segment: String,
+        type_: String,
+    },
+
     #[error("cannot parse contract ID {contract_id}: {error}")]
     InvalidContractId {
         contract_id: String,
@@ -123,6 +215,9 @@ pub enum Error {
     #[error("invalid timestamp in event: {ts}")]
     InvalidTimestamp { ts: String },
 
+    #[error("cannot write cursor file {path}: {error}")]
+    CannotWriteCursorFile { path: String, error: io::Error },
+
     #[error("missing start_ledger and cursor")]
     MissingStartLedgerAndCursor,
     #[error("missing target")]
@@ -151,71 +246,115 @@ pub enum Error {
 
     #[error(transparent)]
     Locator(#[from] locator::Error),
+
+    #[error(transparent)]
+    Ledger(#[from] ledger_file::Error),
+
+    #[error(transparent)]
+    Spec(#[from] soroban_spec_tools::Error),
+
+    #[error(transparent)]
+    Export(#[from] export::Error),
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, clap::ValueEnum)]
 pub enum OutputFormat {
     /// Colorful, human-oriented console output
     Pretty,
-    /// Human-oriented console output without colors
+    /// Human-oriented console output without colors, with the topics and value decoded to JSON
+    /// rather than printed as raw `ScVal`s
     Plain,
     /// JSONified console output
     Json,
 }
 
 impl Cmd {
-    pub async fn run(&mut self) -> Result<(), Error> {
-        // Validate that topics are made up of segments.
-        for topic in &self.topic_filters {
-            for (i, segment) in topic.split(',').enumerate() {
-                if i > 4 {
-                    return Err(Error::InvalidTopicFilter {
-                        topic: topic.to_string(),
-                    });
+    pub async fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        let filters = self.filters()?;
+        let client = self.client().await?;
+        let mut start = self.start()?;
+        let mut specs = HashMap::new();
+        let mut exporter = match (self.export, &self.export_file) {
+            (Some(format), Some(path)) => Some(export::Exporter::open(format, path)?),
+            _ => None,
+        };
+        loop {
+            let response = match &client {
+                Some(client) => {
+                    self.run_against_rpc_server(client, start.clone(), &filters)
+                        .await
                 }
+                None => self.run_in_sandbox(&start, &filters),
+            }?;
+            self.add_specs(&mut specs, client.as_ref(), &response.events)
+                .await?;
+            if let Some(exporter) = &mut exporter {
+                exporter.export(&response.events, &specs)?;
+            } else {
+                self.print(global_args, &response, &specs)?;
+            }
 
-                if segment != "*" {
-                    if let Err(e) = xdr::ScVal::from_xdr_base64(segment) {
-                        return Err(Error::InvalidSegment {
-                            topic: topic.to_string(),
-                            segment: segment.to_string(),
-                            error: e,
-                        });
-                    }
+            let page_is_full = self.count != 0 && response.events.len() >= self.count;
+            if let Some(event) = response.events.last() {
+                // Continue after the last event, which is where a restarted watcher resumes too
+                start = rpc::EventStart::Cursor(event.paging_token.clone());
+                if let Some(path) = &self.cursor_file {
+                    write_cursor_file(path, &event.paging_token)?;
+                }
+            } else if response.latest_ledger > start_ledger(&start)? {
+                // Nothing matched up to the latest ledger, so don't scan those ledgers again
+                start = rpc::EventStart::Ledger(response.latest_ledger);
+            }
+            if !self.follow {
+                if exporter.is_some() {
+                    print_export_summary(global_args, &response);
                 }
+                return Ok(());
+            }
+            // Page through events that are already available without waiting
+            if !page_is_full {
+                tokio::time::sleep(Duration::from_secs(self.poll_interval)).await;
             }
         }
+    }
 
-        // Validate and normalize contract_ids
-        for id in &mut self.contract_ids {
-            // We parse the contract IDs to ensure they're the correct format, and padded out
-            // correctly.
-            //
-            // TODO: Once soroban-rpc supports passing these as a strkey, we should change to
-            // formatting these as C-strkeys.
-            *id = utils::contract_id_from_str(id)
-                .map(hex::encode)
-                .map_err(|e| Error::InvalidContractId {
-                    contract_id: id.clone(),
-                    error: e,
-                })?;
+    fn print(
+        &self,
+        global_args: &global::Args,
+        response: &rpc::GetEventsResponse,
+        specs: &HashMap<String, Spec>,
+    ) -> Result<(), Error> {
+        let spec = |event: &rpc::Event| specs.get(&event.contract_id).unwrap_or(&NO_SPEC);
+
+        if global_args.is_json() {
+            if self.follow {
+                // Stream one event per line so the output can be consumed as it arrives
+                for event in &response.events {
+                    println!("{}", event_to_json(event, spec(event))?);
+                }
+            } else {
+                let json = json!({
+                    "events": response
+                        .events
+                        .iter()
+                        .map(|event| event_to_json(event, spec(event)))
+                        .collect::<Result<Vec<_>, _>>()?,
+                    "latest_ledger": response.latest_ledger,
+                });
+                println!("{json:#}");
+            }
+            return Ok(());
         }
-
-        let response = if self.network.is_no_network() {
-            self.run_in_sandbox()
-        } else {
-            self.run_against_rpc_server().await
-        }?;
-
         for event in &response.events {
             match self.output {
                 // Should we pretty-print the JSON like we're doing here or just
                 // dump an event in raw JSON on each line? The latter is easier
                 // to consume programmatically.
                 OutputFormat::Json => {
+                    let json = event_to_json(event, spec(event))?;
                     println!(
                         "{}",
-                        serde_json::to_string_pretty(&event).map_err(|e| {
+                        serde_json::to_string_pretty(&json).map_err(|e| {
                             Error::InvalidJson {
                                 debug: format!("{event:#?}"),
                                 error: e,
@@ -223,37 +362,46 @@ impl Cmd {
                         })?,
                     );
                 }
-                OutputFormat::Plain => println!("{event}"),
-                OutputFormat::Pretty => event.pretty_print()?,
+                OutputFormat::Plain => println!("{}", event.to_string_with_spec(spec(event))?),
+                OutputFormat::Pretty => event.pretty_print(spec(event))?,
             }
         }
-        println!("Latest Ledger: {}", response.latest_ledger);
-
+        if !self.follow {
+            println!("Latest Ledger: {}", response.latest_ledger);
+        }
         Ok(())
     }
 
-    async fn run_against_rpc_server(&self) -> Result<rpc::GetEventsResponse, Error> {
-        let start = self.start()?;
+    /// Client of the RPC server to pull events from, or `None` in sandbox mode
+    async fn client(&self) -> Result<Option<rpc::Client>, Error> {
+        if self.network.is_no_network() {
+            return Ok(None);
+        }
         let network = self.network.get(&self.locator)?;
-
         let client = rpc::Client::new(&network.rpc_url)?;
         client
             .verify_network_passphrase(Some(&network.network_passphrase))
             .await?;
+        Ok(Some(client))
+    }
+
+    async fn run_against_rpc_server(
+        &self,
+        client: &rpc::Client,
+        start: rpc::EventStart,
+        filters: &[rpc::EventFilter],
+    ) -> Result<rpc::GetEventsResponse, Error> {
         client
-            .get_events(
-                start,
-                Some(self.event_type),
-                &self.contract_ids,
-                &self.topic_filters,
-                Some(self.count),
-            )
+            .get_events(start, filters, Some(self.count))
             .await
             .map_err(Error::Rpc)
     }
 
-    pub fn run_in_sandbox(&self) -> Result<rpc::GetEventsResponse, Error> {
-        let start = self.start()?;
+    pub fn run_in_sandbox(
+        &self,
+        start: &rpc::EventStart,
+        filters: &[rpc::EventFilter],
+    ) -> Result<rpc::GetEventsResponse, Error> {
         let count: usize = if self.count == 0 {
             std::usize::MAX
         } else {
@@ -261,38 +409,320 @@ impl Cmd {
         };
 
         let start_cursor = match start {
-            rpc::EventStart::Ledger(l) => (toid::Toid::new(l, 0, 0).into(), -1),
-            rpc::EventStart::Cursor(c) => rpc::parse_cursor(&c)?,
+            rpc::EventStart::Ledger(l) => (toid::Toid::new(*l, 0, 0).into(), -1),
+            rpc::EventStart::Cursor(c) => rpc::parse_cursor(c)?,
         };
         let path = self.locator.config_dir()?;
-        let file = self.events_file.read(&path)?;
 
         // Read the JSON events from disk and find the ones that match the
-        // contract ID filter(s) that were passed in.
-        Ok(rpc::GetEventsResponse {
-            events: events_file::Args::filter_events(
-                &file.events,
-                &path,
-                start_cursor,
-                &self.contract_ids,
-                &self.topic_filters,
-                count,
-            ),
-            latest_ledger: file.latest_ledger,
-        })
+        // filter(s) that were passed in.
+        Ok(self
+            .events_file
+            .query(&path, start_cursor, filters, count)?)
+    }
+
+    /// Add the specs of the contracts that emitted `events` and aren't in `specs` yet, by contract
+    /// ID, used to decode the events. Contracts whose spec can't be found get an empty spec, so
+    /// they aren't looked up again.
+    async fn add_specs(
+        &self,
+        specs: &mut HashMap<String, Spec>,
+        client: Option<&rpc::Client>,
+        events: &[rpc::Event],
+    ) -> Result<(), Error> {
+        let contract_ids = events
+            .iter()
+            .map(|event| event.contract_id.clone())
+            .filter(|id| !id.is_empty() && !specs.contains_key(id))
+            .collect::<HashSet<_>>();
+        if contract_ids.is_empty() {
+            return Ok(());
+        }
+        if let Some(wasm) = &self.wasm {
+            let wasm = fs::read(wasm).map_err(|e| Error::CannotReadFile {
+                path: wasm.to_string_lossy().to_string(),
+                error: e.to_string(),
+            })?;
+            let spec = Spec::from_wasm(&wasm)?;
+            specs.extend(contract_ids.into_iter().map(|id| (id, spec.clone())));
+            return Ok(());
+        }
+
+        if let Some(client) = client {
+            let ids = contract_ids
+                .iter()
+                .filter_map(|id| utils::contract_id_from_str(id).ok())
+                .collect::<Vec<_>>();
+            let mut remote_specs = client.get_remote_contract_specs(&ids).await?;
+            for id in contract_ids {
+                let spec = utils::contract_id_from_str(&id)
+                    .ok()
+                    .and_then(|contract_id| remote_specs.remove(&contract_id));
+                specs.insert(id, spec.map(Spec::new).unwrap_or_default());
+            }
+        } else {
+            let state = self.ledger_file.read(&self.locator.config_dir()?)?;
+            for id in contract_ids {
+                let spec = utils::contract_id_from_str(&id)
+                    .ok()
+                    .and_then(|contract_id| {
+                        utils::get_contract_spec_from_state(&state, contract_id).ok()
+                    });
+                specs.insert(id, spec.map(Spec::new).unwrap_or_default());
+            }
+        }
+        Ok(())
+    }
+
+    /// Filters to pull events with: the ones passed with `--filter`, or the one made up of
+    /// `--type`, `--id` and `--topic`
+    fn filters(&self) -> Result<Vec<rpc::EventFilter>, Error> {
+        if !self.filters.is_empty() {
+            return self.filters.iter().map(|f| parse_filter(f)).collect();
+        }
+        Ok(vec![event_filter(
+            self.event_type,
+            &self.contract_ids,
+            &self.topic_filters,
+        )?])
     }
 
     fn start(&self) -> Result<rpc::EventStart, Error> {
+        if let Some(path) = &self.cursor_file {
+            if let Some(cursor) = read_cursor_file(path)? {
+                return Ok(rpc::EventStart::Cursor(cursor));
+            }
+        }
         let start = match (self.start_ledger, self.cursor.clone()) {
             (Some(start), _) => rpc::EventStart::Ledger(start),
             (_, Some(c)) => rpc::EventStart::Cursor(c),
-            // should never happen because of required_unless_present flags
+            // should never happen because of required_unless_present flags, unless the cursor
+            // file doesn't exist yet
             _ => return Err(Error::MissingStartLedgerAndCursor),
         };
         Ok(start)
     }
 }
 
+/// Ledger a poll from `start` begins at
+fn start_ledger(start: &rpc::EventStart) -> Result<u32, Error> {
+    Ok(match start {
+        rpc::EventStart::Ledger(ledger) => *ledger,
+        // The ledger makes up the high 32 bits of the TOID in a cursor
+        rpc::EventStart::Cursor(cursor) => {
+            u32::try_from(rpc::parse_cursor(cursor)?.0 >> 32).unwrap_or(u32::MAX)
+        }
+    })
+}
+
+/// Cursor persisted to `path`, if the file exists and isn't empty
+fn read_cursor_file(path: &Path) -> Result<Option<String>, Error> {
+    match fs::read_to_string(path) {
+        Ok(cursor) => Ok(Some(cursor.trim().to_string()).filter(|c| !c.is_empty())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::CannotReadFile {
+            path: path.to_string_lossy().to_string(),
+            error: e.to_string(),
+        }),
+    }
+}
+
+/// Persist `cursor` to `path`, replacing the file at once so an interrupted watcher never leaves
+/// a partial cursor behind
+fn write_cursor_file(path: &Path, cursor: &str) -> Result<(), Error> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, format!("{cursor}\n"))
+        .and_then(|()| fs::rename(&tmp, path))
+        .map_err(|error| Error::CannotWriteCursorFile {
+            path: path.to_string_lossy().to_string(),
+            error,
+        })
+}
+
+static NO_SPEC: Spec = Spec(None);
+
+fn print_export_summary(global_args: &global::Args, response: &rpc::GetEventsResponse) {
+    let exported = response.events.len();
+    if global_args.is_json() {
+        let json = json!({ "exported": exported, "latest_ledger": response.latest_ledger });
+        println!("{json:#}");
+    } else {
+        println!("Exported {exported} events");
+        println!("Latest Ledger: {}", response.latest_ledger);
+    }
+}
+
+/// Filter object with its contract IDs and topic filters validated and normalized to the format
+/// the RPC server and the sandbox match against
+fn event_filter(
+    event_type: rpc::EventType,
+    contract_ids: &[String],
+    topics: &[String],
+) -> Result<rpc::EventFilter, Error> {
+    // We parse the contract IDs to ensure they're the correct format, and padded out
+    // correctly.
+    //
+    // TODO: Once soroban-rpc supports passing these as a strkey, we should change to
+    // formatting these as C-strkeys.
+    let contract_ids = contract_ids
+        .iter()
+        .map(|id| {
+            utils::contract_id_from_str(id)
+                .map(hex::encode)
+                .map_err(|e| Error::InvalidContractId {
+                    contract_id: id.clone(),
+                    error: e,
+                })
+        })
+        .collect::<Result<_, _>>()?;
+    // Validate that topics are made up of segments, and normalize the segments to
+    // base64-encoded XDR.
+    let topics = topics
+        .iter()
+        .map(|topic| {
+            let segments = split_segments(topic);
+            if segments.len() > 4 {
+                return Err(Error::InvalidTopicFilter {
+                    topic: topic.to_string(),
+                });
+            }
+            Ok(segments
+                .into_iter()
+                .map(|segment| parse_segment(topic, segment))
+                .collect::<Result<Vec<_>, _>>()?
+                .join(","))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(rpc::EventFilter {
+        event_type,
+        contract_ids,
+        topics,
+    })
+}
+
+/// Filter object written as space-separated `type=<TYPE>`, `id=<ID>` and `topic=<TOPIC>` pairs,
+/// quoted like shell arguments
+fn parse_filter(filter: &str) -> Result<rpc::EventFilter, Error> {
+    let invalid_filter = |error: String| Error::InvalidFilter {
+        filter: filter.to_string(),
+        error,
+    };
+    let mut event_type = None;
+    let mut contract_ids = vec![];
+    let mut topics = vec![];
+    // Split like a shell would, so values with spaces can be quoted
+    let pairs = shlex::split(filter)
+        .ok_or_else(|| invalid_filter("unbalanced quotes or trailing backslash".to_string()))?;
+    for pair in &pairs {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| invalid_filter(format!("expected <key>=<value>, got {pair}")))?;
+        match key {
+            "type" if event_type.is_some() => {
+                return Err(invalid_filter("type can only be set once".to_string()));
+            }
+            "type" => {
+                event_type = Some(
+                    <rpc::EventType as clap::ValueEnum>::from_str(value, true)
+                        .map_err(invalid_filter)?,
+                );
+            }
+            "id" => contract_ids.push(value.to_string()),
+            "topic" => topics.push(value.to_string()),
+            _ => {
+                return Err(invalid_filter(format!(
+                    "unknown key {key}, expected one of: type, id, topic"
+                )))
+            }
+        }
+    }
+    event_filter(event_type.unwrap_or_default(), &contract_ids, &topics)
+}
+
+/// Segments of a topic filter, split on the commas that aren't inside a JSON object or array or a
+/// double-quoted string, so segments like `{"vec":[{"u32":1},{"u32":2}]}` and `str:"a,b"` stay
+/// whole
+fn split_segments(topic: &str) -> Vec<&str> {
+    let mut segments = vec![];
+    let mut start = 0;
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in topic.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => depth += 1,
+            '}' | ']' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                segments.push(&topic[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    segments.push(&topic[start..]);
+    segments
+}
+
+/// Base64-encoded XDR of a topic filter segment, which is a wildcard, a typed value like
+/// `sym:transfer`, a JSON `ScVal` or already base64-encoded XDR
+fn parse_segment(topic: &str, segment: &str) -> Result<String, Error> {
+    if segment == "*" {
+        return Ok(segment.to_string());
+    }
+    let typed_segment_error = |error| Error::InvalidTypedSegment {
+        topic: topic.to_string(),
+        segment: segment.to_string(),
+        error,
+    };
+    // Neither JSON objects nor typed values are valid base64, so they can't be mistaken for it
+    let val = if segment.starts_with('{') {
+        soroban_spec_tools::from_string_primitive(segment, &ScSpecTypeDef::Val)
+            .map_err(typed_segment_error)?
+    } else if let Some((type_, value)) = segment.split_once(':') {
+        let (_, t) = utils::PRIMITIVE_TYPES
+            .iter()
+            .find(|(name, _)| *name == type_)
+            .ok_or_else(|| Error::UnknownSegmentType {
+                topic: topic.to_string(),
+                segment: segment.to_string(),
+                type_: type_.to_string(),
+            })?;
+        soroban_spec_tools::from_string_primitive(value, t).map_err(typed_segment_error)?
+    } else {
+        xdr::ScVal::from_xdr_base64(segment).map_err(|e| Error::InvalidSegment {
+            topic: topic.to_string(),
+            segment: segment.to_string(),
+            error: e,
+        })?
+    };
+    Ok(val.to_xdr_base64()?)
+}
+
+/// The event as returned by the RPC server, along with its topics and value decoded with `spec`
+fn event_to_json(event: &rpc::Event, spec: &Spec) -> Result<Value, Error> {
+    let mut json = serde_json::to_value(event)?;
+    if let Value::Object(object) = &mut json {
+        object.insert(
+            "decoded".to_string(),
+            json!({
+                "topic": event.topics_to_json(spec)?,
+                "value": event.value_to_json(spec)?,
+            }),
+        );
+    }
+    Ok(json)
+}
+
 #[cfg(test)]
 mod tests {
     use std::path;
@@ -308,6 +738,7 @@ mod tests {
         let temp = NamedTempFile::new("events.json").unwrap();
         let events_file = Args {
             events_file: Some(temp.to_path_buf()),
+            ..Default::default()
         };
         // Make a couple of fake events with slightly different properties and
         // write them to disk, then read the serialized versions from disk and
@@ -363,6 +794,61 @@ mod tests {
         assert_eq!(file.latest_ledger, 2);
     }
 
+    #[test]
+    fn test_events_file_parallel_commits_and_compaction() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let pwd = dir.path();
+        let events_file = Args {
+            events_file: None,
+            events_retention: 10,
+        };
+        let event = |i| events::HostEvent {
+            event: xdr::ContractEvent {
+                ext: xdr::ExtensionPoint::V0,
+                contract_id: Some(xdr::Hash([0; 32])),
+                type_: xdr::ContractEventType::Contract,
+                body: xdr::ContractEventBody::V0(xdr::ContractEventV0 {
+                    topics: xdr::ScVec(vec![].try_into().unwrap()),
+                    data: xdr::ScVal::U32(i),
+                }),
+            },
+            failed_call: false,
+        };
+        let ledger = |sequence_number| soroban_ledger_snapshot::LedgerSnapshot {
+            sequence_number,
+            ..Default::default()
+        };
+
+        std::thread::scope(|s| {
+            for i in 1..=8 {
+                let events_file = &events_file;
+                s.spawn(move || events_file.commit(&[event(i)], &ledger(i), pwd).unwrap());
+            }
+        });
+        let file = events_file.read(pwd).unwrap();
+        assert_eq!(file.events.len(), 8);
+        assert_eq!(file.latest_ledger, 8);
+
+        events_file.commit(&[event(30)], &ledger(30), pwd).unwrap();
+        let file = events_file.read(pwd).unwrap();
+        assert_eq!(file.events.len(), 1);
+        assert_eq!(file.events[0].ledger, "30");
+        assert_eq!(file.latest_ledger, 30);
+
+        // Events of older ledgers appended last count towards the latest and oldest ledgers
+        events_file.commit(&[event(25)], &ledger(25), pwd).unwrap();
+        assert_eq!(events_file.read(pwd).unwrap().latest_ledger, 30);
+        events_file.commit(&[event(5)], &ledger(5), pwd).unwrap();
+        let file = events_file.read(pwd).unwrap();
+        let ledgers = file
+            .events
+            .iter()
+            .map(|e| e.ledger.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ledgers, ["30", "25"]);
+        assert_eq!(file.latest_ledger, 30);
+    }
+
     #[test]
     fn test_does_event_fixture_load() {
         // This test ensures that the included JSON fixture file matches the
@@ -371,9 +857,161 @@ mod tests {
             path::PathBuf::from("../crates/soroban-test/tests/fixtures/test-jsons/get-events.json");
         let events_file = Args {
             events_file: Some(filename),
+            ..Default::default()
         };
         let result = events_file.read(&std::env::current_dir().unwrap());
         println!("{result:?}");
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_event_decoding() {
+        let event = rpc::Event::fixture(
+            0,
+            &"0".repeat(64),
+            vec![
+                xdr::ScVal::Symbol(xdr::ScSymbol("COUNTER".try_into().unwrap()))
+                    .to_xdr_base64()
+                    .unwrap(),
+            ],
+            xdr::ScVal::U32(7).to_xdr_base64().unwrap(),
+        );
+        let json = event_to_json(&event, &NO_SPEC).unwrap();
+        assert_eq!(json["decoded"], json!({ "topic": ["COUNTER"], "value": 7 }));
+        assert!(event
+            .to_string_with_spec(&NO_SPEC)
+            .unwrap()
+            .contains("            COUNTER\n  Value:    7\n"));
+    }
+
+    #[test]
+    fn test_cursor_file() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = dir.join("cursor");
+        assert_eq!(read_cursor_file(&path).unwrap(), None);
+
+        write_cursor_file(&path, "0000000008589934592-0000000000").unwrap();
+        write_cursor_file(&path, "0000000012884901888-0000000001").unwrap();
+        assert_eq!(
+            read_cursor_file(&path).unwrap().as_deref(),
+            Some("0000000012884901888-0000000001")
+        );
+        assert!(!path.with_extension("tmp").exists());
+    }
+
+    #[test]
+    fn test_start_ledger() {
+        assert_eq!(start_ledger(&rpc::EventStart::Ledger(7)).unwrap(), 7);
+        assert_eq!(
+            start_ledger(&rpc::EventStart::Cursor(
+                "0000000012884901888-0000000001".to_string()
+            ))
+            .unwrap(),
+            3
+        );
+        assert!(start_ledger(&rpc::EventStart::Cursor("nope".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_parse_segment() {
+        let symbol = xdr::ScVal::Symbol(xdr::ScSymbol("transfer".try_into().unwrap()))
+            .to_xdr_base64()
+            .unwrap();
+        let five = xdr::ScVal::U32(5).to_xdr_base64().unwrap();
+        assert_eq!(parse_segment("", "*").unwrap(), "*");
+        assert_eq!(parse_segment("", "sym:transfer").unwrap(), symbol);
+        assert_eq!(parse_segment("", &symbol).unwrap(), symbol);
+        assert_eq!(parse_segment("", "u32:5").unwrap(), five);
+        assert_eq!(parse_segment("", r#"{"u32":5}"#).unwrap(), five);
+        assert_eq!(
+            parse_segment("", "i128:-5").unwrap(),
+            xdr::ScVal::I128(xdr::Int128Parts {
+                hi: -1,
+                lo: u64::MAX - 4
+            })
+            .to_xdr_base64()
+            .unwrap()
+        );
+        assert!(matches!(
+            parse_segment("", "int:5"),
+            Err(Error::UnknownSegmentType { .. })
+        ));
+        assert!(matches!(
+            parse_segment("", "u32:five"),
+            Err(Error::InvalidTypedSegment { .. })
+        ));
+    }
+
+    #[test]
+    fn test_split_segments() {
+        assert_eq!(split_segments("sym:transfer,*"), ["sym:transfer", "*"]);
+        assert_eq!(split_segments("*"), ["*"]);
+        assert_eq!(
+            split_segments(r#"{"vec":[{"u32":1},{"u32":2}]},str:"a,b",*"#),
+            [r#"{"vec":[{"u32":1},{"u32":2}]}"#, r#"str:"a,b""#, "*"]
+        );
+        assert_eq!(split_segments(r#"str:"a\",b",*"#), [r#"str:"a\",b""#, "*"]);
+
+        let filter = event_filter(
+            rpc::EventType::All,
+            &[],
+            &[r#"{"vec":[{"u32":1},{"u32":2}]},str:"a,b""#.to_string()],
+        )
+        .unwrap();
+        let vec = xdr::ScVal::Vec(Some(
+            vec![xdr::ScVal::U32(1), xdr::ScVal::U32(2)]
+                .try_into()
+                .unwrap(),
+        ));
+        let string = xdr::ScVal::String(xdr::ScString("a,b".try_into().unwrap()));
+        assert_eq!(
+            filter.topics,
+            [format!(
+                "{},{}",
+                vec.to_xdr_base64().unwrap(),
+                string.to_xdr_base64().unwrap()
+            )]
+        );
+
+        assert!(event_filter(rpc::EventType::All, &[], &["*,*,*,*".to_string()]).is_ok());
+        assert!(matches!(
+            event_filter(rpc::EventType::All, &[], &["*,*,*,*,*".to_string()]),
+            Err(Error::InvalidTopicFilter { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        let id = stellar_strkey::Contract([1; 32]).to_string();
+        let filter = parse_filter(&format!("type=contract id={id} topic=sym:transfer,*")).unwrap();
+        assert_eq!(
+            filter,
+            rpc::EventFilter {
+                event_type: rpc::EventType::Contract,
+                contract_ids: vec!["01".repeat(32)],
+                topics: vec![format!("{},*", parse_segment("", "sym:transfer").unwrap())],
+            }
+        );
+        assert_eq!(parse_filter("").unwrap(), rpc::EventFilter::default());
+        assert_eq!(
+            parse_filter(r#"topic='str:"a b",*'"#).unwrap().topics,
+            [format!("{},*", parse_segment("", r#"str:"a b""#).unwrap())]
+        );
+        assert!(matches!(
+            parse_filter("topic='sym:transfer"),
+            Err(Error::InvalidFilter { .. })
+        ));
+        assert!(matches!(
+            parse_filter("type=contract type=system"),
+            Err(Error::InvalidFilter { .. })
+        ));
+        assert!(matches!(
+            parse_filter("contract=abc"),
+            Err(Error::InvalidFilter { .. })
+        ));
+        assert!(matches!(
+            parse_filter("type=diagnostic"),
+            Err(Error::InvalidFilter { .. })
+        ));
+    }
 }
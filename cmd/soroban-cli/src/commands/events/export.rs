@@ -0,0 +1,300 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, Write},
+    path::Path,
+};
+
+use serde_json::json;
+use soroban_spec_tools::Spec;
+
+use super::NO_SPEC;
+use crate::{rpc, utils};
+
+/// Soroban events have at most 4 topics, each of which gets its own column
+const MAX_TOPICS: usize = 4;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, clap::ValueEnum)]
+pub enum Format {
+    /// One JSON object per line
+    Ndjson,
+    /// Comma-separated values, with a header row
+    Csv,
+    /// Table `events` of a SQLite database
+    Sqlite,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("cannot open export file {path}: {error}")]
+    CannotOpenFile { path: String, error: io::Error },
+    #[error("invalid ledger in event: {0}")]
+    InvalidLedger(String),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+    #[error(transparent)]
+    Rpc(#[from] rpc::Error),
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+}
+
+/// Writes events to a file, appending to what is already there so that an export can be resumed,
+/// e.g. with `--cursor-file`
+pub enum Exporter {
+    Ndjson(File),
+    Csv(csv::Writer<File>),
+    Sqlite(rusqlite::Connection),
+}
+
+impl Exporter {
+    pub fn open(format: Format, path: &Path) -> Result<Self, Error> {
+        let cannot_open = |error| Error::CannotOpenFile {
+            path: path.to_string_lossy().to_string(),
+            error,
+        };
+        Ok(match format {
+            Format::Ndjson => Self::Ndjson(
+                fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(cannot_open)?,
+            ),
+            Format::Csv => {
+                let file = fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(cannot_open)?;
+                let is_new = file.metadata()?.len() == 0;
+                let mut writer = csv::Writer::from_writer(file);
+                if is_new {
+                    writer.write_record(
+                        ["ledger", "timestamp", "contract_id", "type"]
+                            .into_iter()
+                            .map(ToString::to_string)
+                            .chain((1..=MAX_TOPICS).map(|i| format!("topic_{i}")))
+                            .chain(["value".to_string(), "paging_token".to_string()]),
+                    )?;
+                }
+                Self::Csv(writer)
+            }
+            Format::Sqlite => {
+                let connection = rusqlite::Connection::open(path)?;
+                connection.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS events (
+                        paging_token TEXT PRIMARY KEY,
+                        ledger INTEGER NOT NULL,
+                        timestamp TEXT NOT NULL,
+                        contract_id TEXT NOT NULL,
+                        type TEXT NOT NULL,
+                        topic_1 TEXT,
+                        topic_2 TEXT,
+                        topic_3 TEXT,
+                        topic_4 TEXT,
+                        value TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS events_contract_id ON events (contract_id, ledger);",
+                )?;
+                Self::Sqlite(connection)
+            }
+        })
+    }
+
+    /// Write `events`, with their topics and value decoded with the spec of the contract that
+    /// emitted them, found in `specs` by contract ID
+    pub fn export(
+        &mut self,
+        events: &[rpc::Event],
+        specs: &HashMap<String, Spec>,
+    ) -> Result<(), Error> {
+        let spec = |event: &rpc::Event| specs.get(&event.contract_id).unwrap_or(&NO_SPEC);
+        match self {
+            Self::Ndjson(file) => {
+                for event in events {
+                    let spec = spec(event);
+                    let json = json!({
+                        "ledger": ledger(event)?,
+                        "timestamp": event.ledger_closed_at,
+                        "contract_id": contract_id(event),
+                        "type": event.event_type,
+                        "topics": event.topics_to_json(spec)?,
+                        "value": event.value_to_json(spec)?,
+                        "paging_token": event.paging_token,
+                    });
+                    writeln!(file, "{json}")?;
+                }
+                file.flush()?;
+            }
+            Self::Csv(writer) => {
+                for event in events {
+                    let row = Row::new(event, spec(event))?;
+                    writer.write_record(
+                        [
+                            row.ledger.to_string(),
+                            row.timestamp,
+                            row.contract_id,
+                            row.event_type,
+                        ]
+                        .into_iter()
+                        .chain(row.topics.into_iter().map(Option::unwrap_or_default))
+                        .chain([row.value, row.paging_token]),
+                    )?;
+                }
+                writer.flush()?;
+            }
+            Self::Sqlite(connection) => {
+                let transaction = connection.transaction()?;
+                {
+                    // Events already exported are skipped, so overlapping exports don't
+                    // duplicate them
+                    let mut insert = transaction.prepare(
+                        "INSERT OR IGNORE INTO events (paging_token, ledger, timestamp, contract_id,
+                            type, topic_1, topic_2, topic_3, topic_4, value)
+                        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    )?;
+                    for event in events {
+                        let row = Row::new(event, spec(event))?;
+                        let [topic_1, topic_2, topic_3, topic_4] = row.topics;
+                        insert.execute(rusqlite::params![
+                            row.paging_token,
+                            row.ledger,
+                            row.timestamp,
+                            row.contract_id,
+                            row.event_type,
+                            topic_1,
+                            topic_2,
+                            topic_3,
+                            topic_4,
+                            row.value,
+                        ])?;
+                    }
+                }
+                transaction.commit()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Columns of an exported event. Topics are decoded like in the console output, with symbols as
+/// text and other values as JSON, and the value is decoded as JSON.
+struct Row {
+    ledger: u32,
+    timestamp: String,
+    contract_id: String,
+    event_type: String,
+    topics: [Option<String>; MAX_TOPICS],
+    value: String,
+    paging_token: String,
+}
+
+impl Row {
+    fn new(event: &rpc::Event, spec: &Spec) -> Result<Self, Error> {
+        let mut topics: [Option<String>; MAX_TOPICS] = Default::default();
+        for (column, topic) in topics.iter_mut().zip(event.topics_to_strings(spec)?) {
+            *column = Some(topic);
+        }
+        Ok(Self {
+            ledger: ledger(event)?,
+            timestamp: event.ledger_closed_at.clone(),
+            contract_id: contract_id(event),
+            event_type: event.event_type.clone(),
+            topics,
+            value: event.value_to_json(spec)?.to_string(),
+            paging_token: event.paging_token.clone(),
+        })
+    }
+}
+
+fn ledger(event: &rpc::Event) -> Result<u32, Error> {
+    event
+        .ledger
+        .parse()
+        .map_err(|_| Error::InvalidLedger(event.ledger.clone()))
+}
+
+/// Contract ID of the event as a strkey, falling back to how the RPC server returned it
+fn contract_id(event: &rpc::Event) -> String {
+    utils::contract_id_from_str(&event.contract_id).map_or_else(
+        |_| event.contract_id.clone(),
+        |id| stellar_strkey::Contract(id).to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use soroban_env_host::xdr::{ScSymbol, ScVal, WriteXdr};
+
+    use super::*;
+
+    fn event(i: u32) -> rpc::Event {
+        rpc::Event::fixture(
+            i,
+            &"0".repeat(64),
+            vec![ScVal::Symbol(ScSymbol("COUNTER".try_into().unwrap()))
+                .to_xdr_base64()
+                .unwrap()],
+            ScVal::U32(i).to_xdr_base64().unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = dir.join("events.csv");
+        Exporter::open(Format::Csv, &path)
+            .unwrap()
+            .export(&[event(1)], &HashMap::new())
+            .unwrap();
+        Exporter::open(Format::Csv, &path)
+            .unwrap()
+            .export(&[event(2)], &HashMap::new())
+            .unwrap();
+        let contract_id = stellar_strkey::Contract([0; 32]);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!(
+                "ledger,timestamp,contract_id,type,topic_1,topic_2,topic_3,topic_4,value,paging_token\n\
+                 2,2023-01-01T00:00:00Z,{contract_id},contract,COUNTER,,,,1,{}\n\
+                 2,2023-01-01T00:00:00Z,{contract_id},contract,COUNTER,,,,2,{}\n",
+                event(1).paging_token,
+                event(2).paging_token,
+            )
+        );
+    }
+
+    #[test]
+    fn test_export_sqlite() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = dir.join("events.db");
+        let mut exporter = Exporter::open(Format::Sqlite, &path).unwrap();
+        exporter
+            .export(&[event(1), event(2)], &HashMap::new())
+            .unwrap();
+        exporter
+            .export(&[event(2), event(3)], &HashMap::new())
+            .unwrap();
+
+        let connection = rusqlite::Connection::open(&path).unwrap();
+        let rows = connection
+            .prepare("SELECT topic_1, value FROM events ORDER BY paging_token")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<Vec<(String, String)>, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                ("COUNTER".to_string(), "1".to_string()),
+                ("COUNTER".to_string(), "2".to_string()),
+                ("COUNTER".to_string(), "3".to_string()),
+            ]
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use clap::arg;
+use clap::{arg, ValueEnum};
 use std::path::PathBuf;
 
 use super::config;
@@ -29,6 +29,19 @@ pub struct Args {
     /// List installed plugins. E.g. `soroban-hello`
     #[arg(long)]
     pub list: bool,
+
+    /// Format of the output of commands. With `json` each command prints a single JSON object, including the transaction hash, fee charged, footprint and events of the transactions it sends, or the footprint and events of the sandbox runs
+    #[arg(long, value_enum, default_value_t)]
+    pub output: Output,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, ValueEnum)]
+pub enum Output {
+    /// Human readable output
+    #[default]
+    Text,
+    /// One JSON object per command
+    Json,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -47,6 +60,10 @@ pub enum Error {
 }
 
 impl Args {
+    pub fn is_json(&self) -> bool {
+        self.output == Output::Json
+    }
+
     pub fn log_level(&self) -> Option<tracing::Level> {
         if self.quiet {
             None
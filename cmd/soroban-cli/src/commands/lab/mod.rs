@@ -1,5 +1,7 @@
 use clap::Subcommand;
 
+use crate::commands::global;
+
 pub mod token;
 pub mod xdr;
 
@@ -21,10 +23,10 @@ pub enum Error {
 }
 
 impl Cmd {
-    pub async fn run(&self) -> Result<(), Error> {
+    pub async fn run(&self, global_args: &global::Args) -> Result<(), Error> {
         match &self {
-            Cmd::Token(token) => token.run().await?,
-            Cmd::Xdr(xdr) => xdr.run()?,
+            Cmd::Token(token) => token.run(global_args).await?,
+            Cmd::Xdr(xdr) => xdr.run(global_args)?,
         }
         Ok(())
     }
@@ -1,6 +1,7 @@
 use clap::{arg, command, Parser};
+use serde_json::json;
 
-use crate::commands::config;
+use crate::commands::{config, global};
 
 use crate::utils::contract_id_hash_from_asset;
 use crate::utils::parsing::parse_asset;
@@ -25,12 +26,16 @@ pub enum Error {
     Xdr(#[from] soroban_env_host::xdr::Error),
 }
 impl Cmd {
-    pub fn run(&self) -> Result<(), Error> {
+    pub fn run(&self, global_args: &global::Args) -> Result<(), Error> {
         let asset = parse_asset(&self.asset)?;
         let network = self.config.get_network()?;
         let contract_id = contract_id_hash_from_asset(&asset, &network.network_passphrase)?;
         let strkey_contract_id = stellar_strkey::Contract(contract_id.0).to_string();
-        println!("{strkey_contract_id}");
+        if global_args.is_json() {
+            println!("{:#}", json!({ "contract_id": strkey_contract_id }));
+        } else {
+            println!("{strkey_contract_id}");
+        }
         Ok(())
     }
 }
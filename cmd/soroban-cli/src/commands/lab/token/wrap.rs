@@ -1,4 +1,5 @@
 use clap::{arg, command, Parser};
+use serde_json::json;
 use soroban_env_host::{
     budget::Budget,
     storage::Storage,
@@ -15,21 +16,25 @@ use std::convert::Infallible;
 use std::{array::TryFromSliceError, fmt::Debug, num::ParseIntError, rc::Rc};
 
 use crate::{
-    commands::config,
+    commands::{
+        config, global,
+        txn_result::{self, Applied, TxnResult},
+    },
     rpc::{Client, Error as SorobanRpcError},
-    utils::{contract_id_hash_from_asset, parsing::parse_asset},
+    signer::{self, Signer},
+    utils::{self, contract_id_hash_from_asset, parsing::parse_asset},
 };
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
-    #[error(transparent)]
-    // TODO: the Display impl of host errors is pretty user-unfriendly
-    //       (it just calls Debug). I think we can do better than that
-    Host(#[from] HostError),
+    #[error("{0}")]
+    Failed(Box<utils::host_error::Report>),
     #[error("error parsing int: {0}")]
     ParseIntError(#[from] ParseIntError),
     #[error(transparent)]
     Client(#[from] SorobanRpcError),
+    #[error(transparent)]
+    Signer(#[from] signer::Error),
     #[error("internal conversion error: {0}")]
     TryFromSliceError(#[from] TryFromSliceError),
     #[error("xdr processing error: {0}")]
@@ -38,6 +43,16 @@ pub enum Error {
     Config(#[from] config::Error),
     #[error(transparent)]
     ParseAssetError(#[from] crate::utils::parsing::Error),
+    #[error(transparent)]
+    TxnResult(#[from] txn_result::Error),
+    #[error(transparent)]
+    Fee(#[from] crate::fee::Error),
+}
+
+impl From<HostError> for Error {
+    fn from(error: HostError) -> Self {
+        Error::Failed(Box::new(utils::host_error::Report::from_host_error(&error)))
+    }
 }
 
 impl From<Infallible> for Error {
@@ -60,20 +75,28 @@ pub struct Cmd {
 }
 
 impl Cmd {
-    pub async fn run(&self) -> Result<(), Error> {
+    pub async fn run(&self, global_args: &global::Args) -> Result<(), Error> {
         // Parse asset
         let asset = parse_asset(&self.asset)?;
 
         let res_str = if self.config.is_no_network() {
+            self.fee.check_sandbox()?;
             self.run_in_sandbox(&asset)?
         } else {
             self.run_against_rpc_server(asset).await?
         };
-        println!("{res_str}");
+        if global_args.is_json() {
+            println!(
+                "{:#}",
+                res_str.to_json(|contract_id| json!({ "contract_id": contract_id }))?
+            );
+        } else {
+            println!("{res_str}");
+        }
         Ok(())
     }
 
-    pub fn run_in_sandbox(&self, asset: &Asset) -> Result<String, Error> {
+    pub fn run_in_sandbox(&self, asset: &Asset) -> Result<TxnResult<String>, Error> {
         // Initialize storage and host
         // TODO: allow option to separate input and output file
         let mut state = self.config.get_state()?;
@@ -89,47 +112,76 @@ impl Cmd {
         ledger_info.timestamp += 5;
         h.set_ledger_info(ledger_info);
 
-        let res = h.invoke_function(HostFunction::CreateContract(CreateContractArgs {
-            contract_id_preimage: ContractIdPreimage::Asset(asset.clone()),
-            executable: ContractExecutable::Token,
-        }))?;
+        let res = h
+            .invoke_function(HostFunction::CreateContract(CreateContractArgs {
+                contract_id_preimage: ContractIdPreimage::Asset(asset.clone()),
+                executable: ContractExecutable::Token,
+            }))
+            .map_err(|error| {
+                let events = h.get_events().map(|events| events.0).unwrap_or_default();
+                Error::Failed(Box::new(
+                    utils::host_error::Report::from_host_error(&error).with_events(&events),
+                ))
+            })?;
 
         let contract_id = vec_to_hash(&res)?;
 
         state.update(&h);
         self.config.set_state(&mut state)?;
-        Ok(stellar_strkey::Contract(contract_id.0).to_string())
+
+        let (storage, _, events, _) = h.try_finish().map_err(|h| h.1)?;
+        let footprint = utils::create_ledger_footprint(&storage.footprint);
+        Ok(TxnResult::Sandbox(
+            stellar_strkey::Contract(contract_id.0).to_string(),
+            Box::new(Applied::new(footprint, &events.0)),
+        ))
     }
 
-    async fn run_against_rpc_server(&self, asset: Asset) -> Result<String, Error> {
+    async fn run_against_rpc_server(&self, asset: Asset) -> Result<TxnResult<String>, Error> {
         let network = self.config.get_network()?;
-        let client = Client::new(&network.rpc_url)?;
+        let client = Client::new(&network.rpc_url)?.with_wait(self.fee.wait.wait()?);
         client
             .verify_network_passphrase(Some(&network.network_passphrase))
             .await?;
-        let key = self.config.key_pair()?;
+        let key = self.config.signer()?;
+        let public_key = key.public_key()?;
 
         // Get the account sequence number
-        let public_strkey = stellar_strkey::ed25519::PublicKey(key.public.to_bytes()).to_string();
+        let public_strkey = stellar_strkey::ed25519::PublicKey(public_key.to_bytes()).to_string();
         // TODO: use symbols for the method names (both here and in serve)
-        let account_details = client.get_account(&public_strkey).await?;
-        let sequence: i64 = account_details.seq_num.into();
         let network_passphrase = &network.network_passphrase;
+        let sequence = self
+            .fee
+            .next_sequence(&client, network_passphrase, &public_strkey)
+            .await?;
         let contract_id = contract_id_hash_from_asset(&asset, network_passphrase)?;
         let tx = build_wrap_token_tx(
             &asset,
             &contract_id,
-            sequence + 1,
+            sequence,
             self.fee.fee,
             network_passphrase,
-            &key,
+            &public_key,
         )?;
 
-        client
-            .prepare_and_send_transaction(&tx, &key, network_passphrase, None)
+        if self.fee.build_only {
+            return Ok(TxnResult::Txn(client.prepare_transaction(&tx, None).await?));
+        }
+        if self.fee.no_wait {
+            let tx = client
+                .prepare_and_sign_transaction(&tx, &key, || Ok(vec![]), network_passphrase, None)
+                .await?;
+            return Ok(TxnResult::Hash(client.submit_transaction(&tx).await?));
+        }
+
+        let sent = client
+            .prepare_and_send_transaction(&tx, &key, || Ok(vec![]), network_passphrase, None)
             .await?;
 
-        Ok(stellar_strkey::Contract(contract_id.0).to_string())
+        Ok(TxnResult::Sent(
+            stellar_strkey::Contract(contract_id.0).to_string(),
+            Box::new(sent),
+        ))
     }
 }
 
@@ -154,7 +206,7 @@ fn build_wrap_token_tx(
     sequence: i64,
     fee: u32,
     _network_passphrase: &str,
-    key: &ed25519_dalek::Keypair,
+    key: &ed25519_dalek::PublicKey,
 ) -> Result<Transaction, Error> {
     let contract = ScAddress::Contract(contract_id.clone());
     let mut read_write = vec![
@@ -196,7 +248,7 @@ fn build_wrap_token_tx(
     };
 
     Ok(Transaction {
-        source_account: MuxedAccount::Ed25519(Uint256(key.public.to_bytes())),
+        source_account: MuxedAccount::Ed25519(Uint256(key.to_bytes())),
         fee,
         seq_num: SequenceNumber(sequence),
         cond: Preconditions::None,
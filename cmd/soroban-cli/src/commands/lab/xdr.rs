@@ -4,6 +4,8 @@ use std::fmt::Debug;
 
 use clap::{Parser, Subcommand};
 
+use crate::commands::global;
+
 #[derive(Parser, Debug, Clone)]
 #[group(skip)]
 pub struct Cmd {
@@ -25,9 +27,9 @@ pub enum Error {
 
 impl Cmd {
     #[allow(clippy::too_many_lines)]
-    pub fn run(&self) -> Result<(), Error> {
+    pub fn run(&self, global_args: &global::Args) -> Result<(), Error> {
         match &self.sub {
-            SubCmd::Dec(d) => d.run()?,
+            SubCmd::Dec(d) => d.run(global_args)?,
         };
         Ok(())
     }
@@ -6,6 +6,8 @@ use clap::{
 use core::str::FromStr;
 use soroban_env_host::xdr;
 
+use crate::commands::global;
+
 #[derive(Parser, Debug, Clone)]
 #[group(skip)]
 pub struct Cmd {
@@ -48,11 +50,16 @@ pub enum Error {
 }
 
 impl Cmd {
-    pub fn run(&self) -> Result<(), Error> {
+    pub fn run(&self, global_args: &global::Args) -> Result<(), Error> {
         let value =
             xdr::Type::from_xdr_base64(self.r#type, self.xdr.clone()).map_err(Error::Xdr)?;
 
-        match self.output {
+        let output = if global_args.is_json() {
+            Output::Json
+        } else {
+            self.output
+        };
+        match output {
             Output::Default => println!("{value:#?}"),
             Output::Json => println!(
                 "{}",
@@ -9,6 +9,9 @@ pub mod events;
 pub mod global;
 pub mod lab;
 pub mod plugin;
+pub mod sandbox;
+pub mod tx;
+pub mod txn_result;
 pub mod version;
 
 pub const HEADING_SANDBOX: &str = "Options (Sandbox)";
@@ -73,12 +76,14 @@ impl Root {
     }
     pub async fn run(&mut self) -> Result<(), Error> {
         match &mut self.cmd {
-            Cmd::Contract(contract) => contract.run().await?,
-            Cmd::Events(events) => events.run().await?,
-            Cmd::Lab(lab) => lab.run().await?,
+            Cmd::Contract(contract) => contract.run(&self.global_args).await?,
+            Cmd::Events(events) => events.run(&self.global_args).await?,
+            Cmd::Lab(lab) => lab.run(&self.global_args).await?,
+            Cmd::Tx(tx) => tx.run(&self.global_args).await?,
+            Cmd::Sandbox(sandbox) => sandbox.run(&self.global_args).await?,
             Cmd::Version(version) => version.run(),
             Cmd::Completion(completion) => completion.run(),
-            Cmd::Config(config) => config.run()?,
+            Cmd::Config(config) => config.run(&self.global_args)?,
         };
         Ok(())
     }
@@ -102,6 +107,12 @@ pub enum Cmd {
     Config(config::Cmd),
     /// Watch the network for contract events
     Events(events::Cmd),
+    /// Sign, submit and look up transactions, e.g. ones built with `--build-only`
+    #[command(subcommand)]
+    Tx(tx::Cmd),
+    /// Inspect and change the state of the local sandbox
+    #[command(subcommand)]
+    Sandbox(sandbox::Cmd),
     /// Experiment with early features and expert tools
     #[command(subcommand)]
     Lab(lab::Cmd),
@@ -124,4 +135,8 @@ pub enum Error {
     Lab(#[from] lab::Error),
     #[error(transparent)]
     Config(#[from] config::Error),
+    #[error(transparent)]
+    Tx(#[from] tx::Error),
+    #[error(transparent)]
+    Sandbox(#[from] sandbox::Error),
 }
@@ -0,0 +1,405 @@
+use std::ffi::OsString;
+use std::path::Path;
+use std::str::FromStr;
+
+use clap::{arg, command, Parser};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use soroban_env_host::{
+    xdr::{
+        AccountId, ConfigSettingEntry, ConfigSettingId, ContractEntryBodyType, Error as XdrError,
+        Hash, LedgerEntry, LedgerEntryData, LedgerEntryExt, LedgerKey, LedgerKeyAccount,
+        LedgerKeyConfigSetting, LedgerKeyContractData, PublicKey, ReadXdr, ScAddress,
+        ScSpecTypeDef, ScVal, StateExpirationSettings, Uint256,
+    },
+    LedgerInfo,
+};
+use soroban_ledger_snapshot::LedgerSnapshot;
+
+use crate::{
+    commands::{
+        config,
+        contract::{invoke, Durability},
+        global,
+    },
+    rpc::{self, Client},
+    utils, Pwd,
+};
+
+#[derive(Parser, Debug, Clone)]
+#[group(skip)]
+pub struct Cmd {
+    /// Contract ID to fork. Its instance and code are always copied
+    #[arg(long = "id")]
+    pub contract_id: String,
+    /// Storage key of the contract to copy (symbols only)
+    #[arg(long = "key")]
+    pub key: Vec<String>,
+    /// Storage key of the contract to copy (base64-encoded XDR ScVal)
+    #[arg(long = "key-xdr")]
+    pub key_xdr: Vec<String>,
+    /// Durability of the storage keys
+    #[arg(long, value_enum, default_value = "persistent")]
+    pub durability: Durability,
+    /// Account to copy, as an identity name or public key. Can be passed multiple times
+    #[arg(long = "account")]
+    pub accounts: Vec<String>,
+    /// Invocation to simulate to find the ledger entries to copy, e.g. `-- transfer --from alice
+    /// --to bob --amount 10`. All the entries in the footprint of the simulation are copied
+    #[arg(last = true, id = "CONTRACT_FN_AND_ARGS")]
+    pub slop: Vec<OsString>,
+
+    #[command(flatten)]
+    pub config: config::Args,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("forking requires a network, pass `--network` or `--rpc-url`")]
+    NetworkNotProvided,
+    #[error("cannot parse contract ID {0}: {1}")]
+    CannotParseContractId(String, stellar_strkey::DecodeError),
+    #[error("parsing key {key}: {error}")]
+    CannotParseKey {
+        key: String,
+        error: soroban_spec_tools::Error,
+    },
+    #[error("parsing XDR key {key}: {error}")]
+    CannotParseXdrKey { key: String, error: XdrError },
+    #[error("cannot parse last modified ledger {0}")]
+    CannotParseLastModifiedLedger(String),
+    #[error("cannot parse latest ledger {0}")]
+    CannotParseLatestLedger(String),
+    #[error("contract {0} not found")]
+    ContractNotFound(String),
+    #[error(transparent)]
+    Config(#[from] config::Error),
+    #[error(transparent)]
+    Invoke(#[from] invoke::Error),
+    #[error(transparent)]
+    Rpc(#[from] rpc::Error),
+    #[error(transparent)]
+    Xdr(#[from] XdrError),
+}
+
+impl FromStr for Cmd {
+    type Err = clap::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use clap::{CommandFactory, FromArgMatches};
+        Self::from_arg_matches_mut(&mut Self::command().get_matches_from(s.split_whitespace()))
+    }
+}
+
+impl Pwd for Cmd {
+    fn set_pwd(&mut self, pwd: &Path) {
+        self.config.set_pwd(pwd);
+    }
+}
+
+impl Cmd {
+    pub async fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        if self.config.is_no_network() {
+            return Err(Error::NetworkNotProvided);
+        }
+        let network = self.config.get_network()?;
+        let client = Client::new(&network.rpc_url)?;
+        let network_info = client.get_network().await?;
+        let contract_id = self.contract_id()?;
+
+        let instance_key = LedgerKey::ContractData(LedgerKeyContractData {
+            contract: ScAddress::Contract(Hash(contract_id)),
+            key: ScVal::LedgerKeyContractInstance,
+            durability: Durability::Persistent.into(),
+            body_type: ContractEntryBodyType::DataEntry,
+        });
+        let instance = parse_entries(
+            client
+                .get_ledger_entries(vec![instance_key.clone()])
+                .await?,
+        )?;
+        if instance.is_empty() {
+            return Err(Error::ContractNotFound(self.contract_id.clone()));
+        }
+
+        let mut keys = self.parse_keys(contract_id)?;
+        keys.push(instance_key);
+        keys.extend(
+            instance
+                .iter()
+                .filter_map(|(_, entry)| utils::instance_code_key(entry)),
+        );
+        for account in &self.accounts {
+            keys.push(self.account_key(account)?);
+        }
+        if !self.slop.is_empty() {
+            let footprint = invoke::Cmd {
+                contract_id: self.contract_id.clone(),
+                slop: self.slop.clone(),
+                config: self.config.clone(),
+                ..Default::default()
+            }
+            .simulate_footprint()
+            .await?;
+            keys.extend(footprint.read_only.iter().cloned());
+            keys.extend(footprint.read_write.iter().cloned());
+        }
+        keys.push(LedgerKey::ConfigSetting(LedgerKeyConfigSetting {
+            config_setting_id: ConfigSettingId::StateExpiration,
+        }));
+        keys.sort();
+        keys.dedup();
+
+        let mut entries = vec![];
+        let mut latest_ledger = 0;
+        // Split the keys across requests to keep each response small
+        for chunk in keys.chunks(rpc::LEDGER_KEYS_PER_REQUEST) {
+            let response = client.get_ledger_entries(chunk.to_vec()).await?;
+            latest_ledger = response
+                .latest_ledger
+                .parse()
+                .map_err(|_| Error::CannotParseLatestLedger(response.latest_ledger.clone()))?;
+            entries.extend(parse_entries(response)?);
+        }
+        let close_time = latest_ledger_close_time(&client).await?;
+
+        let mut state = self.config.get_state()?;
+        let mut ledger_info = state.ledger_info();
+        set_network(&mut ledger_info, &network_info, latest_ledger, close_time);
+        let mut count = 0;
+        for (key, entry) in entries {
+            if set_expiration_settings(&mut ledger_info, &entry) {
+                continue;
+            }
+            upsert(&mut state, key, entry);
+            count += 1;
+        }
+        state.set_ledger_info(ledger_info);
+        self.config.set_state(&mut state)?;
+
+        if global_args.is_json() {
+            println!("{:#}", json!({ "ledger": latest_ledger, "entries": count }));
+        } else {
+            println!("Forked {count} ledger entries as of ledger {latest_ledger}");
+        }
+        Ok(())
+    }
+
+    fn contract_id(&self) -> Result<[u8; 32], Error> {
+        utils::contract_id_from_str(&self.contract_id)
+            .map_err(|e| Error::CannotParseContractId(self.contract_id.clone(), e))
+    }
+
+    fn parse_keys(&self, contract_id: [u8; 32]) -> Result<Vec<LedgerKey>, Error> {
+        let mut keys: Vec<ScVal> = vec![];
+        for key in &self.key {
+            keys.push(
+                soroban_spec_tools::from_string_primitive(key, &ScSpecTypeDef::Symbol).map_err(
+                    |e| Error::CannotParseKey {
+                        key: key.clone(),
+                        error: e,
+                    },
+                )?,
+            );
+        }
+        for key in &self.key_xdr {
+            keys.push(
+                ScVal::from_xdr_base64(key).map_err(|e| Error::CannotParseXdrKey {
+                    key: key.clone(),
+                    error: e,
+                })?,
+            );
+        }
+        Ok(keys
+            .into_iter()
+            .map(|key| {
+                LedgerKey::ContractData(LedgerKeyContractData {
+                    contract: ScAddress::Contract(Hash(contract_id)),
+                    key,
+                    durability: self.durability.into(),
+                    body_type: ContractEntryBodyType::DataEntry,
+                })
+            })
+            .collect())
+    }
+
+    fn account_key(&self, account: &str) -> Result<LedgerKey, Error> {
+        let public_key = match stellar_strkey::ed25519::PublicKey::from_string(account) {
+            Ok(public_key) => public_key.0,
+            Err(_) => self
+                .config
+                .account(account)?
+                .key_pair(None)
+                .map_err(config::Error::from)?
+                .public
+                .to_bytes(),
+        };
+        Ok(LedgerKey::Account(LedgerKeyAccount {
+            account_id: AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(public_key))),
+        }))
+    }
+}
+
+fn parse_entries(
+    response: rpc::GetLedgerEntriesResponse,
+) -> Result<Vec<(LedgerKey, LedgerEntry)>, Error> {
+    response
+        .entries
+        .unwrap_or_default()
+        .into_iter()
+        .map(|result| {
+            let last_modified_ledger_seq = result
+                .last_modified_ledger
+                .parse()
+                .map_err(|_| Error::CannotParseLastModifiedLedger(result.last_modified_ledger))?;
+            Ok((
+                LedgerKey::from_xdr_base64(&result.key)?,
+                LedgerEntry {
+                    last_modified_ledger_seq,
+                    data: LedgerEntryData::from_xdr_base64(&result.xdr)?,
+                    ext: LedgerEntryExt::V0,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Close time of the latest ledger of the network, in seconds since the epoch.
+///
+/// This is a workaround: of the server's methods only getTransaction reports the close time, and
+/// it does so even for a transaction it doesn't know, so this asks for the all-zero hash and reads
+/// the close time off the not found response. Replace it once getLatestLedger reports the close
+/// time.
+async fn latest_ledger_close_time(client: &Client) -> Result<u64, Error> {
+    Ok(client
+        .get_transaction(&hex::encode([0u8; 32]))
+        .await?
+        .latest_ledger_close_time)
+}
+
+/// Make the sandbox ledger the `latest_ledger` of the network, closed at `close_time`
+fn set_network(
+    ledger_info: &mut LedgerInfo,
+    network: &rpc::GetNetworkResponse,
+    latest_ledger: u32,
+    close_time: u64,
+) {
+    ledger_info.sequence_number = latest_ledger;
+    ledger_info.timestamp = close_time;
+    ledger_info.protocol_version = network.protocol_version;
+    ledger_info.network_id = Sha256::digest(network.passphrase.as_bytes()).into();
+}
+
+/// Copy the state expiration settings of the network into the ledger info when `entry` holds
+/// them, returning whether it did
+fn set_expiration_settings(ledger_info: &mut LedgerInfo, entry: &LedgerEntry) -> bool {
+    let LedgerEntryData::ConfigSetting(ConfigSettingEntry::StateExpiration(
+        StateExpirationSettings {
+            max_entry_expiration,
+            min_temp_entry_expiration,
+            min_persistent_entry_expiration,
+            ..
+        },
+    )) = &entry.data
+    else {
+        return false;
+    };
+    ledger_info.max_entry_expiration = *max_entry_expiration;
+    ledger_info.min_temp_entry_expiration = *min_temp_entry_expiration;
+    ledger_info.min_persistent_entry_expiration = *min_persistent_entry_expiration;
+    true
+}
+
+fn upsert(state: &mut LedgerSnapshot, key: LedgerKey, entry: LedgerEntry) {
+    match state.ledger_entries.iter_mut().find(|(k, _)| **k == key) {
+        Some((_, e)) => **e = entry,
+        None => state.ledger_entries.push((Box::new(key), Box::new(entry))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use soroban_env_host::xdr::WriteXdr;
+
+    use super::*;
+
+    fn account() -> (LedgerKey, LedgerEntry) {
+        let account_id = AccountId(PublicKey::PublicKeyTypeEd25519(Uint256([1; 32])));
+        (
+            LedgerKey::Account(LedgerKeyAccount {
+                account_id: account_id.clone(),
+            }),
+            utils::default_account_ledger_entry(account_id),
+        )
+    }
+
+    fn response(last_modified_ledger: &str) -> rpc::GetLedgerEntriesResponse {
+        let (key, entry) = account();
+        rpc::GetLedgerEntriesResponse {
+            entries: Some(vec![rpc::LedgerEntryResult {
+                key: key.to_xdr_base64().unwrap(),
+                xdr: entry.data.to_xdr_base64().unwrap(),
+                last_modified_ledger: last_modified_ledger.to_string(),
+            }]),
+            latest_ledger: "12".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_entries() {
+        let (key, entry) = account();
+        assert_eq!(
+            parse_entries(response("10")).unwrap(),
+            vec![(
+                key,
+                LedgerEntry {
+                    last_modified_ledger_seq: 10,
+                    ..entry
+                }
+            )]
+        );
+        assert!(matches!(
+            parse_entries(response("ten")),
+            Err(Error::CannotParseLastModifiedLedger(ledger)) if ledger == "ten"
+        ));
+        assert!(parse_entries(rpc::GetLedgerEntriesResponse {
+            entries: None,
+            latest_ledger: "12".to_string(),
+        })
+        .unwrap()
+        .is_empty());
+    }
+
+    #[test]
+    fn test_set_network() {
+        let mut ledger_info = LedgerInfo {
+            sequence_number: 1,
+            timestamp: 5,
+            ..Default::default()
+        };
+        let network = rpc::GetNetworkResponse {
+            friendbot_url: None,
+            passphrase: "Test SDF Network ; September 2015".to_string(),
+            protocol_version: 20,
+        };
+        set_network(&mut ledger_info, &network, 1234, 1_690_000_000);
+        assert_eq!(ledger_info.sequence_number, 1234);
+        assert_eq!(ledger_info.timestamp, 1_690_000_000);
+        assert_eq!(ledger_info.protocol_version, 20);
+        assert_eq!(
+            ledger_info.network_id,
+            <[u8; 32]>::from(Sha256::digest(network.passphrase.as_bytes()))
+        );
+    }
+
+    #[test]
+    fn test_set_expiration_settings_ignores_other_entries() {
+        let mut ledger_info = LedgerInfo {
+            max_entry_expiration: 100,
+            ..Default::default()
+        };
+        let (_, entry) = account();
+        assert!(!set_expiration_settings(&mut ledger_info, &entry));
+        assert_eq!(ledger_info.max_entry_expiration, 100);
+    }
+}
@@ -0,0 +1,206 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use clap::{arg, command, Parser};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use soroban_env_host::LedgerInfo;
+
+use crate::{
+    commands::{
+        config::{ledger_file, locator},
+        global, HEADING_SANDBOX,
+    },
+    Pwd,
+};
+
+#[derive(Parser, Debug, Clone)]
+#[group(skip)]
+pub struct Cmd {
+    #[command(flatten)]
+    pub ledger: Args,
+    #[command(flatten)]
+    pub ledger_file: ledger_file::Args,
+    #[command(flatten)]
+    pub locator: locator::Args,
+}
+
+/// Changes to the ledger the sandbox runs contracts in
+#[derive(Debug, clap::Args, Clone, Default)]
+#[group(skip)]
+pub struct Args {
+    /// Set the sequence number of the sandbox ledger
+    #[arg(
+        long,
+        conflicts_with = "advance_ledgers",
+        help_heading = HEADING_SANDBOX,
+    )]
+    pub ledger_sequence: Option<u32>,
+    /// Advance the sequence number of the sandbox ledger by this many ledgers
+    #[arg(long, help_heading = HEADING_SANDBOX)]
+    pub advance_ledgers: Option<u32>,
+    /// Set the close time of the sandbox ledger, in seconds since the Unix epoch
+    #[arg(
+        long,
+        conflicts_with = "advance_time",
+        help_heading = HEADING_SANDBOX,
+    )]
+    pub ledger_timestamp: Option<u64>,
+    /// Advance the close time of the sandbox ledger by this many seconds
+    #[arg(long, help_heading = HEADING_SANDBOX)]
+    pub advance_time: Option<u64>,
+    /// Set the protocol version of the sandbox ledger
+    #[arg(long, help_heading = HEADING_SANDBOX)]
+    pub ledger_protocol_version: Option<u32>,
+    /// Set the network passphrase of the sandbox ledger, which changes its network ID
+    #[arg(long, help_heading = HEADING_SANDBOX)]
+    pub ledger_network_passphrase: Option<String>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("ledger sequence overflows advancing {sequence} by {ledgers} ledgers")]
+    SequenceOverflow { sequence: u32, ledgers: u32 },
+    #[error("ledger timestamp overflows advancing {timestamp} by {seconds} seconds")]
+    TimestampOverflow { timestamp: u64, seconds: u64 },
+    #[error(transparent)]
+    Ledger(#[from] ledger_file::Error),
+    #[error(transparent)]
+    Locator(#[from] locator::Error),
+}
+
+impl FromStr for Cmd {
+    type Err = clap::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use clap::{CommandFactory, FromArgMatches};
+        Self::from_arg_matches_mut(&mut Self::command().get_matches_from(s.split_whitespace()))
+    }
+}
+
+impl Pwd for Cmd {
+    fn set_pwd(&mut self, pwd: &Path) {
+        self.locator.set_pwd(pwd);
+    }
+}
+
+impl Cmd {
+    pub fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        let config_dir = self.locator.config_dir()?;
+        let mut state = self.ledger_file.read(&config_dir)?;
+        if !self.ledger.is_empty() {
+            let mut ledger_info = state.ledger_info();
+            self.ledger.apply(&mut ledger_info, 0, 0)?;
+            state.set_ledger_info(ledger_info);
+            self.ledger_file.write(&mut state, &config_dir)?;
+        }
+
+        if global_args.is_json() {
+            let json = json!({
+                "sequence_number": state.sequence_number,
+                "timestamp": state.timestamp,
+                "protocol_version": state.protocol_version,
+                "network_id": hex::encode(state.network_id),
+            });
+            println!("{json:#}");
+        } else {
+            println!("Sequence number: {}", state.sequence_number);
+            println!("Timestamp: {}", state.timestamp);
+            println!("Protocol version: {}", state.protocol_version);
+            println!("Network ID: {}", hex::encode(state.network_id));
+        }
+        Ok(())
+    }
+}
+
+impl Args {
+    pub fn is_empty(&self) -> bool {
+        self.ledger_sequence.is_none()
+            && self.advance_ledgers.is_none()
+            && self.ledger_timestamp.is_none()
+            && self.advance_time.is_none()
+            && self.ledger_protocol_version.is_none()
+            && self.ledger_network_passphrase.is_none()
+    }
+
+    /// Apply the changes to `ledger_info`. Unless set or advanced explicitly, the sequence number
+    /// advances by `ledgers` and the close time by `seconds`.
+    pub fn apply(
+        &self,
+        ledger_info: &mut LedgerInfo,
+        ledgers: u32,
+        seconds: u64,
+    ) -> Result<(), Error> {
+        let sequence = ledger_info.sequence_number;
+        ledger_info.sequence_number = if let Some(sequence) = self.ledger_sequence {
+            sequence
+        } else {
+            let ledgers = self.advance_ledgers.unwrap_or(ledgers);
+            sequence
+                .checked_add(ledgers)
+                .ok_or(Error::SequenceOverflow { sequence, ledgers })?
+        };
+
+        let timestamp = ledger_info.timestamp;
+        ledger_info.timestamp = if let Some(timestamp) = self.ledger_timestamp {
+            timestamp
+        } else {
+            let seconds = self.advance_time.unwrap_or(seconds);
+            timestamp
+                .checked_add(seconds)
+                .ok_or(Error::TimestampOverflow { timestamp, seconds })?
+        };
+
+        if let Some(protocol_version) = self.ledger_protocol_version {
+            ledger_info.protocol_version = protocol_version;
+        }
+        if let Some(passphrase) = &self.ledger_network_passphrase {
+            ledger_info.network_id = Sha256::digest(passphrase.as_bytes()).into();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ledger_info() -> LedgerInfo {
+        LedgerInfo {
+            sequence_number: 10,
+            timestamp: 100,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_apply_defaults() {
+        let mut info = ledger_info();
+        Args::default().apply(&mut info, 1, 5).unwrap();
+        assert_eq!((info.sequence_number, info.timestamp), (11, 105));
+    }
+
+    #[test]
+    fn test_apply_set_and_advance() {
+        let mut info = ledger_info();
+        let args = Args {
+            advance_ledgers: Some(1000),
+            ledger_timestamp: Some(2_000_000),
+            ledger_protocol_version: Some(20),
+            ..Default::default()
+        };
+        args.apply(&mut info, 1, 5).unwrap();
+        assert_eq!(info.sequence_number, 1010);
+        assert_eq!(info.timestamp, 2_000_000);
+        assert_eq!(info.protocol_version, 20);
+
+        let args = Args {
+            advance_ledgers: Some(u32::MAX),
+            ..Default::default()
+        };
+        assert!(matches!(
+            args.apply(&mut info, 1, 5),
+            Err(Error::SequenceOverflow { .. })
+        ));
+    }
+}
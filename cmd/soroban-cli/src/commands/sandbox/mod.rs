@@ -0,0 +1,41 @@
+use clap::Parser;
+
+use super::global;
+
+pub mod fork;
+pub mod ledger;
+pub mod snapshot;
+
+#[derive(Debug, Parser)]
+pub enum Cmd {
+    /// Show or change the sequence number, close time, protocol version and network of the
+    /// sandbox ledger, e.g. to jump forward in time to test timelocks or entry expiration
+    Ledger(ledger::Cmd),
+    /// Copy a deployed contract's instance, code and storage from a network into the sandbox,
+    /// along with the network's ledger info, to test against real state locally
+    Fork(fork::Cmd),
+    /// Save, list, restore and compare named snapshots of the sandbox ledger
+    #[command(subcommand)]
+    Snapshot(snapshot::Cmd),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Ledger(#[from] ledger::Error),
+    #[error(transparent)]
+    Fork(#[from] fork::Error),
+    #[error(transparent)]
+    Snapshot(#[from] snapshot::Error),
+}
+
+impl Cmd {
+    pub async fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        match self {
+            Cmd::Ledger(cmd) => cmd.run(global_args)?,
+            Cmd::Fork(cmd) => cmd.run(global_args).await?,
+            Cmd::Snapshot(cmd) => cmd.run(global_args)?,
+        };
+        Ok(())
+    }
+}
@@ -0,0 +1,362 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+
+use clap::{command, Parser};
+use serde_json::{json, Value};
+use soroban_env_host::xdr::{
+    AccountEntry, AccountId, ContractDataEntry, ContractDataEntryBody, ContractDataEntryData,
+    ContractExecutable, LedgerEntry, LedgerEntryData, LedgerKey, LedgerKeyAccount,
+    LedgerKeyContractData, PublicKey, ScAddress, ScContractInstance, ScMapEntry, ScVal,
+};
+use soroban_ledger_snapshot::LedgerSnapshot;
+use soroban_spec_tools::Spec;
+
+use super::store;
+use crate::{
+    commands::{
+        config::{ledger_file, locator},
+        global,
+    },
+    utils::{self, archival},
+    Pwd,
+};
+
+#[derive(Parser, Debug, Clone)]
+#[group(skip)]
+pub struct Cmd {
+    /// Name of the snapshot to compare from
+    pub from: String,
+    /// Name of the snapshot to compare to. Default: the sandbox ledger
+    pub to: Option<String>,
+    #[command(flatten)]
+    pub ledger_file: ledger_file::Args,
+    #[command(flatten)]
+    pub locator: locator::Args,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Store(#[from] store::Error),
+    #[error(transparent)]
+    Ledger(#[from] ledger_file::Error),
+    #[error(transparent)]
+    Locator(#[from] locator::Error),
+    #[error(transparent)]
+    Spec(#[from] soroban_spec_tools::Error),
+}
+
+impl FromStr for Cmd {
+    type Err = clap::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use clap::{CommandFactory, FromArgMatches};
+        Self::from_arg_matches_mut(&mut Self::command().get_matches_from(s.split_whitespace()))
+    }
+}
+
+impl Pwd for Cmd {
+    fn set_pwd(&mut self, pwd: &Path) {
+        self.locator.set_pwd(pwd);
+    }
+}
+
+impl Cmd {
+    pub fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        let config_dir = self.locator.config_dir()?;
+        let from = store::read(&config_dir, &self.from)?;
+        let to = match &self.to {
+            Some(name) => store::read(&config_dir, name)?,
+            None => self.ledger_file.read(&config_dir)?,
+        };
+        let changes = changes(&from, &to);
+        let specs = Specs::new(&changes, &from, &to);
+
+        if global_args.is_json() {
+            let mut added = vec![];
+            let mut removed = vec![];
+            let mut changed = vec![];
+            for change in &changes {
+                let key = specs.key_to_string(change.key())?;
+                match change {
+                    Change::Added(_, entry) => {
+                        added.push(specs.entry_to_json(Some(key), entry)?);
+                    }
+                    Change::Removed(_, entry) => {
+                        removed.push(specs.entry_to_json(Some(key), entry)?);
+                    }
+                    Change::Changed(_, before, after) => changed.push(json!({
+                        "key": key,
+                        "before": specs.entry_to_json(None, before)?,
+                        "after": specs.entry_to_json(None, after)?,
+                    })),
+                }
+            }
+            let json = json!({ "added": added, "removed": removed, "changed": changed });
+            println!("{json:#}");
+        } else {
+            for change in &changes {
+                let key = specs.key_to_string(change.key())?;
+                match change {
+                    Change::Added(_, entry) => {
+                        println!("+ {key}: {}", specs.entry_to_string(entry)?);
+                    }
+                    Change::Removed(_, entry) => {
+                        println!("- {key}: {}", specs.entry_to_string(entry)?);
+                    }
+                    Change::Changed(_, before, after) => println!(
+                        "~ {key}: {} -> {}",
+                        specs.entry_to_string(before)?,
+                        specs.entry_to_string(after)?
+                    ),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+enum Change<'a> {
+    Added(&'a LedgerKey, &'a LedgerEntry),
+    Removed(&'a LedgerKey, &'a LedgerEntry),
+    Changed(&'a LedgerKey, &'a LedgerEntry, &'a LedgerEntry),
+}
+
+impl Change<'_> {
+    fn key(&self) -> &LedgerKey {
+        match self {
+            Change::Added(key, _) | Change::Removed(key, _) | Change::Changed(key, _, _) => key,
+        }
+    }
+}
+
+/// Entries added, removed and changed from `from` to `to`, sorted by key. Entries are only
+/// considered changed if their data changed, not just the ledger they were last modified in.
+fn changes<'a>(from: &'a LedgerSnapshot, to: &'a LedgerSnapshot) -> Vec<Change<'a>> {
+    let entries = |state: &'a LedgerSnapshot| {
+        state
+            .ledger_entries
+            .iter()
+            .map(|(k, e)| (k.as_ref(), e.as_ref()))
+            .collect::<HashMap<_, _>>()
+    };
+    let before = entries(from);
+    let after = entries(to);
+    let mut changes = vec![];
+    for (&key, &entry) in &before {
+        match after.get(key) {
+            None => changes.push(Change::Removed(key, entry)),
+            Some(&new) if new.data != entry.data => changes.push(Change::Changed(key, entry, new)),
+            Some(_) => (),
+        }
+    }
+    for (&key, &entry) in &after {
+        if !before.contains_key(key) {
+            changes.push(Change::Added(key, entry));
+        }
+    }
+    changes.sort_by(|a, b| a.key().cmp(b.key()));
+    changes
+}
+
+/// Specs of the contracts whose storage changed, used to decode their keys and values
+struct Specs(HashMap<[u8; 32], Spec>);
+
+impl Specs {
+    fn new(changes: &[Change], from: &LedgerSnapshot, to: &LedgerSnapshot) -> Self {
+        let contracts = changes
+            .iter()
+            .filter_map(|change| match change.key() {
+                LedgerKey::ContractData(LedgerKeyContractData {
+                    contract: ScAddress::Contract(hash),
+                    ..
+                }) => Some(hash.0),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        Self(
+            contracts
+                .into_iter()
+                .map(|id| {
+                    let spec = utils::get_contract_spec_from_state(to, id)
+                        .or_else(|_| utils::get_contract_spec_from_state(from, id))
+                        .map(Spec::new)
+                        .unwrap_or_default();
+                    (id, spec)
+                })
+                .collect(),
+        )
+    }
+
+    fn spec(&self, contract: &ScAddress) -> &Spec {
+        static NO_SPEC: Spec = Spec(None);
+        match contract {
+            ScAddress::Contract(hash) => self.0.get(&hash.0).unwrap_or(&NO_SPEC),
+            ScAddress::Account(_) => &NO_SPEC,
+        }
+    }
+
+    fn key_to_string(&self, key: &LedgerKey) -> Result<String, Error> {
+        Ok(match key {
+            LedgerKey::ContractData(LedgerKeyContractData {
+                contract,
+                key: ScVal::LedgerKeyContractInstance,
+                ..
+            }) => format!("instance of contract {}", address_to_string(contract)),
+            LedgerKey::ContractData(LedgerKeyContractData {
+                contract,
+                key,
+                durability,
+                ..
+            }) => format!(
+                "{} entry {} of contract {}",
+                durability.name(),
+                self.spec(contract).to_string_inferred(key)?,
+                address_to_string(contract)
+            ),
+            LedgerKey::Account(LedgerKeyAccount {
+                account_id: AccountId(PublicKey::PublicKeyTypeEd25519(key)),
+            }) => format!("account {}", stellar_strkey::ed25519::PublicKey(key.0)),
+            _ => archival::key_to_string(key),
+        })
+    }
+
+    /// Value of the entry: the decoded value of contract data, the executable and storage of
+    /// contract instances, and the balance and sequence number of accounts
+    fn value(&self, entry: &LedgerEntry) -> Result<Value, Error> {
+        Ok(match &entry.data {
+            LedgerEntryData::ContractData(ContractDataEntry {
+                contract,
+                body: ContractDataEntryBody::DataEntry(ContractDataEntryData { val, .. }),
+                ..
+            }) => {
+                let spec = self.spec(contract);
+                if let ScVal::ContractInstance(ScContractInstance {
+                    executable,
+                    storage,
+                }) = val
+                {
+                    let executable = match executable {
+                        ContractExecutable::Wasm(hash) => hex::encode(hash.0),
+                        ContractExecutable::Token => "token".to_string(),
+                    };
+                    let mut instance_storage = serde_json::Map::new();
+                    for ScMapEntry { key, val } in storage.iter().flat_map(|map| map.iter()) {
+                        instance_storage.insert(
+                            spec.to_string_inferred(key)?,
+                            spec.xdr_to_json_inferred(val)?,
+                        );
+                    }
+                    json!({ "executable": executable, "storage": instance_storage })
+                } else {
+                    spec.xdr_to_json_inferred(val)?
+                }
+            }
+            LedgerEntryData::Account(AccountEntry {
+                balance, seq_num, ..
+            }) => json!({ "balance": balance, "seq_num": seq_num.0 }),
+            _ => Value::Null,
+        })
+    }
+
+    fn entry_to_json(&self, key: Option<String>, entry: &LedgerEntry) -> Result<Value, Error> {
+        let mut json = json!({
+            "value": self.value(entry)?,
+            "expiration_ledger": archival::expiration_ledger_seq(entry),
+        });
+        if let (Some(key), Value::Object(object)) = (key, &mut json) {
+            object.insert("key".to_string(), json!(key));
+        }
+        Ok(json)
+    }
+
+    fn entry_to_string(&self, entry: &LedgerEntry) -> Result<String, Error> {
+        let value = self.value(entry)?;
+        let mut parts = vec![];
+        if !value.is_null() {
+            parts.push(value.to_string());
+        }
+        if let Some(expiration) = archival::expiration_ledger_seq(entry) {
+            parts.push(format!("expires after ledger {expiration}"));
+        }
+        Ok(parts.join(", "))
+    }
+}
+
+fn address_to_string(address: &ScAddress) -> String {
+    match address {
+        ScAddress::Contract(hash) => stellar_strkey::Contract(hash.0).to_string(),
+        ScAddress::Account(_) => format!("{address:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use soroban_env_host::xdr::{
+        ContractDataDurability, ContractEntryBodyType, Hash, LedgerEntryExt,
+    };
+
+    use super::*;
+
+    fn entry(key: u32, val: u32) -> (Box<LedgerKey>, Box<LedgerEntry>) {
+        let contract = ScAddress::Contract(Hash([0; 32]));
+        (
+            Box::new(LedgerKey::ContractData(LedgerKeyContractData {
+                contract: contract.clone(),
+                key: ScVal::U32(key),
+                durability: ContractDataDurability::Persistent,
+                body_type: ContractEntryBodyType::DataEntry,
+            })),
+            Box::new(LedgerEntry {
+                last_modified_ledger_seq: val,
+                data: LedgerEntryData::ContractData(ContractDataEntry {
+                    contract,
+                    key: ScVal::U32(key),
+                    durability: ContractDataDurability::Persistent,
+                    body: ContractDataEntryBody::DataEntry(ContractDataEntryData {
+                        flags: 0,
+                        val: ScVal::U32(val),
+                    }),
+                    expiration_ledger_seq: 100,
+                }),
+                ext: LedgerEntryExt::V0,
+            }),
+        )
+    }
+
+    #[test]
+    fn test_changes() {
+        let from = LedgerSnapshot {
+            ledger_entries: vec![entry(1, 1), entry(2, 2), entry(3, 3)],
+            ..Default::default()
+        };
+        let mut touched = entry(3, 3);
+        touched.1.last_modified_ledger_seq = 10;
+        let to = LedgerSnapshot {
+            ledger_entries: vec![entry(4, 4), entry(2, 20), touched],
+            ..Default::default()
+        };
+        let changes = changes(&from, &to);
+        let specs = Specs::new(&changes, &from, &to);
+        let summary = changes
+            .iter()
+            .map(|change| {
+                let kind = match change {
+                    Change::Added(..) => "+",
+                    Change::Removed(..) => "-",
+                    Change::Changed(..) => "~",
+                };
+                let key = specs.key_to_string(change.key()).unwrap();
+                format!("{kind} {}", key.split(' ').nth(2).unwrap())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(summary, vec!["- 1", "~ 2", "+ 4"]);
+
+        let (_, entry) = entry(2, 20);
+        assert_eq!(
+            specs.entry_to_string(&entry).unwrap(),
+            "20, expires after ledger 100"
+        );
+    }
+}
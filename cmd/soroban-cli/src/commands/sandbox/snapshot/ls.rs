@@ -0,0 +1,32 @@
+use clap::{command, Parser};
+use serde_json::json;
+
+use super::store;
+use crate::commands::{config::locator, global};
+
+#[derive(Parser, Debug, Clone)]
+#[group(skip)]
+pub struct Cmd {
+    #[command(flatten)]
+    pub locator: locator::Args,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Store(#[from] store::Error),
+    #[error(transparent)]
+    Locator(#[from] locator::Error),
+}
+
+impl Cmd {
+    pub fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        let names = store::list(&self.locator.config_dir()?)?;
+        if global_args.is_json() {
+            println!("{:#}", json!({ "snapshots": names }));
+        } else {
+            println!("{}", names.join("\n"));
+        }
+        Ok(())
+    }
+}
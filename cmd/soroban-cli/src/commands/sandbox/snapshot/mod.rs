@@ -0,0 +1,46 @@
+use clap::Parser;
+
+use crate::commands::global;
+
+pub mod diff;
+pub mod ls;
+pub mod restore;
+pub mod save;
+pub mod store;
+
+#[derive(Debug, Parser)]
+pub enum Cmd {
+    /// Save the sandbox ledger as a named snapshot
+    Save(save::Cmd),
+    /// List the saved snapshots
+    Ls(ls::Cmd),
+    /// Replace the sandbox ledger with a saved snapshot
+    Restore(restore::Cmd),
+    /// Show the ledger entries added, removed and changed between two snapshots, or between a
+    /// snapshot and the sandbox ledger
+    Diff(diff::Cmd),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Save(#[from] save::Error),
+    #[error(transparent)]
+    Ls(#[from] ls::Error),
+    #[error(transparent)]
+    Restore(#[from] restore::Error),
+    #[error(transparent)]
+    Diff(#[from] diff::Error),
+}
+
+impl Cmd {
+    pub fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        match self {
+            Cmd::Save(cmd) => cmd.run()?,
+            Cmd::Ls(cmd) => cmd.run(global_args)?,
+            Cmd::Restore(cmd) => cmd.run()?,
+            Cmd::Diff(cmd) => cmd.run(global_args)?,
+        };
+        Ok(())
+    }
+}
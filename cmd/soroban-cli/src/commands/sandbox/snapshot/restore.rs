@@ -0,0 +1,55 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use clap::{command, Parser};
+
+use super::store;
+use crate::{
+    commands::config::{ledger_file, locator},
+    Pwd,
+};
+
+#[derive(Parser, Debug, Clone)]
+#[group(skip)]
+pub struct Cmd {
+    /// Name of the snapshot
+    pub name: String,
+    #[command(flatten)]
+    pub ledger_file: ledger_file::Args,
+    #[command(flatten)]
+    pub locator: locator::Args,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Store(#[from] store::Error),
+    #[error(transparent)]
+    Ledger(#[from] ledger_file::Error),
+    #[error(transparent)]
+    Locator(#[from] locator::Error),
+}
+
+impl FromStr for Cmd {
+    type Err = clap::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use clap::{CommandFactory, FromArgMatches};
+        Self::from_arg_matches_mut(&mut Self::command().get_matches_from(s.split_whitespace()))
+    }
+}
+
+impl Pwd for Cmd {
+    fn set_pwd(&mut self, pwd: &Path) {
+        self.locator.set_pwd(pwd);
+    }
+}
+
+impl Cmd {
+    pub fn run(&self) -> Result<(), Error> {
+        let config_dir = self.locator.config_dir()?;
+        let mut state = store::read(&config_dir, &self.name)?;
+        self.ledger_file.write(&mut state, &config_dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use clap::{arg, command, Parser};
+
+use super::store;
+use crate::{
+    commands::config::{ledger_file, locator},
+    Pwd,
+};
+
+#[derive(Parser, Debug, Clone)]
+#[group(skip)]
+pub struct Cmd {
+    /// Name of the snapshot
+    pub name: String,
+    /// Overwrite the snapshot if it already exists
+    #[arg(long)]
+    pub force: bool,
+    #[command(flatten)]
+    pub ledger_file: ledger_file::Args,
+    #[command(flatten)]
+    pub locator: locator::Args,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("snapshot {0} already exists, pass --force to overwrite it")]
+    AlreadyExists(String),
+    #[error(transparent)]
+    Store(#[from] store::Error),
+    #[error(transparent)]
+    Ledger(#[from] ledger_file::Error),
+    #[error(transparent)]
+    Locator(#[from] locator::Error),
+}
+
+impl FromStr for Cmd {
+    type Err = clap::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use clap::{CommandFactory, FromArgMatches};
+        Self::from_arg_matches_mut(&mut Self::command().get_matches_from(s.split_whitespace()))
+    }
+}
+
+impl Pwd for Cmd {
+    fn set_pwd(&mut self, pwd: &Path) {
+        self.locator.set_pwd(pwd);
+    }
+}
+
+impl Cmd {
+    pub fn run(&self) -> Result<(), Error> {
+        let config_dir = self.locator.config_dir()?;
+        if !self.force && store::exists(&config_dir, &self.name)? {
+            return Err(Error::AlreadyExists(self.name.clone()));
+        }
+        let state = self.ledger_file.read(&config_dir)?;
+        store::write(&config_dir, &self.name, &state)?;
+        Ok(())
+    }
+}
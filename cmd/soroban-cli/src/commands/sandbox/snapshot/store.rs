@@ -0,0 +1,121 @@
+use std::{
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use soroban_ledger_snapshot::LedgerSnapshot;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("invalid snapshot name {0:?}, only letters, digits, `-`, `_` and `.` are allowed")]
+    InvalidName(String),
+    #[error("snapshot {0} not found, list the saved snapshots with `soroban sandbox snapshot ls`")]
+    NotFound(String),
+    #[error("reading snapshot {filepath}: {error}")]
+    CannotReadSnapshot {
+        filepath: PathBuf,
+        error: soroban_ledger_snapshot::Error,
+    },
+    #[error("writing snapshot {filepath}: {error}")]
+    CannotWriteSnapshot {
+        filepath: PathBuf,
+        error: soroban_ledger_snapshot::Error,
+    },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Directory snapshots are saved in, `snapshots` in the config directory
+fn dir(config_dir: &Path) -> PathBuf {
+    config_dir.join("snapshots")
+}
+
+pub fn path(config_dir: &Path, name: &str) -> Result<PathBuf, Error> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        return Err(Error::InvalidName(name.to_string()));
+    }
+    Ok(dir(config_dir).join(format!("{name}.json")))
+}
+
+pub fn exists(config_dir: &Path, name: &str) -> Result<bool, Error> {
+    Ok(path(config_dir, name)?.exists())
+}
+
+pub fn read(config_dir: &Path, name: &str) -> Result<LedgerSnapshot, Error> {
+    let filepath = path(config_dir, name)?;
+    if !filepath.exists() {
+        return Err(Error::NotFound(name.to_string()));
+    }
+    LedgerSnapshot::read_file(&filepath)
+        .map_err(|error| Error::CannotReadSnapshot { filepath, error })
+}
+
+pub fn write(config_dir: &Path, name: &str, state: &LedgerSnapshot) -> Result<(), Error> {
+    let filepath = path(config_dir, name)?;
+    fs::create_dir_all(dir(config_dir))?;
+    state
+        .write_file(&filepath)
+        .map_err(|error| Error::CannotWriteSnapshot { filepath, error })
+}
+
+/// Names of the saved snapshots, sorted
+pub fn list(config_dir: &Path) -> Result<Vec<String>, Error> {
+    let dir = dir(config_dir);
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut names = vec![];
+    for entry in fs::read_dir(dir)?.filter_map(Result::ok) {
+        let path = entry.path();
+        if let (Some("json"), Some(name)) = (
+            path.extension().and_then(OsStr::to_str),
+            path.file_stem().and_then(OsStr::to_str),
+        ) {
+            names.push(name.to_string());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_read_list() {
+        let config_dir = assert_fs::TempDir::new().unwrap();
+        assert!(list(config_dir.path()).unwrap().is_empty());
+
+        let state = LedgerSnapshot {
+            sequence_number: 7,
+            ..Default::default()
+        };
+        write(config_dir.path(), "before-upgrade", &state).unwrap();
+        write(config_dir.path(), "a", &LedgerSnapshot::default()).unwrap();
+        assert_eq!(
+            list(config_dir.path()).unwrap(),
+            vec!["a".to_string(), "before-upgrade".to_string()]
+        );
+        assert_eq!(
+            read(config_dir.path(), "before-upgrade")
+                .unwrap()
+                .sequence_number,
+            7
+        );
+        assert!(matches!(
+            read(config_dir.path(), "missing"),
+            Err(Error::NotFound(_))
+        ));
+        assert!(matches!(
+            path(config_dir.path(), "../ledger"),
+            Err(Error::InvalidName(_))
+        ));
+    }
+}
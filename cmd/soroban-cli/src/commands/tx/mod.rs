@@ -0,0 +1,41 @@
+use clap::Parser;
+
+use super::global;
+
+pub mod send;
+pub mod sign;
+pub mod wait;
+pub mod xdr;
+
+#[derive(Debug, Parser)]
+pub enum Cmd {
+    /// Sign a transaction envelope, e.g. one built with `--build-only`, appending the signature to
+    /// any it already has
+    Sign(sign::Cmd),
+    /// Submit a signed transaction envelope to the network and wait for its result
+    Send(send::Cmd),
+    /// Wait for a submitted transaction to be included in a ledger, or look up one that already
+    /// is, and print its result, meta and events
+    Wait(wait::Cmd),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Sign(#[from] sign::Error),
+    #[error(transparent)]
+    Send(#[from] send::Error),
+    #[error(transparent)]
+    Wait(#[from] wait::Error),
+}
+
+impl Cmd {
+    pub async fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        match self {
+            Cmd::Sign(cmd) => cmd.run(global_args)?,
+            Cmd::Send(cmd) => cmd.run(global_args).await?,
+            Cmd::Wait(cmd) => cmd.run(global_args).await?,
+        };
+        Ok(())
+    }
+}
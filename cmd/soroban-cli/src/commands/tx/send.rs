@@ -0,0 +1,105 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use clap::{arg, command, Parser};
+use serde_json::json;
+use soroban_env_host::xdr::ScVal;
+
+use crate::{
+    commands::{
+        config::{locator, network},
+        global,
+        txn_result::{self, TxnResult},
+    },
+    rpc::{self, Client},
+    wait, Pwd,
+};
+
+#[derive(Parser, Debug, Clone)]
+#[group(skip)]
+pub struct Cmd {
+    #[command(flatten)]
+    pub xdr: super::xdr::Args,
+    /// Print the transaction hash right after submitting the transaction instead of waiting for its result. Use `soroban tx wait` to get the result later
+    #[arg(long)]
+    pub no_wait: bool,
+    #[command(flatten)]
+    pub wait: wait::Args,
+    #[command(flatten)]
+    pub network: network::Args,
+    #[command(flatten)]
+    pub locator: locator::Args,
+}
+
+impl FromStr for Cmd {
+    type Err = clap::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use clap::{CommandFactory, FromArgMatches};
+        Self::from_arg_matches_mut(&mut Self::command().get_matches_from(s.split_whitespace()))
+    }
+}
+
+impl Pwd for Cmd {
+    fn set_pwd(&mut self, pwd: &Path) {
+        self.locator.set_pwd(pwd);
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Network(#[from] network::Error),
+    #[error(transparent)]
+    Envelope(#[from] super::xdr::Error),
+    #[error(transparent)]
+    Rpc(#[from] rpc::Error),
+    #[error(transparent)]
+    StrVal(#[from] soroban_spec_tools::Error),
+    #[error(transparent)]
+    TxnResult(#[from] txn_result::Error),
+}
+
+impl Cmd {
+    pub async fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        let envelope = self.xdr.envelope()?;
+        let network = self.network.get(&self.locator)?;
+        let client = Client::new(&network.rpc_url)?.with_wait(self.wait.wait()?);
+        client
+            .verify_network_passphrase(Some(&network.network_passphrase))
+            .await?;
+
+        if self.no_wait {
+            let hash = client.submit_transaction(&envelope).await?;
+            if global_args.is_json() {
+                println!("{:#}", json!({ "tx_hash": hash }));
+            } else {
+                println!("{hash}");
+            }
+            return Ok(());
+        }
+        let sent = client.send_transaction(&envelope).await?;
+
+        tracing::debug!(result = ?sent.result);
+        tracing::debug!(meta = ?sent.meta);
+        if !sent.events.is_empty() {
+            tracing::debug!(events = ?sent.events);
+        }
+
+        let return_value = sent
+            .return_value()
+            .filter(|return_value| **return_value != ScVal::Void)
+            .cloned();
+        if global_args.is_json() {
+            let res = TxnResult::Sent(return_value, Box::new(sent));
+            println!(
+                "{:#}",
+                res.to_json(|return_value| json!({ "return_value": return_value }))?
+            );
+        } else if let Some(return_value) = return_value {
+            // Without the contract spec the return value can only be printed in its generic form
+            println!("{}", soroban_spec_tools::to_string(&return_value)?);
+        }
+        Ok(())
+    }
+}
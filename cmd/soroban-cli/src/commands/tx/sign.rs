@@ -0,0 +1,125 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use clap::{command, Parser};
+use serde_json::json;
+use soroban_env_host::xdr::{
+    Error as XdrError, TransactionEnvelope, TransactionV1Envelope, WriteXdr,
+};
+
+use crate::{
+    commands::{config, global},
+    signer, Pwd,
+};
+
+#[derive(Parser, Debug, Clone)]
+#[group(skip)]
+pub struct Cmd {
+    #[command(flatten)]
+    pub xdr: super::xdr::Args,
+    #[command(flatten)]
+    pub config: config::Args,
+}
+
+impl FromStr for Cmd {
+    type Err = clap::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use clap::{CommandFactory, FromArgMatches};
+        Self::from_arg_matches_mut(&mut Self::command().get_matches_from(s.split_whitespace()))
+    }
+}
+
+impl Pwd for Cmd {
+    fn set_pwd(&mut self, pwd: &Path) {
+        self.config.set_pwd(pwd);
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Config(#[from] config::Error),
+    #[error(transparent)]
+    Envelope(#[from] super::xdr::Error),
+    #[error("xdr processing error: {0}")]
+    Xdr(#[from] XdrError),
+    #[error(transparent)]
+    Signer(#[from] signer::Error),
+    #[error("only v1 transaction envelopes can be signed")]
+    UnsupportedEnvelopeType,
+}
+
+impl Cmd {
+    pub fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        let envelope = self.sign(self.xdr.envelope()?)?.to_xdr_base64()?;
+        if global_args.is_json() {
+            println!("{:#}", json!({ "transaction": envelope }));
+        } else {
+            println!("{envelope}");
+        }
+        Ok(())
+    }
+
+    pub fn sign(&self, envelope: TransactionEnvelope) -> Result<TransactionEnvelope, Error> {
+        let TransactionEnvelope::Tx(TransactionV1Envelope { tx, signatures }) = envelope else {
+            return Err(Error::UnsupportedEnvelopeType);
+        };
+        let key = self.config.signer()?;
+        let network = self.config.get_network()?;
+
+        let mut signatures = signatures.to_vec();
+        signatures.push(signer::decorated_signature(
+            &key,
+            &tx,
+            &network.network_passphrase,
+        )?);
+
+        Ok(TransactionEnvelope::Tx(TransactionV1Envelope {
+            tx,
+            signatures: signatures.try_into()?,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use soroban_env_host::xdr::VecM;
+
+    use crate::{
+        commands::contract::install::build_install_contract_code_tx, utils, CommandParser,
+    };
+
+    const SECRET_KEY: &str = "SBFGFF27Y64ZUGFAIG5AMJGQODZZKV2YQKAVUUN4HNE24XZXD2OEUVUP";
+
+    #[test]
+    fn test_sign_appends_signature() {
+        let (tx, _) = build_install_contract_code_tx(
+            b"foo".to_vec(),
+            300,
+            1,
+            &utils::parse_secret_key(SECRET_KEY).unwrap().public,
+        )
+        .unwrap();
+        let cmd = Cmd::parse_arg_vec(&[
+            "--rpc-url=http://localhost:8000",
+            "--network-passphrase=Test SDF Network ; September 2015",
+            "--source",
+            SECRET_KEY,
+        ])
+        .unwrap();
+
+        let envelope = TransactionEnvelope::Tx(TransactionV1Envelope {
+            tx,
+            signatures: VecM::default(),
+        });
+        let signed = cmd.sign(cmd.sign(envelope).unwrap()).unwrap();
+
+        let TransactionEnvelope::Tx(TransactionV1Envelope { signatures, .. }) = signed else {
+            panic!("expected a v1 transaction envelope");
+        };
+        assert_eq!(signatures.len(), 2);
+    }
+}
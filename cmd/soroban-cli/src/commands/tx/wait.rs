@@ -0,0 +1,128 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use clap::{command, Parser};
+use serde_json::json;
+use soroban_env_host::xdr::{ScVal, SorobanTransactionMeta, TransactionMeta, TransactionMetaV3};
+
+use crate::{
+    commands::{
+        config::{locator, network},
+        global,
+    },
+    rpc::{self, Client},
+    wait, Pwd,
+};
+
+#[derive(Parser, Debug, Clone)]
+#[group(skip)]
+pub struct Cmd {
+    /// Hash of the transaction, as printed by `--no-wait`
+    pub hash: String,
+    #[command(flatten)]
+    pub wait: wait::Args,
+    #[command(flatten)]
+    pub network: network::Args,
+    #[command(flatten)]
+    pub locator: locator::Args,
+}
+
+impl FromStr for Cmd {
+    type Err = clap::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use clap::{CommandFactory, FromArgMatches};
+        Self::from_arg_matches_mut(&mut Self::command().get_matches_from(s.split_whitespace()))
+    }
+}
+
+impl Pwd for Cmd {
+    fn set_pwd(&mut self, pwd: &Path) {
+        self.locator.set_pwd(pwd);
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Network(#[from] network::Error),
+    #[error(transparent)]
+    Rpc(#[from] rpc::Error),
+    #[error(transparent)]
+    StrVal(#[from] soroban_spec_tools::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+impl Cmd {
+    pub async fn run(&self, global_args: &global::Args) -> Result<(), Error> {
+        let network = self.network.get(&self.locator)?;
+        let client = Client::new(&network.rpc_url)?.with_wait(self.wait.wait()?);
+        client
+            .verify_network_passphrase(Some(&network.network_passphrase))
+            .await?;
+
+        let response = client.poll_transaction(&self.hash).await?;
+        if response.status == "FAILED" {
+            return Err(response.failure(&self.hash)?.into());
+        }
+        let result = response.result()?;
+        let meta = response.meta()?;
+        let events = response.events()?;
+
+        if global_args.is_json() {
+            let return_value = match &meta {
+                Some(TransactionMeta::V3(TransactionMetaV3 {
+                    soroban_meta: Some(SorobanTransactionMeta { return_value, .. }),
+                    ..
+                })) if *return_value != ScVal::Void => Some(return_value),
+                _ => None,
+            };
+            let json = json!({
+                "tx_hash": self.hash,
+                "status": response.status,
+                "ledger": response.ledger,
+                "created_at": response.created_at,
+                "fee_charged": result.as_ref().map(|result| result.fee_charged),
+                "result": serde_json::to_value(&result)?,
+                "meta": serde_json::to_value(&meta)?,
+                "events": serde_json::to_value(&events)?,
+                "return_value": serde_json::to_value(return_value)?,
+            });
+            println!("{json:#}");
+            return Ok(());
+        }
+
+        println!("Status: {}", response.status);
+        if let Some(ledger) = response.ledger {
+            println!("Ledger: {ledger}");
+        }
+        if let Some(created_at) = response.created_at {
+            println!("Created at: {created_at}");
+        }
+        if let Some(result) = &result {
+            println!("Result: {result:#?}");
+        }
+        if let Some(meta) = &meta {
+            println!("Meta: {meta:#?}");
+        }
+        for event in &events {
+            println!("Event: {event:#?}");
+        }
+
+        // Without the contract spec the return value can only be printed in its generic form
+        if let Some(TransactionMeta::V3(TransactionMetaV3 {
+            soroban_meta: Some(SorobanTransactionMeta { return_value, .. }),
+            ..
+        })) = meta
+        {
+            if return_value != ScVal::Void {
+                println!(
+                    "Return value: {}",
+                    soroban_spec_tools::to_string(&return_value)?
+                );
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,31 @@
+use std::io::{self, Read};
+
+use soroban_env_host::xdr::{Error as XdrError, ReadXdr, TransactionEnvelope};
+
+#[derive(Debug, clap::Args, Clone)]
+#[group(skip)]
+pub struct Args {
+    /// Base64-encoded transaction envelope XDR. Read from stdin if omitted
+    pub xdr: Option<String>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("reading transaction envelope from stdin: {0}")]
+    Stdin(#[from] io::Error),
+    #[error("parsing transaction envelope: {0}")]
+    Xdr(#[from] XdrError),
+}
+
+impl Args {
+    pub fn envelope(&self) -> Result<TransactionEnvelope, Error> {
+        let xdr = if let Some(xdr) = &self.xdr {
+            xdr.clone()
+        } else {
+            let mut buf = String::new();
+            io::stdin().read_to_string(&mut buf)?;
+            buf
+        };
+        Ok(TransactionEnvelope::from_xdr_base64(xdr.trim())?)
+    }
+}
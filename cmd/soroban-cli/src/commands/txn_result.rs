@@ -0,0 +1,160 @@
+use std::fmt::{Display, Formatter};
+
+use serde_json::{json, Value};
+use soroban_env_host::{
+    events::HostEvent,
+    xdr::{
+        DiagnosticEvent, Error as XdrError, LedgerFootprint, Transaction, TransactionEnvelope,
+        TransactionV1Envelope, VecM, WriteXdr,
+    },
+};
+
+use crate::rpc::SentTransaction;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Xdr(#[from] XdrError),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+/// Result of an RPC command which may stop after building the transaction (`--build-only`)
+/// or after submitting it (`--no-wait`) instead of returning the value produced by the network.
+pub enum TxnResult<T> {
+    Txn(Transaction),
+    /// Hash of a submitted transaction whose result wasn't waited for
+    Hash(String),
+    Res(T),
+    /// Value produced by a transaction the network applied, along with the transaction
+    Sent(T, Box<SentTransaction>),
+    /// Value produced by a command applied to the sandbox ledger, along with its footprint and
+    /// events
+    Sandbox(T, Box<Applied>),
+}
+
+/// Ledger entries a command read and wrote in the sandbox, and the events it emitted, the
+/// sandbox counterparts of the ones of a sent transaction
+#[derive(Debug, Default)]
+pub struct Applied {
+    pub footprint: LedgerFootprint,
+    pub events: Vec<DiagnosticEvent>,
+}
+
+impl Applied {
+    pub fn new(footprint: LedgerFootprint, events: &[HostEvent]) -> Self {
+        let events = events
+            .iter()
+            .map(|e| DiagnosticEvent {
+                in_successful_contract_call: !e.failed_call,
+                event: e.event.clone(),
+            })
+            .collect();
+        Self { footprint, events }
+    }
+}
+
+impl<T> TxnResult<T> {
+    pub fn into_result(self) -> Option<T> {
+        match self {
+            TxnResult::Res(res) | TxnResult::Sent(res, _) | TxnResult::Sandbox(res, _) => Some(res),
+            TxnResult::Txn(_) | TxnResult::Hash(_) => None,
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> TxnResult<U> {
+        match self {
+            TxnResult::Res(res) => TxnResult::Res(f(res)),
+            TxnResult::Sent(res, sent) => TxnResult::Sent(f(res), sent),
+            TxnResult::Sandbox(res, applied) => TxnResult::Sandbox(f(res), applied),
+            TxnResult::Txn(tx) => TxnResult::Txn(tx),
+            TxnResult::Hash(hash) => TxnResult::Hash(hash),
+        }
+    }
+
+    /// Single JSON object describing the result, for `--output json`. `fields` describes the
+    /// value produced; the hash, fee charged, footprint and events of the transaction are added
+    /// to it when the transaction was sent, and the footprint and events when the command ran in
+    /// the sandbox.
+    pub fn to_json(&self, fields: impl FnOnce(&T) -> Value) -> Result<Value, Error> {
+        Ok(match self {
+            TxnResult::Txn(tx) => json!({ "transaction": envelope(tx).to_xdr_base64()? }),
+            TxnResult::Hash(hash) => json!({ "tx_hash": hash }),
+            TxnResult::Res(res) => fields(res),
+            TxnResult::Sent(res, sent) => {
+                let mut value = fields(res);
+                if let Value::Object(object) = &mut value {
+                    object.insert("tx_hash".to_string(), json!(sent.hash));
+                    object.insert("fee_charged".to_string(), json!(sent.result.fee_charged));
+                    if let Some(footprint) = sent.footprint() {
+                        object.insert("footprint".to_string(), serde_json::to_value(footprint)?);
+                    }
+                    object.insert("events".to_string(), serde_json::to_value(&sent.events)?);
+                }
+                value
+            }
+            TxnResult::Sandbox(res, applied) => {
+                let mut value = fields(res);
+                if let Value::Object(object) = &mut value {
+                    object.insert(
+                        "footprint".to_string(),
+                        serde_json::to_value(&applied.footprint)?,
+                    );
+                    object.insert("events".to_string(), serde_json::to_value(&applied.events)?);
+                }
+                value
+            }
+        })
+    }
+}
+
+fn envelope(tx: &Transaction) -> TransactionEnvelope {
+    TransactionEnvelope::Tx(TransactionV1Envelope {
+        tx: tx.clone(),
+        signatures: VecM::default(),
+    })
+}
+
+impl<T: Display> Display for TxnResult<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TxnResult::Txn(tx) => write!(
+                f,
+                "{}",
+                envelope(tx).to_xdr_base64().map_err(|_| std::fmt::Error)?
+            ),
+            TxnResult::Hash(hash) => write!(f, "{hash}"),
+            TxnResult::Res(res) | TxnResult::Sent(res, _) | TxnResult::Sandbox(res, _) => {
+                write!(f, "{res}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_json() {
+        let fields = |res: &u32| json!({ "expiration_ledger": res });
+        assert_eq!(
+            TxnResult::Res(7).to_json(fields).unwrap(),
+            json!({ "expiration_ledger": 7 })
+        );
+        assert_eq!(
+            TxnResult::Hash("abcd".to_string()).to_json(fields).unwrap(),
+            json!({ "tx_hash": "abcd" })
+        );
+        assert_eq!(
+            TxnResult::Sandbox(7, Box::default())
+                .to_json(fields)
+                .unwrap(),
+            json!({
+                "expiration_ledger": 7,
+                "footprint": { "read_only": [], "read_write": [] },
+                "events": [],
+            })
+        );
+    }
+}
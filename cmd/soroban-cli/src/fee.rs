@@ -1,6 +1,12 @@
-use clap::arg;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use clap::{arg, command};
 
 use crate::commands::HEADING_RPC;
+use crate::rpc::{self, Client};
 
 #[derive(Debug, clap::Args, Clone)]
 #[group(skip)]
@@ -8,10 +14,87 @@ pub struct Args {
     /// fee amount for transaction, in stroops. 1 stroop = 0.0000001 xlm
     #[arg(long, default_value = "100", env = "SOROBAN_FEE", help_heading = HEADING_RPC)]
     pub fee: u32,
+    /// Build the transaction, simulate it and print the assembled, unsigned transaction envelope as base64 XDR instead of signing and submitting it
+    #[arg(long, help_heading = HEADING_RPC)]
+    pub build_only: bool,
+    /// Print the transaction hash right after submitting the transaction instead of waiting for its result. Use `soroban tx wait` to get the result later
+    #[arg(long, conflicts_with = "build_only", help_heading = HEADING_RPC)]
+    pub no_wait: bool,
+    #[command(flatten)]
+    pub wait: crate::wait::Args,
+    /// Sequence numbers shared by the commands of a batch, so that the source account isn't
+    /// fetched before every transaction
+    #[arg(skip)]
+    pub sequence: Option<Sequence>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("`--build-only` requires a network to build the transaction for, pass `--network` or `--rpc-url` and `--network-passphrase`")]
+    BuildOnlyRequiresNetwork,
 }
 
 impl Default for Args {
     fn default() -> Self {
-        Self { fee: 100 }
+        Self {
+            fee: 100,
+            build_only: false,
+            no_wait: false,
+            wait: crate::wait::Args::default(),
+            sequence: None,
+        }
+    }
+}
+
+impl Args {
+    /// Error if `--build-only` is set for a command run in the sandbox, which applies changes
+    /// directly rather than through a transaction
+    pub fn check_sandbox(&self) -> Result<(), Error> {
+        if self.build_only {
+            Err(Error::BuildOnlyRequiresNetwork)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sequence number of the next transaction of `account` on the network with the given
+    /// passphrase
+    pub async fn next_sequence(
+        &self,
+        client: &Client,
+        network_passphrase: &str,
+        account: &str,
+    ) -> Result<i64, rpc::Error> {
+        if let Some(sequence) = &self.sequence {
+            sequence.next(client, network_passphrase, account).await
+        } else {
+            let sequence: i64 = client.get_account(account).await?.seq_num.into();
+            Ok(sequence + 1)
+        }
+    }
+}
+
+/// Sequence numbers of source accounts by network passphrase and account, since the steps of a
+/// batch can send transactions to different networks. They're fetched from the network the first
+/// time they're needed and then incremented locally for every transaction
+#[derive(Debug, Clone, Default)]
+pub struct Sequence(Arc<Mutex<HashMap<(String, String), i64>>>);
+
+impl Sequence {
+    async fn next(
+        &self,
+        client: &Client,
+        network_passphrase: &str,
+        account: &str,
+    ) -> Result<i64, rpc::Error> {
+        let key = (network_passphrase.to_string(), account.to_string());
+        let cached = self.0.lock().unwrap().get(&key).copied();
+        let current = if let Some(sequence) = cached {
+            sequence
+        } else {
+            client.get_account(account).await?.seq_num.into()
+        };
+        self.0.lock().unwrap().insert(key, current + 1);
+        Ok(current + 1)
     }
 }
@@ -8,8 +8,10 @@ pub mod fee;
 pub mod log;
 pub mod network;
 pub mod rpc;
+pub mod signer;
 pub mod toid;
 pub mod utils;
+pub mod wait;
 pub mod wasm;
 
 use std::path::Path;
@@ -0,0 +1,203 @@
+use std::fmt::{Display, Formatter};
+
+use soroban_env_host::{
+    events::HostEvent,
+    xdr::{
+        DiagnosticEvent, InnerTransactionResultResult, OperationResult, OperationResultTr,
+        TransactionMeta, TransactionMetaV3, TransactionResult, TransactionResultResult,
+    },
+};
+use soroban_spec_tools::Spec;
+
+use crate::utils::host_error::Report;
+
+/// Transaction rejected by the network or failed when applied, decoded so it can be shown to the
+/// user instead of its raw XDR
+#[derive(Debug, Clone)]
+pub struct TransactionFailure {
+    pub hash: String,
+    pub result: TransactionResult,
+    /// Diagnostic events emitted while applying the transaction, empty if it was rejected before
+    /// being applied or the server doesn't have diagnostic events enabled
+    pub events: Vec<DiagnosticEvent>,
+    /// Error the contract invocation failed with, as recorded in the diagnostic events
+    pub report: Option<Report>,
+}
+
+impl TransactionFailure {
+    pub fn new(hash: &str, result: TransactionResult, meta: Option<&TransactionMeta>) -> Self {
+        let events = match meta {
+            Some(TransactionMeta::V3(TransactionMetaV3 {
+                soroban_meta: Some(meta),
+                ..
+            })) => meta.diagnostic_events.to_vec(),
+            _ => Vec::new(),
+        };
+        Self::with_events(hash, result, events)
+    }
+
+    pub fn with_events(
+        hash: &str,
+        result: TransactionResult,
+        events: Vec<DiagnosticEvent>,
+    ) -> Self {
+        let report = Report::from_events(&host_events(&events));
+        Self {
+            hash: hash.to_string(),
+            result,
+            events,
+            report,
+        }
+    }
+
+    /// Decode the contract error and the arguments of the calls to `contract_id` with its spec
+    pub fn decode(&mut self, contract_id: &[u8; 32], spec: &Spec) {
+        if let Some(report) = &mut self.report {
+            report.decode(contract_id, spec);
+        }
+    }
+
+    fn operation_results(&self) -> &[OperationResult] {
+        match &self.result.result {
+            TransactionResultResult::TxSuccess(results)
+            | TransactionResultResult::TxFailed(results) => results.as_slice(),
+            TransactionResultResult::TxFeeBumpInnerSuccess(inner)
+            | TransactionResultResult::TxFeeBumpInnerFailed(inner) => match &inner.result.result {
+                InnerTransactionResultResult::TxSuccess(results)
+                | InnerTransactionResultResult::TxFailed(results) => results.as_slice(),
+                _ => &[],
+            },
+            _ => &[],
+        }
+    }
+}
+
+fn host_events(events: &[DiagnosticEvent]) -> Vec<HostEvent> {
+    events
+        .iter()
+        .map(|e| HostEvent {
+            event: e.event.clone(),
+            failed_call: !e.in_successful_contract_call,
+        })
+        .collect()
+}
+
+fn operation_result_name(result: &OperationResult) -> String {
+    match result {
+        OperationResult::OpInner(tr) => {
+            let code = match tr {
+                OperationResultTr::InvokeHostFunction(r) => r.name(),
+                OperationResultTr::BumpFootprintExpiration(r) => r.name(),
+                OperationResultTr::RestoreFootprint(r) => r.name(),
+                _ => return tr.name().to_string(),
+            };
+            format!("{} {code}", tr.name())
+        }
+        _ => result.name().to_string(),
+    }
+}
+
+impl Display for TransactionFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "transaction {} failed with {}",
+            self.hash,
+            self.result.result.name()
+        )?;
+        for (i, result) in self.operation_results().iter().enumerate() {
+            write!(f, "\noperation {i}: {}", operation_result_name(result))?;
+        }
+        if let Some(report) = &self.report {
+            write!(f, "\n{report}")?;
+        }
+        if !self.events.is_empty() {
+            write!(f, "\ndiagnostic events:")?;
+            for event in host_events(&self.events) {
+                write!(f, "\n  {event}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use soroban_env_host::xdr::{
+        ContractEvent, ContractEventBody, ContractEventType, ContractEventV0, ExtensionPoint, Hash,
+        InvokeHostFunctionResult, ScError, ScErrorCode, ScErrorType, ScSymbol, ScVal, ScVec,
+        TransactionResultExt,
+    };
+
+    use super::*;
+
+    fn failure(events: Vec<DiagnosticEvent>) -> TransactionFailure {
+        let result = TransactionResult {
+            fee_charged: 100,
+            result: TransactionResultResult::TxFailed(
+                vec![OperationResult::OpInner(
+                    OperationResultTr::InvokeHostFunction(InvokeHostFunctionResult::Trapped),
+                )]
+                .try_into()
+                .unwrap(),
+            ),
+            ext: TransactionResultExt::V0,
+        };
+        TransactionFailure::with_events("abcd", result, events)
+    }
+
+    fn error_event(in_successful_contract_call: bool, code: ScErrorCode) -> DiagnosticEvent {
+        DiagnosticEvent {
+            in_successful_contract_call,
+            event: ContractEvent {
+                ext: ExtensionPoint::V0,
+                contract_id: Some(Hash([0; 32])),
+                type_: ContractEventType::Diagnostic,
+                body: ContractEventBody::V0(ContractEventV0 {
+                    topics: ScVec(
+                        vec![
+                            ScVal::Symbol(ScSymbol("error".try_into().unwrap())),
+                            ScVal::Error(ScError {
+                                type_: ScErrorType::Contract,
+                                code,
+                            }),
+                        ]
+                        .try_into()
+                        .unwrap(),
+                    ),
+                    data: ScVal::Void,
+                }),
+            },
+        }
+    }
+
+    #[test]
+    fn test_contract_error_from_events() {
+        assert!(failure(vec![]).report.is_none());
+        let events = vec![
+            error_event(true, ScErrorCode::InvalidInput),
+            error_event(false, ScErrorCode::MissingValue),
+        ];
+        let report = failure(events).report.unwrap();
+        assert_eq!(report.type_, ScErrorType::Contract);
+        assert_eq!(
+            report.code,
+            u32::try_from(i32::from(ScErrorCode::MissingValue)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let mut failure = failure(vec![]);
+        assert_eq!(
+            failure.to_string(),
+            "transaction abcd failed with TxFailed\noperation 0: InvokeHostFunction Trapped"
+        );
+        let mut report = Report::new(ScErrorType::Contract, 3);
+        report.contract_error = Some(("NotAllowed".to_string(), "Caller isn't admin".to_string()));
+        failure.report = Some(report);
+        assert!(failure
+            .to_string()
+            .ends_with("\ncontract error #3: NotAllowed: Caller isn't admin"));
+    }
+}
@@ -3,19 +3,25 @@ use itertools::Itertools;
 use jsonrpsee_core::params::ObjectParams;
 use jsonrpsee_core::{self, client::ClientT, rpc_params};
 use jsonrpsee_http_client::{HeaderMap, HttpClient, HttpClientBuilder};
-use serde_aux::prelude::{deserialize_default_from_null, deserialize_number_from_string};
+use serde_aux::prelude::{
+    deserialize_default_from_null, deserialize_number_from_string,
+    deserialize_option_number_from_string,
+};
 use soroban_env_host::{
     budget::Budget,
     events::HostEvent,
     xdr::{
         self, AccountEntry, AccountId, ContractDataEntry, DiagnosticEvent, Error as XdrError,
-        LedgerEntryData, LedgerFootprint, LedgerKey, LedgerKeyAccount, PublicKey, ReadXdr,
-        SorobanAuthorizationEntry, Transaction, TransactionEnvelope, TransactionMeta,
-        TransactionMetaV3, TransactionResult, TransactionV1Envelope, Uint256, VecM, WriteXdr,
+        LedgerEntryData, LedgerFootprint, LedgerKey, LedgerKeyAccount, PublicKey, ReadXdr, ScVal,
+        SorobanAuthorizationEntry, SorobanTransactionMeta, Transaction, TransactionEnvelope,
+        TransactionExt, TransactionMeta, TransactionMetaV3, TransactionResult,
+        TransactionV1Envelope, Uint256, VecM, WriteXdr,
     },
 };
 use soroban_sdk::token;
+use soroban_spec_tools::Spec;
 use std::{
+    collections::BTreeMap,
     fmt::Display,
     str::FromStr,
     time::{Duration, Instant},
@@ -24,13 +30,21 @@ use termcolor::{Color, ColorChoice, StandardStream, WriteColor};
 use termcolor_output::colored;
 use tokio::time::sleep;
 
-use crate::utils::{self, contract_spec};
+use crate::{
+    signer::{self, Signer},
+    utils::{contract_spec, host_error},
+};
 
+mod failure;
 mod transaction;
-use transaction::assemble;
+pub use failure::TransactionFailure;
+use transaction::{assemble, has_address_auth, sign_soroban_authorizations};
 
 const VERSION: Option<&str> = option_env!("CARGO_PKG_VERSION");
 
+// Number of ledgers, from the latest one, for which signed auth entries stay valid
+const AUTH_SIGNATURE_LEDGERS: u32 = 60;
+
 pub type LogEvents = fn(
     footprint: &LedgerFootprint,
     auth: &[VecM<SorobanAuthorizationEntry>],
@@ -44,6 +58,8 @@ pub enum Error {
     InvalidAddress(#[from] stellar_strkey::DecodeError),
     #[error("invalid response from server")]
     InvalidResponse,
+    #[error("poll interval of {interval}s is longer than the maximum poll interval of {max}s")]
+    PollIntervalAboveMax { interval: u64, max: u64 },
     #[error("provided network passphrase {expected:?} does not match the server: {server:?}")]
     InvalidNetworkPassphrase { expected: String, server: String },
     #[error("xdr processing error: {0}")]
@@ -56,16 +72,20 @@ pub enum Error {
     JsonRpc(#[from] jsonrpsee_core::Error),
     #[error("json decoding error: {0}")]
     Serde(#[from] serde_json::Error),
-    #[error("transaction failed: {0}")]
-    TransactionFailed(String),
+    #[error("{0}")]
+    TransactionFailed(Box<TransactionFailure>),
     #[error("transaction submission failed: {0}")]
     TransactionSubmissionFailed(String),
     #[error("expected transaction status: {0}")]
     UnexpectedTransactionStatus(String),
-    #[error("transaction submission timeout")]
-    TransactionSubmissionTimeout,
+    #[error(
+        "transaction {hash} wasn't included in a ledger within {timeout:?}, check on it later with `soroban tx wait {hash}`"
+    )]
+    TransactionSubmissionTimeout { hash: String, timeout: Duration },
     #[error("transaction simulation failed: {0}")]
     TransactionSimulationFailed(String),
+    #[error("transaction simulation failed: {0}")]
+    TransactionSimulationHostError(Box<host_error::Report>),
     #[error("{0} not found: {1}")]
     NotFound(String, String),
     #[error("Missing result in successful response")]
@@ -90,6 +110,14 @@ pub enum Error {
     Spec(#[from] soroban_spec::read::FromWasmError),
     #[error(transparent)]
     SpecBase64(#[from] soroban_spec::read::ParseSpecBase64Error),
+    #[error(transparent)]
+    SpecTools(#[from] soroban_spec_tools::Error),
+    #[error(
+        "missing signing key for account {address}, add it with `soroban config identity add`"
+    )]
+    MissingSignerForAddress { address: String },
+    #[error(transparent)]
+    Signer(#[from] signer::Error),
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
@@ -131,7 +159,114 @@ pub struct GetTransactionResponse {
         default
     )]
     pub result_meta_xdr: Option<String>,
-    // TODO: add ledger info and application order
+    #[serde(
+        rename = "latestLedger",
+        deserialize_with = "deserialize_number_from_string",
+        default
+    )]
+    pub latest_ledger: u32,
+    /// Close time of the latest ledger, in seconds since the epoch
+    #[serde(
+        rename = "latestLedgerCloseTime",
+        deserialize_with = "deserialize_number_from_string",
+        default
+    )]
+    pub latest_ledger_close_time: u64,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_option_number_from_string",
+        default
+    )]
+    pub ledger: Option<u32>,
+    /// Close time of the ledger the transaction was included in, in seconds since the epoch
+    #[serde(
+        rename = "createdAt",
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_option_number_from_string",
+        default
+    )]
+    pub created_at: Option<i64>,
+    #[serde(
+        rename = "applicationOrder",
+        skip_serializing_if = "Option::is_none",
+        default
+    )]
+    pub application_order: Option<u32>,
+    #[serde(rename = "feeBump", default)]
+    pub fee_bump: bool,
+}
+
+impl GetTransactionResponse {
+    pub fn result(&self) -> Result<Option<TransactionResult>, Error> {
+        Ok(self
+            .result_xdr
+            .as_ref()
+            .map(TransactionResult::from_xdr_base64)
+            .transpose()?)
+    }
+
+    pub fn meta(&self) -> Result<Option<TransactionMeta>, Error> {
+        Ok(self
+            .result_meta_xdr
+            .as_ref()
+            .map(TransactionMeta::from_xdr_base64)
+            .transpose()?)
+    }
+
+    /// Error describing why the transaction failed, for a response with the `FAILED` status
+    pub fn failure(&self, hash: &str) -> Result<Error, Error> {
+        let result = self.result()?.ok_or(Error::MissingResult)?;
+        Ok(Error::TransactionFailed(Box::new(TransactionFailure::new(
+            hash,
+            result,
+            self.meta()?.as_ref(),
+        ))))
+    }
+
+    pub fn events(&self) -> Result<Vec<DiagnosticEvent>, Error> {
+        Ok(self
+            .meta()?
+            .as_ref()
+            .map(extract_events)
+            .unwrap_or_default())
+    }
+}
+
+/// Transaction applied by the network, along with what applying it produced
+#[derive(Debug, Clone)]
+pub struct SentTransaction {
+    pub hash: String,
+    pub envelope: TransactionEnvelope,
+    pub result: TransactionResult,
+    pub meta: TransactionMeta,
+    pub events: Vec<DiagnosticEvent>,
+}
+
+impl SentTransaction {
+    pub fn footprint(&self) -> Option<&LedgerFootprint> {
+        match &self.envelope {
+            TransactionEnvelope::Tx(TransactionV1Envelope {
+                tx:
+                    Transaction {
+                        ext: TransactionExt::V1(data),
+                        ..
+                    },
+                ..
+            }) => Some(&data.resources.footprint),
+            _ => None,
+        }
+    }
+
+    /// Value returned by the contract function the transaction invoked, if any
+    pub fn return_value(&self) -> Option<&ScVal> {
+        match &self.meta {
+            TransactionMeta::V3(TransactionMetaV3 {
+                soroban_meta: Some(SorobanTransactionMeta { return_value, .. }),
+                ..
+            }) => Some(return_value),
+            _ => None,
+        }
+    }
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
@@ -142,6 +277,10 @@ pub struct LedgerEntryResult {
     pub last_modified_ledger: String,
 }
 
+/// Number of keys the client asks for in each `getLedgerEntries` request when looking up many
+/// entries. The server has no limit of its own, the batches just keep each response small.
+pub const LEDGER_KEYS_PER_REQUEST: usize = 200;
+
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 pub struct GetLedgerEntriesResponse {
     pub entries: Option<Vec<LedgerEntryResult>>,
@@ -165,6 +304,17 @@ pub struct GetNetworkResponse {
     pub protocol_version: u32,
 }
 
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct GetLatestLedgerResponse {
+    pub id: String,
+    #[serde(
+        rename = "protocolVersion",
+        deserialize_with = "deserialize_number_from_string"
+    )]
+    pub protocol_version: u32,
+    pub sequence: u32,
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 pub struct Cost {
     #[serde(
@@ -209,6 +359,21 @@ pub struct SimulateTransactionResponse {
     pub latest_ledger: u32,
 }
 
+impl SimulateTransactionResponse {
+    pub fn events(&self) -> Result<Vec<HostEvent>, Error> {
+        Ok(self
+            .events
+            .iter()
+            .map(|e| {
+                DiagnosticEvent::from_xdr_base64(e).map(|e| HostEvent {
+                    event: e.event,
+                    failed_call: !e.in_successful_contract_call,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 pub struct GetEventsResponse {
     #[serde(deserialize_with = "deserialize_default_from_null")]
@@ -262,27 +427,14 @@ pub struct Event {
     pub value: EventValue,
 }
 
+/// Same as [`Event::to_string_with_spec`] without a spec, so the topics and value are printed as
+/// JSON decoded from their XDR rather than as debug-formatted `ScVal`s
 impl Display for Event {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(
-            f,
-            "Event {} [{}]:",
-            self.paging_token,
-            self.event_type.to_ascii_uppercase()
-        )?;
-        writeln!(
-            f,
-            "  Ledger:   {} (closed at {})",
-            self.ledger, self.ledger_closed_at
-        )?;
-        writeln!(f, "  Contract: {}", self.contract_id)?;
-        writeln!(f, "  Topics:")?;
-        for topic in &self.topic {
-            let scval = xdr::ScVal::from_xdr_base64(topic).map_err(|_| std::fmt::Error)?;
-            writeln!(f, "            {scval:?}")?;
-        }
-        let scval = xdr::ScVal::from_xdr_base64(&self.value.xdr).map_err(|_| std::fmt::Error)?;
-        writeln!(f, "  Value:    {scval:?}")
+        let event = self
+            .to_string_with_spec(&Spec::default())
+            .map_err(|_| std::fmt::Error)?;
+        write!(f, "{event}")
     }
 }
 
@@ -291,10 +443,52 @@ impl Event {
         parse_cursor(&self.id)
     }
 
-    pub fn pretty_print(&self) -> Result<(), Box<dyn std::error::Error>> {
+    /// Topics of the event as JSON, decoded with `spec`, the spec of the contract that emitted it
+    pub fn topics_to_json(&self, spec: &Spec) -> Result<Vec<serde_json::Value>, Error> {
+        self.topic
+            .iter()
+            .map(|topic| Ok(spec.xdr_to_json_inferred(&ScVal::from_xdr_base64(topic)?)?))
+            .collect()
+    }
+
+    /// Value of the event as JSON, decoded with `spec`, the spec of the contract that emitted it
+    pub fn value_to_json(&self, spec: &Spec) -> Result<serde_json::Value, Error> {
+        Ok(spec.xdr_to_json_inferred(&ScVal::from_xdr_base64(&self.value.xdr)?)?)
+    }
+
+    /// Topics of the event decoded with `spec`, with symbols shown as text and other values as
+    /// JSON
+    pub fn topics_to_strings(&self, spec: &Spec) -> Result<Vec<String>, Error> {
+        self.topic
+            .iter()
+            .map(|topic| Ok(spec.to_string_inferred(&ScVal::from_xdr_base64(topic)?)?))
+            .collect()
+    }
+
+    /// Human-oriented description of the event, with its topics and value decoded with `spec`
+    pub fn to_string_with_spec(&self, spec: &Spec) -> Result<String, Error> {
+        let mut s = format!(
+            "Event {} [{}]:\n",
+            self.paging_token,
+            self.event_type.to_ascii_uppercase()
+        );
+        s.push_str(&format!(
+            "  Ledger:   {} (closed at {})\n",
+            self.ledger, self.ledger_closed_at
+        ));
+        s.push_str(&format!("  Contract: {}\n", self.contract_id));
+        s.push_str("  Topics:\n");
+        for topic in self.topics_to_strings(spec)? {
+            s.push_str(&format!("            {topic}\n"));
+        }
+        s.push_str(&format!("  Value:    {}\n", self.value_to_json(spec)?));
+        Ok(s)
+    }
+
+    pub fn pretty_print(&self, spec: &Spec) -> Result<(), Box<dyn std::error::Error>> {
         let mut stdout = StandardStream::stdout(ColorChoice::Auto);
         if !stdout.supports_color() {
-            println!("{self}");
+            println!("{}", self.to_string_with_spec(spec)?);
             return Ok(());
         }
 
@@ -336,23 +530,21 @@ impl Event {
         )?;
 
         colored!(stdout, "  Topics:\n")?;
-        for topic in &self.topic {
-            let scval = xdr::ScVal::from_xdr_base64(topic)?;
+        for topic in self.topics_to_strings(spec)? {
             colored!(
                 stdout,
-                "            {}{:?}{}\n",
+                "            {}{}{}\n",
                 fg!(Some(Color::Green)),
-                scval,
+                topic,
                 reset!(),
             )?;
         }
 
-        let scval = xdr::ScVal::from_xdr_base64(&self.value.xdr)?;
         colored!(
             stdout,
-            "  Value: {}{:?}{}\n",
+            "  Value: {}{}{}\n",
             fg!(Some(Color::Green)),
-            scval,
+            self.value_to_json(spec)?,
             reset!(),
         )?;
 
@@ -360,26 +552,120 @@ impl Event {
     }
 }
 
+#[cfg(test)]
+impl Event {
+    /// Contract event with the given base64-encoded XDR topics and value, emitted in ledger 2 as
+    /// event `index` of its operation, for tests
+    pub fn fixture(index: u32, contract_id: &str, topic: Vec<String>, value: String) -> Self {
+        let paging_token = format!("{:019}-{index:010}", 8_589_934_592u64);
+        Event {
+            event_type: "contract".to_string(),
+            ledger: "2".to_string(),
+            ledger_closed_at: "2023-01-01T00:00:00Z".to_string(),
+            id: paging_token.clone(),
+            paging_token,
+            contract_id: contract_id.to_string(),
+            topic,
+            value: EventValue { xdr: value },
+        }
+    }
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
 pub struct EventValue {
     pub xdr: String,
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, clap::ValueEnum)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, clap::ValueEnum)]
 pub enum EventType {
+    #[default]
     All,
     Contract,
     System,
 }
 
+/// A filter object of a `getEvents` request: events match if they are of the type, were emitted by
+/// one of the contracts and match one of the topic filters. No contract IDs or topic filters match
+/// any contract or topic.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EventFilter {
+    pub event_type: EventType,
+    pub contract_ids: Vec<String>,
+    /// Comma-separated base64-encoded XDR segments, or "*" wildcards
+    pub topics: Vec<String>,
+}
+
+impl EventFilter {
+    fn to_json(&self) -> serde_json::Map<String, serde_json::Value> {
+        let mut filter = serde_json::Map::new();
+        match self.event_type {
+            EventType::All => (), // all is the default, so avoid incl. the param
+            EventType::Contract => {
+                filter.insert("type".to_string(), "contract".into());
+            }
+            EventType::System => {
+                filter.insert("type".to_string(), "system".into());
+            }
+        }
+        filter.insert("topics".to_string(), self.topics.as_slice().into());
+        filter.insert(
+            "contractIds".to_string(),
+            self.contract_ids.as_slice().into(),
+        );
+        filter
+    }
+
+    /// Whether the event matches the filter, with the same semantics as the RPC server
+    pub fn matches(&self, event: &Event) -> bool {
+        let type_matches = match self.event_type {
+            EventType::All => true,
+            EventType::Contract => event.event_type == "contract",
+            EventType::System => event.event_type == "system",
+        };
+        type_matches
+            && (self.contract_ids.is_empty() || self.contract_ids.contains(&event.contract_id))
+            && (self.topics.is_empty()
+                || self.topics.iter().any(|filter| {
+                    does_topic_match(
+                        &event.topic,
+                        &filter
+                            .split(',')
+                            .map(std::string::ToString::to_string)
+                            .collect::<Vec<String>>(),
+                    )
+                }))
+    }
+}
+
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub enum EventStart {
     Ledger(u32),
     Cursor(String),
 }
 
+/// How long to wait for a submitted transaction to be included in a ledger, and how often to poll
+/// for its status in the meantime
+#[derive(Debug, Clone, Copy)]
+pub struct Wait {
+    pub timeout: Duration,
+    /// Delay before the second poll, which doubles after every poll up to `max_interval`
+    pub interval: Duration,
+    pub max_interval: Duration,
+}
+
+impl Default for Wait {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            interval: Duration::from_secs(1),
+            max_interval: Duration::from_secs(5),
+        }
+    }
+}
+
 pub struct Client {
     base_url: String,
+    wait: Wait,
 }
 
 impl Client {
@@ -410,9 +696,16 @@ impl Client {
         tracing::trace!(?uri);
         Ok(Self {
             base_url: uri.to_string(),
+            wait: Wait::default(),
         })
     }
 
+    #[must_use]
+    pub fn with_wait(mut self, wait: Wait) -> Self {
+        self.wait = wait;
+        self
+    }
+
     fn client(&self) -> Result<HttpClient, Error> {
         let url = self.base_url.clone();
         let mut headers = HeaderMap::new();
@@ -440,6 +733,14 @@ impl Client {
         Ok(self.client()?.request("getNetwork", rpc_params![]).await?)
     }
 
+    pub async fn get_latest_ledger(&self) -> Result<GetLatestLedgerResponse, Error> {
+        tracing::trace!("Getting latest ledger");
+        Ok(self
+            .client()?
+            .request("getLatestLedger", rpc_params![])
+            .await?)
+    }
+
     pub async fn get_account(&self, address: &str) -> Result<AccountEntry, Error> {
         tracing::trace!("Getting address {}", address);
         let key = LedgerKey::Account(LedgerKeyAccount {
@@ -464,10 +765,8 @@ impl Client {
         }
     }
 
-    pub async fn send_transaction(
-        &self,
-        tx: &TransactionEnvelope,
-    ) -> Result<(TransactionResult, TransactionMeta, Vec<DiagnosticEvent>), Error> {
+    /// Submit a transaction without waiting for it to be included in a ledger, returning its hash
+    pub async fn submit_transaction(&self, tx: &TransactionEnvelope) -> Result<String, Error> {
         let client = self.client()?;
         tracing::trace!(?tx);
         let SendTransactionResponse {
@@ -481,54 +780,77 @@ impl Client {
             .map_err(|err| Error::TransactionSubmissionFailed(format!("{err:#?}")))?;
 
         if status == "ERROR" {
-            let error = error_result_xdr
-                .ok_or(Error::MissingError)
-                .and_then(|x| {
-                    TransactionResult::read_xdr_base64(&mut x.as_bytes())
-                        .map_err(|_| Error::InvalidResponse)
-                })
-                .map(|r| r.result);
-            tracing::error!(?error);
-            return Err(Error::TransactionSubmissionFailed(format!("{:#?}", error?)));
+            let result = error_result_xdr.ok_or(Error::MissingError).and_then(|x| {
+                TransactionResult::read_xdr_base64(&mut x.as_bytes())
+                    .map_err(|_| Error::InvalidResponse)
+            });
+            tracing::error!(?result);
+            return Err(Error::TransactionFailed(Box::new(TransactionFailure::new(
+                &hash, result?, None,
+            ))));
         }
+        Ok(hash)
+    }
+
+    pub async fn send_transaction(
+        &self,
+        tx: &TransactionEnvelope,
+    ) -> Result<SentTransaction, Error> {
+        let hash = self.submit_transaction(tx).await?;
         // even if status == "success" we need to query the transaction status in order to get the result
+        let (result, meta, events) = self.wait_for_transaction(&hash).await?;
+        Ok(SentTransaction {
+            hash,
+            envelope: tx.clone(),
+            result,
+            meta,
+            events,
+        })
+    }
+
+    /// Wait for a submitted transaction to be included in a ledger and return its result, failing
+    /// if it didn't succeed
+    pub async fn wait_for_transaction(
+        &self,
+        hash: &str,
+    ) -> Result<(TransactionResult, TransactionMeta, Vec<DiagnosticEvent>), Error> {
+        let response = self.poll_transaction(hash).await?;
+        if response.status == "FAILED" {
+            tracing::error!(?response);
+            return Err(response.failure(hash)?);
+        }
+        // TODO: the caller should probably be printing this
+        tracing::trace!(?response);
+        let result = response.result()?.ok_or(Error::MissingResult)?;
+        let meta = response.meta()?.ok_or(Error::MissingResult)?;
+        let events = extract_events(&meta);
+        Ok((result, meta, events))
+    }
 
-        // Poll the transaction status
+    /// Poll the status of a transaction until it's included in a ledger, successfully or not, or
+    /// the wait times out. Polls at least once, so a zero timeout looks the transaction up.
+    pub async fn poll_transaction(&self, hash: &str) -> Result<GetTransactionResponse, Error> {
         let start = Instant::now();
+        let mut interval = self.wait.interval;
         loop {
-            let response = self.get_transaction(&hash).await?;
+            let response = self.get_transaction(hash).await?;
             match response.status.as_str() {
-                "SUCCESS" => {
-                    // TODO: the caller should probably be printing this
-                    tracing::trace!(?response);
-                    let result = TransactionResult::from_xdr_base64(
-                        response.result_xdr.clone().ok_or(Error::MissingResult)?,
-                    )?;
-                    let meta = TransactionMeta::from_xdr_base64(
-                        response
-                            .result_meta_xdr
-                            .clone()
-                            .ok_or(Error::MissingResult)?,
-                    )?;
-                    let events = extract_events(&meta);
-                    return Ok((result, meta, events));
-                }
-                "FAILED" => {
-                    tracing::error!(?response);
-                    // TODO: provide a more elaborate error
-                    return Err(Error::TransactionSubmissionFailed(format!("{response:#?}")));
-                }
+                "SUCCESS" | "FAILED" => return Ok(response),
                 "NOT_FOUND" => (),
                 _ => {
                     return Err(Error::UnexpectedTransactionStatus(response.status));
                 }
             };
-            let duration = start.elapsed();
-            // TODO: parameterize the timeout instead of using a magic constant
-            if duration.as_secs() > 10 {
-                return Err(Error::TransactionSubmissionTimeout);
+            let elapsed = start.elapsed();
+            if elapsed >= self.wait.timeout {
+                return Err(Error::TransactionSubmissionTimeout {
+                    hash: hash.to_string(),
+                    timeout: self.wait.timeout,
+                });
             }
-            sleep(Duration::from_secs(1)).await;
+            tracing::debug!("transaction {hash} not found yet, polling again in {interval:?}");
+            sleep(interval.min(self.wait.timeout - elapsed)).await;
+            interval = (interval * 2).min(self.wait.max_interval);
         }
     }
 
@@ -543,9 +865,15 @@ impl Client {
             .request("simulateTransaction", rpc_params![base64_tx])
             .await?;
         tracing::trace!(?response);
-        match response.error {
+        match &response.error {
             None => Ok(response),
-            Some(e) => Err(Error::TransactionSimulationFailed(e)),
+            Some(e) => match host_error::Report::from_events(&response.events()?) {
+                Some(report) => {
+                    tracing::debug!(error = %e);
+                    Err(Error::TransactionSimulationHostError(Box::new(report)))
+                }
+                None => Err(Error::TransactionSimulationFailed(e.clone())),
+            },
         }
     }
 
@@ -566,17 +894,66 @@ impl Client {
         assemble(tx, &sim_response, log_events)
     }
 
-    pub async fn prepare_and_send_transaction(
+    /// Simulate the transaction, sign the auth entries it needs and then the transaction itself,
+    /// ready to be submitted. `signers` is only called when there is an auth entry to sign.
+    pub async fn prepare_and_sign_transaction(
         &self,
         tx_without_preflight: &Transaction,
-        key: &ed25519_dalek::Keypair,
+        source_key: &dyn Signer,
+        signers: impl FnOnce() -> Result<Vec<Box<dyn Signer>>, signer::Error>,
         network_passphrase: &str,
         log_events: Option<LogEvents>,
-    ) -> Result<(TransactionResult, TransactionMeta, Vec<DiagnosticEvent>), Error> {
+    ) -> Result<TransactionEnvelope, Error> {
         let unsigned_tx = self
             .prepare_transaction(tx_without_preflight, log_events)
             .await?;
-        let tx = utils::sign_transaction(key, &unsigned_tx, network_passphrase)?;
+        // The signatures of auth entries expire relative to the latest ledger, which is only
+        // needed when there is an entry to sign
+        let part_signed_tx = if has_address_auth(&unsigned_tx) {
+            let GetLatestLedgerResponse { sequence, .. } = self.get_latest_ledger().await?;
+            sign_soroban_authorizations(
+                &unsigned_tx,
+                source_key,
+                &signers()?,
+                sequence + AUTH_SIGNATURE_LEDGERS,
+                network_passphrase,
+            )?
+        } else {
+            None
+        };
+        let fee_ready_txn = if let Some(mut part_signed_tx) = part_signed_tx {
+            // re-simulate now that the auth entries carry signatures, as they change the size
+            // of the transaction and so its resources and fees
+            part_signed_tx.fee = tx_without_preflight.fee;
+            self.prepare_transaction(&part_signed_tx, log_events)
+                .await?
+        } else {
+            unsigned_tx
+        };
+        Ok(signer::sign_transaction(
+            source_key,
+            &fee_ready_txn,
+            network_passphrase,
+        )?)
+    }
+
+    pub async fn prepare_and_send_transaction(
+        &self,
+        tx_without_preflight: &Transaction,
+        source_key: &dyn Signer,
+        signers: impl FnOnce() -> Result<Vec<Box<dyn Signer>>, signer::Error>,
+        network_passphrase: &str,
+        log_events: Option<LogEvents>,
+    ) -> Result<SentTransaction, Error> {
+        let tx = self
+            .prepare_and_sign_transaction(
+                tx_without_preflight,
+                source_key,
+                signers,
+                network_passphrase,
+                log_events,
+            )
+            .await?;
         self.send_transaction(&tx).await
     }
 
@@ -608,23 +985,14 @@ impl Client {
     pub async fn get_events(
         &self,
         start: EventStart,
-        event_type: Option<EventType>,
-        contract_ids: &[String],
-        topics: &[String],
+        filters: &[EventFilter],
         limit: Option<usize>,
     ) -> Result<GetEventsResponse, Error> {
-        let mut filters = serde_json::Map::new();
-
-        event_type
-            .and_then(|t| match t {
-                EventType::All => None, // all is the default, so avoid incl. the param
-                EventType::Contract => Some("contract"),
-                EventType::System => Some("system"),
-            })
-            .map(|t| filters.insert("type".to_string(), t.into()));
-
-        filters.insert("topics".to_string(), topics.into());
-        filters.insert("contractIds".to_string(), contract_ids.into());
+        let filters = if filters.is_empty() {
+            vec![EventFilter::default().to_json()]
+        } else {
+            filters.iter().map(EventFilter::to_json).collect()
+        };
 
         let mut pagination = serde_json::Map::new();
         if let Some(limit) = limit {
@@ -638,7 +1006,7 @@ impl Client {
                 pagination.insert("cursor".to_string(), c.into());
             }
         };
-        oparams.insert("filters", vec![filters])?;
+        oparams.insert("filters", filters)?;
         oparams.insert("pagination", pagination)?;
 
         Ok(self.client()?.request("getEvents", oparams).await?)
@@ -735,6 +1103,106 @@ impl Client {
             _ => Err(Error::Xdr(XdrError::Invalid)),
         }
     }
+
+    /// Specs of the contracts with the given IDs, looking up all their instances and then all
+    /// their code in as few requests as the server allows, rather than one contract at a time.
+    /// Contracts that can't be found or whose spec can't be parsed are left out.
+    pub async fn get_remote_contract_specs(
+        &self,
+        contract_ids: &[[u8; 32]],
+    ) -> Result<BTreeMap<[u8; 32], Vec<xdr::ScSpecEntry>>, Error> {
+        let instance_keys = contract_ids
+            .iter()
+            .map(|contract_id| {
+                LedgerKey::ContractData(xdr::LedgerKeyContractData {
+                    contract: xdr::ScAddress::Contract(xdr::Hash(*contract_id)),
+                    key: xdr::ScVal::LedgerKeyContractInstance,
+                    durability: xdr::ContractDataDurability::Persistent,
+                    body_type: xdr::ContractEntryBodyType::DataEntry,
+                })
+            })
+            .collect();
+        let mut specs = BTreeMap::new();
+        // Contracts running each Wasm, so every code entry is fetched once
+        let mut contracts_by_wasm: BTreeMap<xdr::Hash, Vec<[u8; 32]>> = BTreeMap::new();
+        for (key, data) in self.get_existing_ledger_entries(instance_keys).await? {
+            let (
+                LedgerKey::ContractData(xdr::LedgerKeyContractData {
+                    contract: xdr::ScAddress::Contract(xdr::Hash(contract_id)),
+                    ..
+                }),
+                LedgerEntryData::ContractData(xdr::ContractDataEntry {
+                    body:
+                        xdr::ContractDataEntryBody::DataEntry(xdr::ContractDataEntryData {
+                            val: xdr::ScVal::ContractInstance(instance),
+                            ..
+                        }),
+                    ..
+                }),
+            ) = (key, data)
+            else {
+                continue;
+            };
+            match instance.executable {
+                xdr::ContractExecutable::Wasm(hash) => {
+                    contracts_by_wasm.entry(hash).or_default().push(contract_id);
+                }
+                xdr::ContractExecutable::Token => {
+                    let spec = soroban_spec::read::parse_raw(&token::StellarAssetSpec::spec_xdr())?;
+                    specs.insert(contract_id, spec);
+                }
+            }
+        }
+
+        let code_keys = contracts_by_wasm
+            .keys()
+            .map(|hash| {
+                LedgerKey::ContractCode(xdr::LedgerKeyContractCode {
+                    hash: hash.clone(),
+                    body_type: xdr::ContractEntryBodyType::DataEntry,
+                })
+            })
+            .collect();
+        for (key, data) in self.get_existing_ledger_entries(code_keys).await? {
+            let (
+                LedgerKey::ContractCode(xdr::LedgerKeyContractCode { hash, .. }),
+                LedgerEntryData::ContractCode(xdr::ContractCodeEntry {
+                    body: xdr::ContractCodeEntryBody::DataEntry(code),
+                    ..
+                }),
+            ) = (key, data)
+            else {
+                continue;
+            };
+            let code: Vec<u8> = code.into();
+            let Ok(spec) = contract_spec::ContractSpec::new(&code) else {
+                continue;
+            };
+            for contract_id in contracts_by_wasm.remove(&hash).unwrap_or_default() {
+                specs.insert(contract_id, spec.spec.clone());
+            }
+        }
+        Ok(specs)
+    }
+
+    /// Keys and data of the entries among `keys` that exist, looked up in batches of
+    /// [`LEDGER_KEYS_PER_REQUEST`]
+    async fn get_existing_ledger_entries(
+        &self,
+        keys: Vec<LedgerKey>,
+    ) -> Result<Vec<(LedgerKey, LedgerEntryData)>, Error> {
+        let mut entries = vec![];
+        for chunk in keys.chunks(LEDGER_KEYS_PER_REQUEST) {
+            let response = self.get_ledger_entries(chunk.to_vec()).await?;
+            for entry in response.entries.unwrap_or_default() {
+                entries.push((
+                    LedgerKey::from_xdr_base64(&entry.key)?,
+                    LedgerEntryData::from_xdr_base64(&entry.xdr)?,
+                ));
+            }
+        }
+        Ok(entries)
+    }
 }
 
 fn extract_events(tx_meta: &TransactionMeta) -> Vec<DiagnosticEvent> {
@@ -802,6 +1270,37 @@ mod tests {
         assert_eq!(client.base_url, "https://example.com:443/a/b:80/c/");
     }
 
+    #[test]
+    fn test_get_transaction_response_not_found() {
+        let response: GetTransactionResponse = serde_json::from_str(
+            r#"{"status": "NOT_FOUND", "latestLedger": "1234", "oldestLedger": "1000"}"#,
+        )
+        .unwrap();
+        assert_eq!(response.status, "NOT_FOUND");
+        assert_eq!(response.latest_ledger, 1234);
+        assert_eq!(response.ledger, None);
+        assert!(response.result().unwrap().is_none());
+        assert!(response.events().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_transaction_response_included() {
+        let response: GetTransactionResponse = serde_json::from_str(
+            r#"{
+                "status": "SUCCESS",
+                "latestLedger": "1234",
+                "ledger": "1230",
+                "createdAt": "1690000000",
+                "applicationOrder": 1,
+                "feeBump": false
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(response.ledger, Some(1230));
+        assert_eq!(response.created_at, Some(1_690_000_000));
+        assert_eq!(response.application_order, Some(1));
+    }
+
     #[test]
     // Taken from [RPC server
     // tests](https://github.com/stellar/soroban-tools/blob/main/cmd/soroban-rpc/internal/methods/get_events_test.go#L21).
@@ -938,4 +1437,28 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_event_filter_matches() {
+        let xfer = "AAAABQAAAAh0cmFuc2Zlcg==";
+        let number = "AAAAAQB6Mcc=";
+        let event = Event::fixture(
+            0,
+            &"01".repeat(32),
+            vec![xfer.to_string(), number.to_string()],
+            number.to_string(),
+        );
+        let filter = |event_type, contract_ids: &[&str], topics: &[&str]| EventFilter {
+            event_type,
+            contract_ids: contract_ids.iter().map(ToString::to_string).collect(),
+            topics: topics.iter().map(ToString::to_string).collect(),
+        };
+
+        assert!(EventFilter::default().matches(&event));
+        assert!(filter(EventType::Contract, &[&"01".repeat(32)], &[]).matches(&event));
+        assert!(!filter(EventType::System, &[], &[]).matches(&event));
+        assert!(!filter(EventType::All, &[&"02".repeat(32)], &[]).matches(&event));
+        assert!(filter(EventType::All, &[], &[number, &format!("{xfer},*")]).matches(&event));
+        assert!(!filter(EventType::All, &[], &[&format!("{number},*")]).matches(&event));
+    }
 }
@@ -1,9 +1,15 @@
+use sha2::{Digest, Sha256};
 use soroban_env_host::xdr::{
-    DiagnosticEvent, OperationBody, ReadXdr, SorobanAuthorizationEntry, SorobanTransactionData,
-    Transaction, TransactionExt, VecM,
+    AccountId, DiagnosticEvent, Hash, HashIdPreimage, HashIdPreimageSorobanAuthorization,
+    OperationBody, PublicKey, ReadXdr, ScAddress, ScMap, ScMapEntry, ScSymbol, ScVal, ScVec,
+    SorobanAddressCredentials, SorobanAuthorizationEntry, SorobanCredentials,
+    SorobanTransactionData, Transaction, TransactionExt, Uint256, VecM, WriteXdr,
 };
 
-use crate::rpc::{Error, LogEvents, SimulateTransactionResponse};
+use crate::{
+    rpc::{Error, LogEvents, SimulateTransactionResponse},
+    signer::Signer,
+};
 
 // Apply the result of a simulateTransaction onto a transaction envelope, preparing it for
 // submission to the network.
@@ -64,7 +70,9 @@ pub fn assemble(
                     )
                 })
                 .collect::<Result<Vec<_>, _>>()?;
-            if !auths.is_empty() {
+            // Keep any auth entries already on the transaction, e.g. ones that were signed
+            // before re-simulating it, as the simulation only returns unsigned entries.
+            if !auths.is_empty() && body.auth.is_empty() {
                 body.auth = auths[0].clone();
             }
             auths
@@ -84,11 +92,125 @@ pub fn assemble(
     Ok(tx)
 }
 
+// Whether the invocation has `SorobanCredentials::Address` auth entries, which have to be signed
+// separately from the transaction.
+pub fn has_address_auth(tx: &Transaction) -> bool {
+    tx.operations.iter().any(|op| match &op.body {
+        OperationBody::InvokeHostFunction(body) => body
+            .auth
+            .iter()
+            .any(|auth| matches!(auth.credentials, SorobanCredentials::Address(_))),
+        _ => false,
+    })
+}
+
+// Sign all the `SorobanCredentials::Address` auth entries of an invocation with the matching
+// key in `signers`. Entries authorized by the source account are left as they are, since the
+// transaction signature covers them. Returns `None` when there was nothing to sign.
+pub fn sign_soroban_authorizations(
+    raw: &Transaction,
+    source_key: &dyn Signer,
+    signers: &[Box<dyn Signer>],
+    signature_expiration_ledger: u32,
+    network_passphrase: &str,
+) -> Result<Option<Transaction>, Error> {
+    let mut tx = raw.clone();
+    if tx.operations.len() != 1 {
+        return Err(Error::UnexpectedOperationCount {
+            count: tx.operations.len(),
+        });
+    }
+    let mut op = tx.operations[0].clone();
+    let OperationBody::InvokeHostFunction(ref mut body) = op.body else {
+        return Ok(None);
+    };
+
+    let network_id = Hash(Sha256::digest(network_passphrase.as_bytes()).into());
+    let source_address = source_key.public_key()?.to_bytes();
+    let mut signed_auths = Vec::with_capacity(body.auth.len());
+    let mut signed = false;
+    for raw_auth in body.auth.iter() {
+        let mut auth = raw_auth.clone();
+        let SorobanAuthorizationEntry {
+            credentials: SorobanCredentials::Address(ref mut credentials),
+            ..
+        } = auth
+        else {
+            signed_auths.push(auth);
+            continue;
+        };
+        let needle = match &credentials.address {
+            ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(a)))) => a,
+            ScAddress::Contract(Hash(c)) => {
+                // Custom account contracts have their own signature schemes, which the CLI
+                // doesn't support yet.
+                return Err(Error::MissingSignerForAddress {
+                    address: stellar_strkey::Contract(*c).to_string(),
+                });
+            }
+        };
+        let mut matching = None;
+        for s in signers {
+            if needle == s.public_key()?.as_bytes() {
+                matching = Some(s.as_ref());
+                break;
+            }
+        }
+        let signer = if let Some(s) = matching {
+            s
+        } else if needle == &source_address {
+            source_key
+        } else {
+            return Err(Error::MissingSignerForAddress {
+                address: stellar_strkey::ed25519::PublicKey(*needle).to_string(),
+            });
+        };
+
+        credentials.signature_expiration_ledger = signature_expiration_ledger;
+        let preimage = HashIdPreimage::SorobanAuthorization(HashIdPreimageSorobanAuthorization {
+            network_id: network_id.clone(),
+            nonce: credentials.nonce,
+            signature_expiration_ledger,
+            invocation: auth.root_invocation.clone(),
+        })
+        .to_xdr()?;
+        let payload: [u8; 32] = Sha256::digest(preimage).into();
+        let signature = signer.sign(&payload)?;
+
+        // The account contract expects a vector of maps of the form
+        // `{public_key: BytesN<32>, signature: BytesN<64>}`, with the keys sorted
+        let map = ScMap(
+            vec![
+                ScMapEntry {
+                    key: ScVal::Symbol(ScSymbol("public_key".try_into()?)),
+                    val: ScVal::Bytes(signer.public_key()?.to_bytes().to_vec().try_into()?),
+                },
+                ScMapEntry {
+                    key: ScVal::Symbol(ScSymbol("signature".try_into()?)),
+                    val: ScVal::Bytes(signature.to_bytes().to_vec().try_into()?),
+                },
+            ]
+            .try_into()?,
+        );
+        credentials.signature_args = ScVec(vec![ScVal::Map(Some(map))].try_into()?);
+        signed_auths.push(auth);
+        signed = true;
+    }
+    if !signed {
+        return Ok(None);
+    }
+
+    body.auth = signed_auths.try_into()?;
+    tx.operations = vec![op].try_into()?;
+    Ok(Some(tx))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use super::super::{Cost, SimulateHostFunctionResult};
+    use crate::utils;
     use soroban_env_host::xdr::{
         self, AccountId, ChangeTrustAsset, ChangeTrustOp, ExtensionPoint, Hash, HostFunction,
         InvokeHostFunctionOp, LedgerFootprint, Memo, MuxedAccount, Operation, Preconditions,
@@ -99,6 +221,8 @@ mod tests {
     use stellar_strkey::ed25519::PublicKey as Ed25519PublicKey;
 
     const SOURCE: &str = "GBZXN7PIRZGNMHGA7MUUUF4GWPY5AYPV6LY4UV2GL6VJGIQRXFDNMADI";
+    const FEE_PAYER_SECRET: &str = "SBFGFF27Y64ZUGFAIG5AMJGQODZZKV2YQKAVUUN4HNE24XZXD2OEUVUP";
+    const SIGNER_SECRET: &str = "SBGWSG6BTNCKCOB3DIFBGCVMUPQFYPA2G4O34RMTB343OYPXU5DJDVMN";
 
     fn transaction_data() -> SorobanTransactionData {
         SorobanTransactionData {
@@ -300,4 +424,121 @@ mod tests {
             r => panic!("expected UnexpectedSimulateTransactionResultSize error, got: {r:#?}"),
         }
     }
+
+    fn auth_entry(address: [u8; 32]) -> SorobanAuthorizationEntry {
+        SorobanAuthorizationEntry {
+            credentials: xdr::SorobanCredentials::Address(xdr::SorobanAddressCredentials {
+                address: ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(
+                    address,
+                )))),
+                nonce: 1,
+                signature_expiration_ledger: 0,
+                signature_args: ScVec(VecM::default()),
+            }),
+            root_invocation: SorobanAuthorizedInvocation {
+                function: SorobanAuthorizedFunction::ContractFn(
+                    SorobanAuthorizedContractFunction {
+                        contract_address: ScAddress::Contract(Hash([0; 32])),
+                        function_name: ScSymbol("fn".try_into().unwrap()),
+                        args: ScVec(VecM::default()),
+                    },
+                ),
+                sub_invocations: VecM::default(),
+            },
+        }
+    }
+
+    fn transaction_with_auth(auth: SorobanAuthorizationEntry) -> Transaction {
+        let mut txn = single_contract_fn_transaction();
+        let mut op = txn.operations[0].clone();
+        let OperationBody::InvokeHostFunction(ref mut body) = op.body else {
+            unreachable!();
+        };
+        body.auth = vec![auth].try_into().unwrap();
+        txn.operations = vec![op].try_into().unwrap();
+        txn
+    }
+
+    #[test]
+    fn test_sign_soroban_authorizations_signs_address_credentials() {
+        let source = utils::parse_secret_key(FEE_PAYER_SECRET).unwrap();
+        let signer = utils::parse_secret_key(SIGNER_SECRET).unwrap();
+        let txn = transaction_with_auth(auth_entry(signer.public.to_bytes()));
+
+        let Ok(Some(result)) = sign_soroban_authorizations(
+            &txn,
+            &source,
+            &[Box::new(signer)],
+            42,
+            "Test SDF Network ; September 2015",
+        ) else {
+            panic!("signing failed");
+        };
+
+        let OperationBody::InvokeHostFunction(ref op) = result.operations[0].body else {
+            panic!("unexpected operation type: {:#?}", result.operations[0]);
+        };
+        let xdr::SorobanCredentials::Address(xdr::SorobanAddressCredentials {
+            signature_expiration_ledger,
+            signature_args,
+            ..
+        }) = &op.auth[0].credentials else {
+            panic!("unexpected credentials type");
+        };
+        assert_eq!(42, *signature_expiration_ledger);
+        assert_eq!(1, signature_args.len());
+        let ScVal::Map(Some(map)) = &signature_args[0] else {
+            panic!("unexpected signature args: {signature_args:#?}");
+        };
+        assert_eq!(2, map.len());
+    }
+
+    #[test]
+    fn test_sign_soroban_authorizations_errors_without_signer() {
+        let source = utils::parse_secret_key(FEE_PAYER_SECRET).unwrap();
+        let signer = utils::parse_secret_key(SIGNER_SECRET).unwrap();
+        let txn = transaction_with_auth(auth_entry(signer.public.to_bytes()));
+
+        match sign_soroban_authorizations(&txn, &source, &[], 42, "passphrase") {
+            Err(Error::MissingSignerForAddress { .. }) => {}
+            r => panic!("expected MissingSignerForAddress error, got: {r:#?}"),
+        }
+    }
+
+    #[test]
+    fn test_sign_soroban_authorizations_skips_source_account_credentials() {
+        let source = utils::parse_secret_key(FEE_PAYER_SECRET).unwrap();
+        let mut auth = auth_entry([0; 32]);
+        auth.credentials = xdr::SorobanCredentials::SourceAccount;
+        let txn = transaction_with_auth(auth);
+
+        match sign_soroban_authorizations(&txn, &source, &[], 42, "passphrase") {
+            Ok(None) => {}
+            r => panic!("expected nothing to sign, got: {r:#?}"),
+        }
+    }
+
+    #[test]
+    fn test_has_address_auth() {
+        let mut auth = auth_entry([1; 32]);
+        assert!(has_address_auth(&transaction_with_auth(auth.clone())));
+        auth.credentials = xdr::SorobanCredentials::SourceAccount;
+        assert!(!has_address_auth(&transaction_with_auth(auth)));
+        assert!(!has_address_auth(&single_contract_fn_transaction()));
+    }
+
+    #[test]
+    fn test_assemble_transaction_keeps_existing_auth() {
+        let sim = simulation_response();
+        let auth = auth_entry([1; 32]);
+        let txn = transaction_with_auth(auth.clone());
+        let Ok(result) = assemble(&txn, &sim, None) else {
+            panic!("assemble failed");
+        };
+
+        let OperationBody::InvokeHostFunction(ref op) = result.operations[0].body else {
+            panic!("unexpected operation type: {:#?}", result.operations[0]);
+        };
+        assert_eq!(vec![auth], op.auth.to_vec());
+    }
 }
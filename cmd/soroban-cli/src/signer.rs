@@ -0,0 +1,256 @@
+use std::{
+    io::Write,
+    process::{Command, ExitStatus, Stdio},
+};
+
+use ed25519_dalek::Verifier;
+use serde::{Deserialize, Serialize};
+use soroban_env_host::xdr::{
+    DecoratedSignature, Error as XdrError, Signature, SignatureHint, Transaction,
+    TransactionEnvelope, TransactionV1Envelope,
+};
+
+use crate::{
+    commands::config::{locator, secret},
+    utils,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Secret(#[from] secret::Error),
+    #[error(transparent)]
+    Locator(#[from] locator::Error),
+    #[error(transparent)]
+    Ed25519(#[from] ed25519_dalek::SignatureError),
+    #[error(transparent)]
+    StrKey(#[from] stellar_strkey::DecodeError),
+    #[error(transparent)]
+    Xdr(#[from] XdrError),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("invalid signer command: {0:?}")]
+    InvalidCommand(String),
+    #[error("failed to run signer {command:?}: {error}")]
+    Io {
+        command: String,
+        error: std::io::Error,
+    },
+    #[error("signer {command:?} failed with {status}")]
+    Failed { command: String, status: ExitStatus },
+    #[error("invalid response from signer {command:?}: {message}")]
+    InvalidResponse { command: String, message: String },
+    #[error("signer {command:?} returned a signature that doesn't match {public_key}")]
+    InvalidSignature { command: String, public_key: String },
+}
+
+/// Produces ed25519 signatures for an account, without necessarily holding its private key
+pub trait Signer {
+    /// Public key the signatures verify against
+    fn public_key(&self) -> Result<ed25519_dalek::PublicKey, Error>;
+
+    /// Sign a 32 byte hash, either a transaction hash or a Soroban authorization payload
+    fn sign(&self, hash: &[u8; 32]) -> Result<ed25519_dalek::Signature, Error>;
+}
+
+impl Signer for ed25519_dalek::Keypair {
+    fn public_key(&self) -> Result<ed25519_dalek::PublicKey, Error> {
+        Ok(self.public)
+    }
+
+    fn sign(&self, hash: &[u8; 32]) -> Result<ed25519_dalek::Signature, Error> {
+        Ok(ed25519_dalek::Signer::sign(self, hash))
+    }
+}
+
+impl<T: Signer + ?Sized> Signer for Box<T> {
+    fn public_key(&self) -> Result<ed25519_dalek::PublicKey, Error> {
+        self.as_ref().public_key()
+    }
+
+    fn sign(&self, hash: &[u8; 32]) -> Result<ed25519_dalek::Signature, Error> {
+        self.as_ref().sign(hash)
+    }
+}
+
+/// Hands hashes to an external program to sign, e.g. a bridge to an HSM, a KMS or an approval
+/// workflow.
+///
+/// For every signature the program is run once with a JSON request written to its stdin:
+/// `{"public_key": "G…", "hash": "<hex>"}`. It must exit successfully after writing
+/// `{"signature": "<hex>"}` to its stdout. Its stderr is passed through, so it can prompt the user.
+#[derive(Debug, Clone)]
+pub struct External {
+    pub public_key: ed25519_dalek::PublicKey,
+    pub command: String,
+}
+
+#[derive(Serialize)]
+struct Request {
+    public_key: String,
+    hash: String,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    signature: String,
+}
+
+impl External {
+    pub fn new(public_key: &str, command: &str) -> Result<Self, Error> {
+        let public_key = stellar_strkey::ed25519::PublicKey::from_string(public_key)?;
+        Ok(Self {
+            public_key: ed25519_dalek::PublicKey::from_bytes(&public_key.0)?,
+            command: command.to_string(),
+        })
+    }
+
+    fn public_strkey(&self) -> String {
+        stellar_strkey::ed25519::PublicKey(self.public_key.to_bytes()).to_string()
+    }
+
+    fn run(&self, request: &[u8]) -> Result<Vec<u8>, Error> {
+        let io_error = |error| Error::Io {
+            command: self.command.clone(),
+            error,
+        };
+        let args = shlex::split(&self.command)
+            .filter(|args| !args.is_empty())
+            .ok_or_else(|| Error::InvalidCommand(self.command.clone()))?;
+        let mut child = Command::new(&args[0])
+            .args(&args[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(io_error)?;
+        // Dropping stdin once the request is written closes it, so the program sees EOF
+        child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(request)
+            .map_err(io_error)?;
+        let output = child.wait_with_output().map_err(io_error)?;
+        if !output.status.success() {
+            return Err(Error::Failed {
+                command: self.command.clone(),
+                status: output.status,
+            });
+        }
+        Ok(output.stdout)
+    }
+}
+
+impl Signer for External {
+    fn public_key(&self) -> Result<ed25519_dalek::PublicKey, Error> {
+        Ok(self.public_key)
+    }
+
+    fn sign(&self, hash: &[u8; 32]) -> Result<ed25519_dalek::Signature, Error> {
+        let invalid_response = |message: String| Error::InvalidResponse {
+            command: self.command.clone(),
+            message,
+        };
+        let mut request = serde_json::to_vec(&Request {
+            public_key: self.public_strkey(),
+            hash: hex::encode(hash),
+        })?;
+        request.push(b'\n');
+        let stdout = self.run(&request)?;
+        let Response { signature } =
+            serde_json::from_slice(&stdout).map_err(|e| invalid_response(e.to_string()))?;
+        let signature =
+            hex::decode(signature.trim()).map_err(|e| invalid_response(e.to_string()))?;
+        let signature = ed25519_dalek::Signature::from_bytes(&signature)
+            .map_err(|e| invalid_response(e.to_string()))?;
+        self.public_key
+            .verify(hash, &signature)
+            .map_err(|_| Error::InvalidSignature {
+                command: self.command.clone(),
+                public_key: self.public_strkey(),
+            })?;
+        Ok(signature)
+    }
+}
+
+/// # Errors
+///
+/// Might return an error
+pub fn sign_transaction(
+    key: &(impl Signer + ?Sized),
+    tx: &Transaction,
+    network_passphrase: &str,
+) -> Result<TransactionEnvelope, Error> {
+    Ok(TransactionEnvelope::Tx(TransactionV1Envelope {
+        tx: tx.clone(),
+        signatures: vec![decorated_signature(key, tx, network_passphrase)?].try_into()?,
+    }))
+}
+
+/// Signature of `tx` by `key`, with the hint the network uses to match it to a signer
+pub fn decorated_signature(
+    key: &(impl Signer + ?Sized),
+    tx: &Transaction,
+    network_passphrase: &str,
+) -> Result<DecoratedSignature, Error> {
+    let tx_hash = utils::transaction_hash(tx, network_passphrase)?;
+    let tx_signature = key.sign(&tx_hash)?;
+    Ok(DecoratedSignature {
+        hint: SignatureHint(
+            key.public_key()?.to_bytes()[28..]
+                .try_into()
+                .map_err(XdrError::from)?,
+        ),
+        signature: Signature(tx_signature.to_bytes().try_into()?),
+    })
+}
+
+#[cfg(test)]
+#[cfg(unix)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "SBFGFF27Y64ZUGFAIG5AMJGQODZZKV2YQKAVUUN4HNE24XZXD2OEUVUP";
+
+    fn stub(response: &str) -> (ed25519_dalek::Keypair, External) {
+        let key = utils::parse_secret_key(SECRET).unwrap();
+        let public_key = stellar_strkey::ed25519::PublicKey(key.public.to_bytes()).to_string();
+        let command = format!("sh -c 'cat > /dev/null; echo \"$0\"' '{response}'");
+        (key, External::new(&public_key, &command).unwrap())
+    }
+
+    #[test]
+    fn test_external_signer() {
+        let hash = [1; 32];
+        let key = utils::parse_secret_key(SECRET).unwrap();
+        let expected = ed25519_dalek::Signer::sign(&key, &hash);
+        let (key, signer) = stub(&format!(
+            r#"{{"signature": "{}"}}"#,
+            hex::encode(expected.to_bytes())
+        ));
+        assert_eq!(signer.public_key().unwrap(), key.public);
+        assert_eq!(signer.sign(&hash).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_external_signer_rejects_wrong_signature() {
+        let key = utils::parse_secret_key(SECRET).unwrap();
+        let signature = ed25519_dalek::Signer::sign(&key, &[2; 32]);
+        let (_, signer) = stub(&format!(
+            r#"{{"signature": "{}"}}"#,
+            hex::encode(signature.to_bytes())
+        ));
+        assert!(matches!(
+            signer.sign(&[1; 32]),
+            Err(Error::InvalidSignature { .. })
+        ));
+    }
+
+    #[test]
+    fn test_external_signer_failure() {
+        let (_, mut signer) = stub("");
+        signer.command = "sh -c 'exit 1'".to_string();
+        assert!(matches!(signer.sign(&[1; 32]), Err(Error::Failed { .. })));
+    }
+}
@@ -1,6 +1,5 @@
 use std::{collections::HashMap, io::ErrorKind, path::Path};
 
-use ed25519_dalek::Signer;
 use sha2::{Digest, Sha256};
 
 use soroban_env_host::{
@@ -10,13 +9,12 @@ use soroban_env_host::{
     xdr::{
         AccountEntry, AccountEntryExt, AccountId, Asset, ContractCodeEntry, ContractCodeEntryBody,
         ContractDataDurability, ContractDataEntry, ContractDataEntryBody, ContractDataEntryData,
-        ContractEntryBodyType, ContractExecutable, ContractIdPreimage, DecoratedSignature,
-        Error as XdrError, ExtensionPoint, Hash, HashIdPreimage, HashIdPreimageContractId,
-        LedgerEntry, LedgerEntryData, LedgerEntryExt, LedgerFootprint, LedgerKey,
-        LedgerKeyContractCode, LedgerKeyContractData, ScAddress, ScContractInstance, ScSpecEntry,
-        ScVal, SequenceNumber, Signature, SignatureHint, String32, Thresholds, Transaction,
-        TransactionEnvelope, TransactionSignaturePayload,
-        TransactionSignaturePayloadTaggedTransaction, TransactionV1Envelope, VecM, WriteXdr,
+        ContractEntryBodyType, ContractExecutable, ContractIdPreimage, Error as XdrError,
+        ExtensionPoint, Hash, HashIdPreimage, HashIdPreimageContractId, LedgerEntry,
+        LedgerEntryData, LedgerEntryExt, LedgerFootprint, LedgerKey, LedgerKeyContractCode,
+        LedgerKeyContractData, ScAddress, ScContractInstance, ScSpecEntry, ScSpecTypeDef, ScVal,
+        SequenceNumber, String32, Thresholds, Transaction, TransactionSignaturePayload,
+        TransactionSignaturePayloadTaggedTransaction, VecM, WriteXdr,
     },
 };
 use soroban_ledger_snapshot::LedgerSnapshot;
@@ -26,7 +24,9 @@ use stellar_strkey::ed25519::PrivateKey;
 
 use crate::network::sandbox_network_id;
 
+pub mod archival;
 pub mod contract_spec;
+pub mod host_error;
 
 /// # Errors
 ///
@@ -60,27 +60,61 @@ pub fn ledger_snapshot_read_or_default(
     }
 }
 
+/// Key of the ledger entry holding the Wasm with the given hash
+pub fn contract_code_key(hash: Hash) -> LedgerKey {
+    LedgerKey::ContractCode(LedgerKeyContractCode {
+        hash,
+        body_type: ContractEntryBodyType::DataEntry,
+    })
+}
+
+/// Key of the code of a contract instance running a Wasm, `None` for any other entry
+pub fn instance_code_key(instance: &LedgerEntry) -> Option<LedgerKey> {
+    match &instance.data {
+        LedgerEntryData::ContractData(ContractDataEntry {
+            body:
+                ContractDataEntryBody::DataEntry(ContractDataEntryData {
+                    val:
+                        ScVal::ContractInstance(ScContractInstance {
+                            executable: ContractExecutable::Wasm(hash),
+                            ..
+                        }),
+                    ..
+                }),
+            ..
+        }) => Some(contract_code_key(hash.clone())),
+        _ => None,
+    }
+}
+
+/// Key of the ledger entry holding the instance of a contract
+pub fn contract_instance_key(contract_id: [u8; 32]) -> LedgerKey {
+    LedgerKey::ContractData(LedgerKeyContractData {
+        contract: ScAddress::Contract(contract_id.into()),
+        key: ScVal::LedgerKeyContractInstance,
+        durability: ContractDataDurability::Persistent,
+        body_type: ContractEntryBodyType::DataEntry,
+    })
+}
+
 /// # Errors
 ///
 /// Might return an error
 pub fn add_contract_code_to_ledger_entries(
     entries: &mut Vec<(Box<LedgerKey>, Box<LedgerEntry>)>,
     contract: Vec<u8>,
-    min_persistent_entry_expiration: u32,
+    expiration_ledger_seq: u32,
 ) -> Result<Hash, XdrError> {
     // Install the code
     let hash = contract_hash(contract.as_slice())?;
-    let code_key = LedgerKey::ContractCode(LedgerKeyContractCode {
-        hash: hash.clone(),
-        body_type: ContractEntryBodyType::DataEntry,
-    });
+    let code_key = contract_code_key(hash.clone());
     let code_entry = LedgerEntry {
         last_modified_ledger_seq: 0,
         data: LedgerEntryData::ContractCode(ContractCodeEntry {
             ext: ExtensionPoint::V0,
             hash: hash.clone(),
             body: ContractCodeEntryBody::DataEntry(contract.try_into()?),
-            expiration_ledger_seq: min_persistent_entry_expiration,
+            expiration_ledger_seq,
         }),
         ext: LedgerEntryExt::V0,
     };
@@ -98,15 +132,10 @@ pub fn add_contract_to_ledger_entries(
     entries: &mut Vec<(Box<LedgerKey>, Box<LedgerEntry>)>,
     contract_id: [u8; 32],
     wasm_hash: [u8; 32],
-    min_persistent_entry_expiration: u32,
+    expiration_ledger_seq: u32,
 ) {
     // Create the contract
-    let contract_key = LedgerKey::ContractData(LedgerKeyContractData {
-        contract: ScAddress::Contract(contract_id.into()),
-        key: ScVal::LedgerKeyContractInstance,
-        durability: ContractDataDurability::Persistent,
-        body_type: ContractEntryBodyType::DataEntry,
-    });
+    let contract_key = contract_instance_key(contract_id);
 
     let contract_entry = LedgerEntry {
         last_modified_ledger_seq: 0,
@@ -121,7 +150,7 @@ pub fn add_contract_to_ledger_entries(
                     storage: None,
                 }),
             }),
-            expiration_ledger_seq: min_persistent_entry_expiration,
+            expiration_ledger_seq,
         }),
         ext: LedgerEntryExt::V0,
     };
@@ -145,8 +174,16 @@ pub fn bump_ledger_entry_expirations(
         .collect::<HashMap<_, _>>();
     for (k, e) in entries.iter_mut() {
         if let Some(min_expiration) = lookup.get(k.as_ref()) {
-            if let LedgerEntryData::ContractData(entry) = &mut e.data {
-                entry.expiration_ledger_seq = *min_expiration;
+            match &mut e.data {
+                LedgerEntryData::ContractData(ContractDataEntry {
+                    expiration_ledger_seq,
+                    ..
+                })
+                | LedgerEntryData::ContractCode(ContractCodeEntry {
+                    expiration_ledger_seq,
+                    ..
+                }) => *expiration_ledger_seq = *min_expiration,
+                _ => (),
             }
         }
     }
@@ -163,28 +200,6 @@ pub fn transaction_hash(tx: &Transaction, network_passphrase: &str) -> Result<[u
     Ok(Sha256::digest(signature_payload.to_xdr()?).into())
 }
 
-/// # Errors
-///
-/// Might return an error
-pub fn sign_transaction(
-    key: &ed25519_dalek::Keypair,
-    tx: &Transaction,
-    network_passphrase: &str,
-) -> Result<TransactionEnvelope, XdrError> {
-    let tx_hash = transaction_hash(tx, network_passphrase)?;
-    let tx_signature = key.sign(&tx_hash);
-
-    let decorated_signature = DecoratedSignature {
-        hint: SignatureHint(key.public.to_bytes()[28..].try_into()?),
-        signature: Signature(tx_signature.to_bytes().try_into()?),
-    };
-
-    Ok(TransactionEnvelope::Tx(TransactionV1Envelope {
-        tx: tx.clone(),
-        signatures: vec![decorated_signature].try_into()?,
-    }))
-}
-
 /// # Errors
 ///
 /// Might return an error
@@ -277,6 +292,59 @@ pub fn get_contract_spec_from_storage(
     }
 }
 
+/// Spec of a contract deployed in the sandbox ledger `state`, whether or not its entries have
+/// expired
+///
+/// # Errors
+///
+/// Might return an error
+pub fn get_contract_spec_from_state(
+    state: &LedgerSnapshot,
+    contract_id: [u8; 32],
+) -> Result<Vec<ScSpecEntry>, FromWasmError> {
+    let entry = |key: LedgerKey| {
+        state
+            .ledger_entries
+            .iter()
+            .find(|(k, _)| **k == key)
+            .map(|(_, e)| &e.data)
+    };
+    let instance_key = LedgerKey::ContractData(LedgerKeyContractData {
+        contract: ScAddress::Contract(contract_id.into()),
+        key: ScVal::LedgerKeyContractInstance,
+        body_type: ContractEntryBodyType::DataEntry,
+        durability: ContractDataDurability::Persistent,
+    });
+    match entry(instance_key) {
+        Some(LedgerEntryData::ContractData(ContractDataEntry {
+            body:
+                ContractDataEntryBody::DataEntry(ContractDataEntryData {
+                    val: ScVal::ContractInstance(ScContractInstance { executable, .. }),
+                    ..
+                }),
+            ..
+        })) => match executable {
+            ContractExecutable::Token => {
+                soroban_spec::read::parse_raw(&token::StellarAssetSpec::spec_xdr())
+                    .map_err(FromWasmError::Parse)
+            }
+            ContractExecutable::Wasm(hash) => {
+                match entry(LedgerKey::ContractCode(LedgerKeyContractCode {
+                    hash: hash.clone(),
+                    body_type: ContractEntryBodyType::DataEntry,
+                })) {
+                    Some(LedgerEntryData::ContractCode(ContractCodeEntry {
+                        body: ContractCodeEntryBody::DataEntry(code),
+                        ..
+                    })) => soroban_spec::read::from_wasm(code.as_vec()),
+                    _ => Err(FromWasmError::NotFound),
+                }
+            }
+        },
+        _ => Err(FromWasmError::NotFound),
+    }
+}
+
 /// # Panics
 ///
 /// May panic
@@ -356,6 +424,32 @@ pub(crate) fn parse_secret_key(
     into_key_pair(&PrivateKey::from_string(s).unwrap())
 }
 
+/// Primitive types that values given on the command line can be parsed as, by name, e.g. in
+/// `<type>:<value>` event topic filter segments or with `--key-type`
+pub const PRIMITIVE_TYPES: &[(&str, ScSpecTypeDef)] = &[
+    ("sym", ScSpecTypeDef::Symbol),
+    ("str", ScSpecTypeDef::String),
+    ("addr", ScSpecTypeDef::Address),
+    ("bool", ScSpecTypeDef::Bool),
+    ("u32", ScSpecTypeDef::U32),
+    ("i32", ScSpecTypeDef::I32),
+    ("u64", ScSpecTypeDef::U64),
+    ("i64", ScSpecTypeDef::I64),
+    ("u128", ScSpecTypeDef::U128),
+    ("i128", ScSpecTypeDef::I128),
+    ("u256", ScSpecTypeDef::U256),
+    ("i256", ScSpecTypeDef::I256),
+    ("bytes", ScSpecTypeDef::Bytes),
+];
+
+pub fn primitive_type_names() -> String {
+    PRIMITIVE_TYPES
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 pub fn is_hex_string(s: &str) -> bool {
     s.chars().all(|s| s.is_ascii_hexdigit())
 }
@@ -0,0 +1,256 @@
+use std::{cell::RefCell, rc::Rc};
+
+use soroban_env_host::{
+    storage::SnapshotSource,
+    xdr::{
+        ContractCodeEntry, ContractDataDurability, ContractDataEntry, LedgerEntry, LedgerEntryData,
+        LedgerKey, LedgerKeyContractCode, LedgerKeyContractData, ScAddress, ScErrorCode,
+        ScErrorType,
+    },
+    HostError,
+};
+use soroban_ledger_snapshot::LedgerSnapshot;
+
+/// Ledger the entry expires after, for the entry types that expire
+pub fn expiration_ledger_seq(entry: &LedgerEntry) -> Option<u32> {
+    match &entry.data {
+        LedgerEntryData::ContractData(ContractDataEntry {
+            expiration_ledger_seq,
+            ..
+        })
+        | LedgerEntryData::ContractCode(ContractCodeEntry {
+            expiration_ledger_seq,
+            ..
+        }) => Some(*expiration_ledger_seq),
+        _ => None,
+    }
+}
+
+/// Whether the entry has expired by ledger `sequence`. The network archives expired persistent
+/// entries and contract code, which have to be restored before they can be used again, and
+/// deletes expired temporary entries.
+pub fn is_expired(entry: &LedgerEntry, sequence: u32) -> bool {
+    expiration_ledger_seq(entry).is_some_and(|expiration| expiration < sequence)
+}
+
+pub fn is_temporary(entry: &LedgerEntry) -> bool {
+    matches!(
+        &entry.data,
+        LedgerEntryData::ContractData(ContractDataEntry {
+            durability: ContractDataDurability::Temporary,
+            ..
+        })
+    )
+}
+
+/// Whether the entry has been archived by ledger `sequence`
+pub fn is_archived(entry: &LedgerEntry, sequence: u32) -> bool {
+    is_expired(entry, sequence) && !is_temporary(entry)
+}
+
+pub fn set_expiration_ledger_seq(entry: &mut LedgerEntry, seq: u32) {
+    match &mut entry.data {
+        LedgerEntryData::ContractData(ContractDataEntry {
+            expiration_ledger_seq,
+            ..
+        })
+        | LedgerEntryData::ContractCode(ContractCodeEntry {
+            expiration_ledger_seq,
+            ..
+        }) => *expiration_ledger_seq = seq,
+        _ => (),
+    }
+}
+
+/// Ledger the next invocation in the sandbox will happen in
+pub fn next_ledger(state: &LedgerSnapshot) -> u32 {
+    state.sequence_number.saturating_add(1)
+}
+
+/// Delete the temporary entries that have expired by ledger `sequence`, like the network does
+pub fn evict_expired_temporary(
+    entries: &mut Vec<(Box<LedgerKey>, Box<LedgerEntry>)>,
+    sequence: u32,
+) {
+    entries.retain(|(_, entry)| !(is_temporary(entry) && is_expired(entry, sequence)));
+}
+
+/// Expiration ledger of a persistent entry created in the current ledger of `state`
+pub fn new_persistent_expiration(state: &LedgerSnapshot) -> u32 {
+    state
+        .sequence_number
+        .saturating_add(state.min_persistent_entry_expiration)
+}
+
+pub fn key_to_string(key: &LedgerKey) -> String {
+    match key {
+        LedgerKey::ContractData(LedgerKeyContractData {
+            contract,
+            key,
+            durability,
+            ..
+        }) => {
+            let contract = match contract {
+                ScAddress::Contract(hash) => stellar_strkey::Contract(hash.0).to_string(),
+                ScAddress::Account(_) => format!("{contract:?}"),
+            };
+            let key = soroban_spec_tools::to_string(key).unwrap_or_else(|_| format!("{key:?}"));
+            format!("{} entry {key} of contract {contract}", durability.name())
+        }
+        LedgerKey::ContractCode(LedgerKeyContractCode { hash, .. }) => {
+            format!("contract code {}", hex::encode(hash.0))
+        }
+        _ => format!("{key:?}"),
+    }
+}
+
+/// Ledger state as of ledger `sequence`, as seen by contracts. Archived entries can't be accessed
+/// and expired temporary entries are missing. The archived entries contracts tried to access are
+/// recorded so the failure can be explained.
+pub struct ArchivalSnapshot {
+    snapshot: LedgerSnapshot,
+    sequence: u32,
+    archived_accesses: RefCell<Vec<LedgerKey>>,
+}
+
+impl ArchivalSnapshot {
+    pub fn new(snapshot: LedgerSnapshot, sequence: u32) -> Self {
+        Self {
+            snapshot,
+            sequence,
+            archived_accesses: RefCell::default(),
+        }
+    }
+
+    /// First archived entry a contract tried to access, if any
+    pub fn archived_access(&self) -> Option<LedgerKey> {
+        self.archived_accesses.borrow().first().cloned()
+    }
+
+    fn entry(&self, key: &Rc<LedgerKey>) -> Result<Option<Rc<LedgerEntry>>, HostError> {
+        if !self.snapshot.has(key)? {
+            return Ok(None);
+        }
+        let entry = self.snapshot.get(key)?;
+        if is_archived(&entry, self.sequence) {
+            self.archived_accesses
+                .borrow_mut()
+                .push(key.as_ref().clone());
+            return Err(missing_value());
+        }
+        Ok((!is_expired(&entry, self.sequence)).then_some(entry))
+    }
+}
+
+fn missing_value() -> HostError {
+    soroban_env_host::Error::from_type_and_code(ScErrorType::Storage, ScErrorCode::MissingValue)
+        .into()
+}
+
+impl SnapshotSource for ArchivalSnapshot {
+    fn get(&self, key: &Rc<LedgerKey>) -> Result<Rc<LedgerEntry>, HostError> {
+        self.entry(key)?.ok_or_else(missing_value)
+    }
+
+    fn has(&self, key: &Rc<LedgerKey>) -> Result<bool, HostError> {
+        Ok(self.entry(key)?.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use soroban_env_host::xdr::{
+        ContractDataEntryBody, ContractDataEntryData, ContractEntryBodyType, Hash, LedgerEntryExt,
+        ScVal,
+    };
+
+    use super::*;
+
+    fn entry(
+        key: u32,
+        durability: ContractDataDurability,
+        expiration_ledger_seq: u32,
+    ) -> (Box<LedgerKey>, Box<LedgerEntry>) {
+        let contract = ScAddress::Contract(Hash([0; 32]));
+        (
+            Box::new(LedgerKey::ContractData(LedgerKeyContractData {
+                contract: contract.clone(),
+                key: ScVal::U32(key),
+                durability,
+                body_type: ContractEntryBodyType::DataEntry,
+            })),
+            Box::new(LedgerEntry {
+                last_modified_ledger_seq: 0,
+                data: LedgerEntryData::ContractData(ContractDataEntry {
+                    contract,
+                    key: ScVal::U32(key),
+                    durability,
+                    body: ContractDataEntryBody::DataEntry(ContractDataEntryData {
+                        flags: 0,
+                        val: ScVal::Void,
+                    }),
+                    expiration_ledger_seq,
+                }),
+                ext: LedgerEntryExt::V0,
+            }),
+        )
+    }
+
+    #[test]
+    fn test_evict_expired_temporary() {
+        let mut entries = vec![
+            entry(1, ContractDataDurability::Temporary, 9),
+            entry(2, ContractDataDurability::Temporary, 10),
+            entry(3, ContractDataDurability::Persistent, 9),
+        ];
+        evict_expired_temporary(&mut entries, 10);
+        let keys = entries
+            .iter()
+            .map(|(k, _)| match k.as_ref() {
+                LedgerKey::ContractData(LedgerKeyContractData {
+                    key: ScVal::U32(key),
+                    ..
+                }) => *key,
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_archival_snapshot() {
+        let entries = vec![
+            entry(1, ContractDataDurability::Persistent, 20),
+            entry(2, ContractDataDurability::Persistent, 9),
+            entry(3, ContractDataDurability::Temporary, 9),
+        ];
+        let snapshot = ArchivalSnapshot::new(
+            LedgerSnapshot {
+                ledger_entries: entries.clone(),
+                ..Default::default()
+            },
+            10,
+        );
+        let key = |i: usize| Rc::new(entries[i].0.as_ref().clone());
+
+        assert!(snapshot.get(&key(0)).is_ok());
+        assert!(!snapshot.has(&key(2)).unwrap());
+        assert!(snapshot.get(&key(2)).is_err());
+        assert_eq!(snapshot.archived_access(), None);
+
+        assert!(snapshot.get(&key(1)).is_err());
+        assert_eq!(snapshot.archived_access(), Some(key(1).as_ref().clone()));
+    }
+
+    #[test]
+    fn test_key_to_string() {
+        let (key, _) = entry(1, ContractDataDurability::Persistent, 0);
+        assert_eq!(
+            key_to_string(&key),
+            format!(
+                "Persistent entry 1 of contract {}",
+                stellar_strkey::Contract([0; 32])
+            )
+        );
+    }
+}
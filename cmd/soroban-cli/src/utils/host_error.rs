@@ -0,0 +1,279 @@
+use std::fmt::{Display, Formatter};
+
+use soroban_env_host::{
+    events::HostEvent,
+    xdr::{ContractEventBody, ContractEventType, ScError, ScErrorCode, ScErrorType, ScVal, ScVec},
+    HostError,
+};
+use soroban_spec_tools::Spec;
+
+/// Human readable account of why a contract invocation failed, built from the host error and the
+/// diagnostic events emitted up to the failure. Used in place of the `Debug` output of
+/// [`HostError`], both for invocations in the sandbox and for ones simulated or applied by the
+/// network.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub type_: ScErrorType,
+    pub code: u32,
+    /// Name and doc of the contract error, once found in the contract spec
+    pub contract_error: Option<(String, String)>,
+    /// Contract calls in progress when the error happened, outermost first
+    pub stack: Vec<Frame>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub contract_id: [u8; 32],
+    pub function: String,
+    pub args: Vec<ScVal>,
+    /// Arguments as JSON, once decoded with the contract spec
+    pub decoded_args: Option<Vec<serde_json::Value>>,
+}
+
+impl Report {
+    pub fn new(type_: ScErrorType, code: u32) -> Self {
+        Self {
+            type_,
+            code,
+            contract_error: None,
+            stack: Vec::new(),
+        }
+    }
+
+    pub fn from_host_error(error: &HostError) -> Self {
+        let type_ = ScErrorType::VARIANTS
+            .iter()
+            .copied()
+            .find(|type_| error.error.is_type(*type_))
+            .unwrap_or(ScErrorType::Context);
+        Self::new(type_, error.error.get_code())
+    }
+
+    /// Report for the error recorded in the diagnostic events of a failed invocation, if any,
+    /// e.g. the events returned by a failed simulation
+    pub fn from_events(events: &[HostEvent]) -> Option<Self> {
+        let error = events
+            .iter()
+            .rev()
+            .filter(|e| e.failed_call)
+            .find_map(|e| match &e.event.body {
+                ContractEventBody::V0(v0) => match v0.topics.as_slice() {
+                    [ScVal::Symbol(topic), ScVal::Error(error), ..]
+                        if topic.as_slice() == b"error" =>
+                    {
+                        Some(error)
+                    }
+                    _ => None,
+                },
+            })?;
+        Some(Self::from_sc_error(error).with_events(events))
+    }
+
+    pub fn from_sc_error(ScError { type_, code }: &ScError) -> Self {
+        Self::new(*type_, u32::try_from(i32::from(*code)).unwrap_or_default())
+    }
+
+    /// Rebuild the call stack at the time of the error from the `fn_call` and `fn_return`
+    /// diagnostic events
+    #[must_use]
+    pub fn with_events(mut self, events: &[HostEvent]) -> Self {
+        let mut stack = Vec::new();
+        for event in events {
+            if event.event.type_ != ContractEventType::Diagnostic {
+                continue;
+            }
+            let ContractEventBody::V0(v0) = &event.event.body;
+            match v0.topics.as_slice() {
+                [ScVal::Symbol(topic), ScVal::Bytes(contract_id), ScVal::Symbol(function), ..]
+                    if topic.as_slice() == b"fn_call" =>
+                {
+                    let Ok(contract_id) = contract_id.as_slice().try_into() else {
+                        continue;
+                    };
+                    let args = match &v0.data {
+                        ScVal::Vec(Some(ScVec(args))) => args.to_vec(),
+                        ScVal::Void => Vec::new(),
+                        arg => vec![arg.clone()],
+                    };
+                    stack.push(Frame {
+                        contract_id,
+                        function: function.to_string_lossy(),
+                        args,
+                        decoded_args: None,
+                    });
+                }
+                [ScVal::Symbol(topic), ..] if topic.as_slice() == b"fn_return" => {
+                    stack.pop();
+                }
+                _ => (),
+            }
+        }
+        self.stack = stack;
+        self
+    }
+
+    /// Decode the contract error and the arguments of the calls to `contract_id` with its spec
+    pub fn decode(&mut self, contract_id: &[u8; 32], spec: &Spec) {
+        if self.type_ == ScErrorType::Contract {
+            if let Ok(error) = spec.find_error_type(self.code) {
+                self.contract_error =
+                    Some((error.name.to_string_lossy(), error.doc.to_string_lossy()));
+            }
+        }
+        for frame in self
+            .stack
+            .iter_mut()
+            .filter(|frame| &frame.contract_id == contract_id)
+        {
+            let Ok(function) = spec.find_function(&frame.function) else {
+                continue;
+            };
+            frame.decoded_args = function
+                .inputs
+                .iter()
+                .zip(&frame.args)
+                .map(|(input, arg)| spec.xdr_to_json(arg, &input.type_))
+                .collect::<Result<Vec<_>, _>>()
+                .ok();
+        }
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (&self.type_, &self.contract_error) {
+            (ScErrorType::Contract, Some((name, doc))) if doc.is_empty() => {
+                write!(f, "contract error #{}: {name}", self.code)?;
+            }
+            (ScErrorType::Contract, Some((name, doc))) => {
+                write!(f, "contract error #{}: {name}: {doc}", self.code)?;
+            }
+            (ScErrorType::Contract, None) => write!(f, "contract error #{}", self.code)?,
+            (type_, _) => {
+                let code = i32::try_from(self.code)
+                    .ok()
+                    .and_then(|code| ScErrorCode::try_from(code).ok());
+                match code {
+                    Some(code) => write!(f, "host error: {} {}", type_.name(), code.name())?,
+                    None => write!(f, "host error: {} #{}", type_.name(), self.code)?,
+                }
+            }
+        }
+        if !self.stack.is_empty() {
+            write!(f, "\ncall stack (outermost first):")?;
+            for frame in &self.stack {
+                write!(f, "\n  {frame}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for Frame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let args: Vec<String> = if let Some(decoded_args) = &self.decoded_args {
+            decoded_args.iter().map(ToString::to_string).collect()
+        } else {
+            self.args
+                .iter()
+                .map(|arg| {
+                    soroban_spec_tools::to_string(arg).unwrap_or_else(|_| format!("{arg:?}"))
+                })
+                .collect()
+        };
+        write!(
+            f,
+            "{} {}({})",
+            stellar_strkey::Contract(self.contract_id),
+            self.function,
+            args.join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use soroban_env_host::xdr::{
+        ContractEvent, ContractEventV0, ExtensionPoint, Hash, ScBytes, ScSymbol,
+    };
+
+    use super::*;
+
+    fn diagnostic_event(topics: Vec<ScVal>, data: ScVal) -> HostEvent {
+        HostEvent {
+            event: ContractEvent {
+                ext: ExtensionPoint::V0,
+                contract_id: Some(Hash([0; 32])),
+                type_: ContractEventType::Diagnostic,
+                body: ContractEventBody::V0(ContractEventV0 {
+                    topics: ScVec(topics.try_into().unwrap()),
+                    data,
+                }),
+            },
+            failed_call: true,
+        }
+    }
+
+    fn symbol(s: &str) -> ScVal {
+        ScVal::Symbol(ScSymbol(s.try_into().unwrap()))
+    }
+
+    fn fn_call(contract_id: u8, function: &str, args: Vec<ScVal>) -> HostEvent {
+        diagnostic_event(
+            vec![
+                symbol("fn_call"),
+                ScVal::Bytes(ScBytes(vec![contract_id; 32].try_into().unwrap())),
+                symbol(function),
+            ],
+            ScVal::Vec(Some(ScVec(args.try_into().unwrap()))),
+        )
+    }
+
+    fn fn_return(function: &str) -> HostEvent {
+        diagnostic_event(vec![symbol("fn_return"), symbol(function)], ScVal::Void)
+    }
+
+    #[test]
+    fn test_call_stack() {
+        let events = vec![
+            fn_call(1, "swap", vec![ScVal::U32(1), ScVal::U32(2)]),
+            fn_call(2, "balance", vec![]),
+            fn_return("balance"),
+            fn_call(2, "transfer", vec![ScVal::U32(3)]),
+        ];
+        let report = Report::new(ScErrorType::Contract, 4).with_events(&events);
+        let stack = report
+            .stack
+            .iter()
+            .map(|frame| {
+                (
+                    frame.contract_id[0],
+                    frame.function.as_str(),
+                    frame.args.len(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(stack, vec![(1, "swap", 2), (2, "transfer", 1)]);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            Report::new(ScErrorType::Storage, 3).to_string(),
+            "host error: Storage MissingValue"
+        );
+        let mut report = Report::new(ScErrorType::Contract, 4).with_events(&[fn_call(
+            1,
+            "transfer",
+            vec![ScVal::U32(3)],
+        )]);
+        report.contract_error = Some(("NotAllowed".to_string(), String::new()));
+        assert_eq!(
+            report.to_string(),
+            format!(
+                "contract error #4: NotAllowed\ncall stack (outermost first):\n  {} transfer(3)",
+                stellar_strkey::Contract([1; 32])
+            )
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use clap::arg;
+
+use crate::{commands::HEADING_RPC, rpc};
+
+#[derive(Debug, clap::Args, Clone)]
+#[group(skip)]
+pub struct Args {
+    /// Seconds to wait for a submitted transaction to be included in a ledger
+    #[arg(long, default_value = "30", env = "SOROBAN_TX_TIMEOUT", help_heading = HEADING_RPC)]
+    pub timeout: u64,
+    /// Seconds to wait between the first polls for the status of a submitted transaction. The interval doubles after every poll, up to `--max-poll-interval`
+    #[arg(
+        long,
+        default_value = "1",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help_heading = HEADING_RPC
+    )]
+    pub poll_interval: u64,
+    /// Maximum number of seconds to wait between polls for the status of a submitted transaction
+    #[arg(
+        long,
+        default_value = "5",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help_heading = HEADING_RPC
+    )]
+    pub max_poll_interval: u64,
+}
+
+impl Args {
+    pub fn wait(&self) -> Result<rpc::Wait, rpc::Error> {
+        if self.poll_interval > self.max_poll_interval {
+            return Err(rpc::Error::PollIntervalAboveMax {
+                interval: self.poll_interval,
+                max: self.max_poll_interval,
+            });
+        }
+        Ok(rpc::Wait {
+            timeout: Duration::from_secs(self.timeout),
+            interval: Duration::from_secs(self.poll_interval),
+            max_interval: Duration::from_secs(self.max_poll_interval),
+        })
+    }
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            timeout: 30,
+            poll_interval: 1,
+            max_poll_interval: 5,
+        }
+    }
+}